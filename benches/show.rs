@@ -0,0 +1,48 @@
+//! Compares the cost of `show` with and without validation.
+//!
+//! Run with `cargo bench`. This shows real toasts, so it needs an interactive session.
+
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use winrt_toast_reborn::content::image::ImagePlacement;
+use winrt_toast_reborn::{Image, Result, Toast, ToastManager};
+
+const ITERATIONS: u32 = 50;
+
+fn bench(name: &str, mut f: impl FnMut() -> Result<()>) -> Result<()> {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        f()?;
+    }
+    let per_call = start.elapsed() / ITERATIONS;
+    println!("{:<16} {:>10.3?} per call", name, per_call);
+    Ok(())
+}
+
+fn main() -> Result<()> {
+    let manager = ToastManager::new(ToastManager::POWERSHELL_AUM_ID);
+
+    let mut toast = Toast::new();
+    toast
+        .tag("bench")
+        .group("bench")
+        .text1("Benchmark")
+        .text2("Measuring the cost of validation")
+        .expires_in(Duration::from_secs(5))
+        .image(
+            1,
+            Image::new_local(
+                Path::new(env!("CARGO_MANIFEST_DIR")).join("resources/test/flower.jpeg"),
+            )?
+            .with_placement(ImagePlacement::Hero),
+        );
+
+    bench("validate", || manager.validate(&toast))?;
+    bench("show_unchecked", || {
+        manager.show_unchecked(&toast).map(|_| ())
+    })?;
+    bench("show", || manager.show(&toast).map(|_| ()))?;
+
+    manager.remove_group("bench")
+}
@@ -0,0 +1,69 @@
+//! Compares showing a toast built from scratch with showing a pre-rendered template,
+//! also on managers whose adjustments apply to every toast.
+//!
+//! Run with `cargo bench`. This shows real toasts, so it needs an interactive session.
+//! The quiet hours cover the whole day except 23:59, so don't run it then.
+
+use std::time::{Duration, Instant};
+
+use winrt_toast_reborn::{QuietHours, Result, Toast, ToastData, ToastManager, ToastTemplate};
+
+const ITERATIONS: u32 = 50;
+
+fn bench(name: &str, mut f: impl FnMut(u32) -> Result<()>) -> Result<()> {
+    let start = Instant::now();
+    for i in 0..ITERATIONS {
+        f(i)?;
+    }
+    let per_call = start.elapsed() / ITERATIONS;
+    println!("{:<16} {:>10.3?} per call", name, per_call);
+    Ok(())
+}
+
+fn main() -> Result<()> {
+    let manager = ToastManager::new(ToastManager::POWERSHELL_AUM_ID);
+
+    let mut toast = Toast::new();
+    toast
+        .tag("bench")
+        .group("bench")
+        .text1("Ticker")
+        .expires_in(Duration::from_secs(5));
+
+    bench("show", |i| {
+        toast.text2(format!("Price: {}", i));
+        manager.show_unchecked(&toast).map(|_| ())
+    })?;
+
+    toast.text2("Price: {price}");
+    let template = ToastTemplate::new(&toast)?;
+    let mut data = ToastData::new();
+    bench("show_template", |i| {
+        data.set("price", i.to_string());
+        manager.show_template(&template, &data)
+    })?;
+
+    // The group is set by the manager, which doesn't change the XML.
+    let grouped = manager.clone().default_group("bench");
+    let mut ungrouped = Toast::new();
+    ungrouped
+        .tag("bench")
+        .text1("Ticker")
+        .text2("Price: {price}")
+        .expires_in(Duration::from_secs(5));
+    let ungrouped = ToastTemplate::new(&ungrouped)?;
+    bench("default_group", |i| {
+        data.set("price", i.to_string());
+        grouped.show_template(&ungrouped, &data)
+    })?;
+
+    let quiet = manager
+        .clone()
+        .quiet_hours(QuietHours::new((0, 0), (23, 59))?);
+    bench("quiet_hours", |i| {
+        data.set("price", i.to_string());
+        quiet.show_template(&template, &data)
+    })?;
+
+    manager.remove_group("bench")
+}
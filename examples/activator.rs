@@ -0,0 +1,36 @@
+use std::{sync::mpsc, time::Duration};
+
+use windows::Win32::System::Com::{CoInitializeEx, COINIT_MULTITHREADED};
+use winrt_toast_reborn::{
+    register, register_activator, Action, ComActivator, Result, Toast, ToastManager,
+};
+
+const AUM_ID: &str = "WinrtToastReborn.ActivatorExample";
+const CLSID: u128 = 0x6f1c2b3a_4d5e_4f60_8a7b_9c0d1e2f3a4b;
+
+fn main() -> Result<()> {
+    unsafe { CoInitializeEx(None, COINIT_MULTITHREADED).ok()? };
+
+    register(AUM_ID, "Activator example", None)?;
+    register_activator(AUM_ID, CLSID, &std::env::current_exe()?)?;
+
+    let (tx, rx) = mpsc::channel();
+    let _activator = ComActivator::register(CLSID, move |action| {
+        let _ = tx.send(action);
+    })?;
+
+    if !ComActivator::launched_for_activation() {
+        let mut toast = Toast::new();
+        toast
+            .text1("Activator example")
+            .text2("Close this app, then click the toast in the Action Center.")
+            .action(Action::new("Open", "open", "open"));
+        ToastManager::new(AUM_ID).show(&toast)?;
+    }
+
+    if let Ok(action) = rx.recv_timeout(Duration::from_secs(30)) {
+        println!("Activated: {:?}", action);
+    }
+
+    Ok(())
+}
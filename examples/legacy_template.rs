@@ -0,0 +1,16 @@
+use winrt_toast_reborn::{LegacyTemplate, Result, Toast, ToastManager};
+
+fn main() -> Result<()> {
+    let manager = ToastManager::new(ToastManager::POWERSHELL_AUM_ID);
+
+    let mut toast = Toast::from_template(
+        LegacyTemplate::ToastText02,
+        &["Title", "The body text, wrapped across the second and third lines."],
+        None,
+    )?;
+    toast.tag("legacy-template").group("examples");
+
+    manager.show(&toast)?;
+
+    Ok(())
+}
@@ -0,0 +1,16 @@
+use winrt_toast_reborn::{Result, Scenario, Toast, ToastManager};
+
+fn main() -> Result<()> {
+    let manager = ToastManager::new(ToastManager::POWERSHELL_AUM_ID);
+
+    let mut toast = Toast::new();
+    toast
+        .text1("Stand-up meeting")
+        .text2("Starts in 5 minutes")
+        .scenario(Scenario::Reminder)
+        .with_default_dismiss();
+
+    manager.show(&toast)?;
+
+    Ok(())
+}
@@ -75,16 +75,17 @@ fn main() -> Result<()> {
             handle_activated_action(action);
             action_clone.store(true, Ordering::SeqCst);
         })
-        .on_dismissed(move |reason| {
-            match reason {
+        .on_dismissed(move |dismissed| {
+            match dismissed.map(|dismissed| dismissed.reason) {
                 Ok(DismissalReason::UserCanceled) => println!("UserCanceled"),
                 Ok(DismissalReason::ApplicationHidden) => println!("ApplicationHidden"),
                 Ok(DismissalReason::TimedOut) => println!("TimedOut"),
+                Ok(reason) => println!("{:?}", reason),
                 Err(e) => eprintln!("Error: {:?}", e),
             }
             dismiss_clone.store(true, Ordering::SeqCst);
         })
-        .on_failed(|e| eprintln!("Error: {:?}", e))
+        .on_failed(|failed| eprintln!("Error: {:?}", failed.error))
         .show(&toast)
         .expect("Failed to show toast");
 
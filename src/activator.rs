@@ -0,0 +1,188 @@
+use std::{collections::HashMap, ffi::c_void, sync::Arc};
+
+use windows::{
+    core::{implement, IUnknown, Interface, GUID, PCWSTR},
+    Win32::{
+        Foundation::{BOOL, CLASS_E_NOAGGREGATION, E_POINTER},
+        System::Com::{
+            CoRegisterClassObject, CoRevokeClassObject, IClassFactory, IClassFactory_Impl,
+            CLSCTX_LOCAL_SERVER, REGCLS_MULTIPLEUSE,
+        },
+        UI::Notifications::{
+            INotificationActivationCallback, INotificationActivationCallback_Impl,
+            NOTIFICATION_USER_INPUT_DATA,
+        },
+    },
+};
+
+use crate::{catch_panic, correlation, ActivatedAction};
+
+type Callback = Arc<dyn Fn(ActivatedAction) + Send + Sync>;
+
+/// Receives toast activations through COM, including activations from the Action Center
+/// after the app has exited.
+///
+/// The [`on_activated`](crate::ToastManager::on_activated) callback only works while the
+/// process that showed the toast is running. To be activated after that, the app
+/// registers a COM class under the `CustomActivator` CLSID of its AUM_ID with
+/// [`register_activator`](crate::register_activator), and implements that class with
+/// a `ComActivator`.
+///
+/// # COM server lifetime
+/// * COM must be initialized on the thread calling [`ComActivator::register`], for
+///   example with `CoInitializeEx(None, COINIT_MULTITHREADED)`. Activations are
+///   delivered on a COM worker thread, not on the registering thread.
+/// * When the app is not running, Windows starts it with the [`ComActivator::LAUNCH_ARG`]
+///   argument and waits for the class to be registered. Register it early during startup,
+///   then keep the process alive until the activation arrived, see
+///   [`ComActivator::launched_for_activation`].
+/// * The class is registered until the `ComActivator` is dropped. Keep it alive for as
+///   long as the process should receive activations.
+///
+/// # Example
+/// ```no_run
+/// use winrt_toast_reborn::ComActivator;
+///
+/// const CLSID: u128 = 0x1d4b5a6c_0f3e_4c8a_9b2d_7e6f5a4b3c2d;
+///
+/// let activator = ComActivator::register(CLSID, |action| {
+///     println!("Activated with {:?}", action.arg);
+/// })
+/// .expect("Failed to register the activator");
+/// # drop(activator);
+/// ```
+pub struct ComActivator {
+    cookie: u32,
+}
+
+impl ComActivator {
+    /// The command line argument Windows passes when it starts the app to activate a toast.
+    pub const LAUNCH_ARG: &'static str = "-ToastActivated";
+
+    /// Register the class `clsid`, calling `f` for every activation of a toast.
+    ///
+    /// `clsid` must be the CLSID given to [`register_activator`](crate::register_activator).
+    /// [`ActivatedAction::value`] is always `None`, since no input id is known;
+    /// the inputs are available in [`ActivatedAction::values`].
+    /// [`ActivatedAction::full_title`] is always `None` as well, since the notification
+    /// is not available here.
+    pub fn register<F>(clsid: u128, f: F) -> crate::Result<Self>
+    where
+        F: Fn(ActivatedAction) + Send + Sync + 'static,
+    {
+        let factory: IClassFactory = ActivatorFactory {
+            callback: Arc::new(f),
+        }
+        .into();
+
+        let cookie = unsafe {
+            CoRegisterClassObject(
+                &GUID::from_u128(clsid),
+                &factory,
+                CLSCTX_LOCAL_SERVER,
+                REGCLS_MULTIPLEUSE,
+            )?
+        };
+
+        Ok(Self { cookie })
+    }
+
+    /// Whether Windows started this process to deliver a toast activation.
+    ///
+    /// Such a process should register its [`ComActivator`] and exit once the
+    /// activation was handled, instead of starting up normally.
+    pub fn launched_for_activation() -> bool {
+        std::env::args().any(|arg| arg == Self::LAUNCH_ARG)
+    }
+}
+
+impl Drop for ComActivator {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = CoRevokeClassObject(self.cookie);
+        }
+    }
+}
+
+impl std::fmt::Debug for ComActivator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "ComActivator({})", self.cookie)
+    }
+}
+
+#[implement(IClassFactory)]
+struct ActivatorFactory {
+    callback: Callback,
+}
+
+impl IClassFactory_Impl for ActivatorFactory {
+    fn CreateInstance(
+        &self,
+        outer: Option<&IUnknown>,
+        iid: *const GUID,
+        object: *mut *mut c_void,
+    ) -> windows::core::Result<()> {
+        if object.is_null() {
+            return Err(E_POINTER.into());
+        }
+        unsafe { *object = std::ptr::null_mut() };
+        if outer.is_some() {
+            return Err(CLASS_E_NOAGGREGATION.into());
+        }
+
+        let callback: INotificationActivationCallback = ActivationCallback {
+            callback: self.callback.clone(),
+        }
+        .into();
+        unsafe { callback.query(iid, object).ok() }
+    }
+
+    fn LockServer(&self, _lock: BOOL) -> windows::core::Result<()> {
+        Ok(())
+    }
+}
+
+#[implement(INotificationActivationCallback)]
+struct ActivationCallback {
+    callback: Callback,
+}
+
+impl INotificationActivationCallback_Impl for ActivationCallback {
+    fn Activate(
+        &self,
+        _app_user_model_id: &PCWSTR,
+        invoked_args: &PCWSTR,
+        data: *const NOTIFICATION_USER_INPUT_DATA,
+        count: u32,
+    ) -> windows::core::Result<()> {
+        let data = if data.is_null() {
+            &[]
+        } else {
+            unsafe { std::slice::from_raw_parts(data, count as usize) }
+        };
+
+        let mut values = HashMap::new();
+        for entry in data {
+            values.insert(pcwstr_to_string(&entry.Key), pcwstr_to_string(&entry.Value));
+        }
+
+        let arguments = pcwstr_to_string(invoked_args);
+        let (arg, correlation_id) = correlation::split(&arguments);
+        let action = ActivatedAction {
+            arg: arg.to_string(),
+            value: None,
+            values,
+            full_title: None,
+            correlation_id,
+        };
+        catch_panic(|| (self.callback)(action));
+        Ok(())
+    }
+}
+
+fn pcwstr_to_string(s: &PCWSTR) -> String {
+    if s.is_null() {
+        return String::new();
+    }
+    unsafe { s.to_string() }.unwrap_or_default()
+}
@@ -0,0 +1,72 @@
+use windows::Win32::{
+    Foundation::RPC_E_CHANGED_MODE,
+    System::Com::{CoInitializeEx, COINIT_APARTMENTTHREADED, COINIT_MULTITHREADED},
+};
+
+use crate::WinToastError;
+
+/// The COM apartment model of a thread.
+///
+/// Toast callbacks are delivered on the apartment the toast was shown from. In a
+/// single-threaded apartment, they only fire while the thread pumps window messages,
+/// e.g. in a GUI event loop; a thread that blocks or sleeps never receives them.
+/// In a multithreaded apartment, they fire on a thread pool thread at any time.
+///
+/// See <https://docs.microsoft.com/en-us/windows/win32/com/processes--threads--and-apartments>
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Apartment {
+    /// A single-threaded apartment (STA), as used by most GUI threads.
+    /// The thread must pump messages for callbacks to fire.
+    SingleThreaded,
+    /// The multithreaded apartment (MTA), suited to console apps and worker threads.
+    MultiThreaded,
+}
+
+/// Make sure the calling thread is in `apartment`, initializing COM on it if needed.
+///
+/// Callbacks are only delivered if the thread showing toasts is in a suitable apartment,
+/// see [`Apartment`] for the requirements of each. Call this on the thread that will show
+/// the toasts; COM stays initialized on it until it exits.
+///
+/// Returns [`WinToastError::ApartmentConflict`] if the thread is already in the other apartment,
+/// e.g. because a GUI framework initialized it.
+///
+/// # Example
+/// ```no_run
+/// # use winrt_toast_reborn::{ensure_apartment, Apartment, ToastManager};
+/// ensure_apartment(Apartment::MultiThreaded)?;
+/// let manager = ToastManager::new(ToastManager::POWERSHELL_AUM_ID);
+/// # Ok::<(), winrt_toast_reborn::WinToastError>(())
+/// ```
+pub fn ensure_apartment(apartment: Apartment) -> crate::Result<()> {
+    let coinit = match apartment {
+        Apartment::SingleThreaded => COINIT_APARTMENTTHREADED,
+        Apartment::MultiThreaded => COINIT_MULTITHREADED,
+    };
+    let hr = unsafe { CoInitializeEx(None, coinit) };
+    if hr == RPC_E_CHANGED_MODE {
+        return Err(WinToastError::ApartmentConflict(apartment));
+    }
+    hr.ok()?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn conflicting_apartments_are_detected() {
+        std::thread::spawn(|| {
+            ensure_apartment(Apartment::MultiThreaded).unwrap();
+            ensure_apartment(Apartment::MultiThreaded).unwrap();
+            assert!(matches!(
+                ensure_apartment(Apartment::SingleThreaded),
+                Err(WinToastError::ApartmentConflict(Apartment::SingleThreaded))
+            ));
+        })
+        .join()
+        .unwrap();
+    }
+}
@@ -0,0 +1,72 @@
+use crate::{limits::MAX_AUM_ID_LEN, WinToastError};
+
+/// Check that `aum_id` can identify an app to the notification platform.
+///
+/// Windows accepts a notifier for almost any string, but never shows toasts for an AUM_ID
+/// that doesn't match the registration exactly, which is easy to miss for surrounding
+/// whitespace from a config file.
+pub(crate) fn validate_aum_id(aum_id: &str) -> crate::Result<()> {
+    let problem = if aum_id.is_empty() {
+        "it is empty".to_string()
+    } else if aum_id.trim() != aum_id {
+        "it starts or ends with whitespace".to_string()
+    } else if aum_id.encode_utf16().count() > MAX_AUM_ID_LEN {
+        format!("it is longer than {} UTF-16 code units", MAX_AUM_ID_LEN)
+    } else if let Some(c) = aum_id.chars().find(|c| c.is_control()) {
+        format!("it contains the control character {:?}", c)
+    } else if aum_id.contains('"') {
+        "it contains '\"', which the shell uses to delimit arguments".to_string()
+    } else {
+        return Ok(());
+    };
+
+    Err(WinToastError::InvalidAumid(format!(
+        "{:?}: {}",
+        aum_id, problem
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn length_is_counted_in_utf16_code_units() {
+        // Each of these takes two UTF-16 code units, but is a single `char`.
+        let emoji = "\u{1F514}";
+        let fits = emoji.repeat(MAX_AUM_ID_LEN / 2);
+        assert!(validate_aum_id(&fits).is_ok());
+        let too_long = emoji.repeat(MAX_AUM_ID_LEN / 2 + 1);
+        assert!(too_long.chars().count() <= MAX_AUM_ID_LEN);
+        assert!(matches!(
+            validate_aum_id(&too_long),
+            Err(WinToastError::InvalidAumid(_))
+        ));
+    }
+
+    #[test]
+    fn table_of_aum_ids() {
+        let too_long = "a".repeat(MAX_AUM_ID_LEN + 1);
+        let cases: &[(&str, bool)] = &[
+            ("", false),
+            (&too_long, false),
+            (" Contoso.App", false),
+            ("Contoso.App\t", false),
+            ("Contoso.App\0", false),
+            ("Contoso.\"App\"", false),
+            // Inner spaces and backslashes are fine, the latter are even part of the
+            // PowerShell AUM_ID.
+            ("Contoso App", true),
+            ("Contoso\\App", true),
+            (crate::ToastManager::POWERSHELL_AUM_ID, true),
+            ("Contoso.App.Mail", true),
+        ];
+        for (aum_id, valid) in cases {
+            let result = validate_aum_id(aum_id);
+            assert_eq!(result.is_ok(), *valid, "{:?}", aum_id);
+            if let Err(error) = result {
+                assert!(matches!(error, WinToastError::InvalidAumid(_)));
+            }
+        }
+    }
+}
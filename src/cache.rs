@@ -0,0 +1,333 @@
+use std::{
+    cmp::Reverse,
+    fs, io,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use crate::{hash::fnv1a, WinToastError};
+
+/// Where and for how long this crate keeps the files it writes, such as images created
+/// with [`Image::from_bytes`](crate::Image::from_bytes).
+///
+/// Toasts refer to media by path, so the files must outlive the call that showed the
+/// toast, and possibly the process. Files are named after a hash of their content, so
+/// writing the same content twice reuses the file.
+///
+/// The directory should be dedicated to this crate: [`CacheConfig::reconcile`] and
+/// [`CacheConfig::purge`] delete files in it that look like cache files.
+///
+/// # Example
+/// ```no_run
+/// # use winrt_toast_reborn::CacheConfig;
+/// # use std::time::Duration;
+/// let local_app_data = std::env::var("LOCALAPPDATA").unwrap();
+/// let cache = CacheConfig::new(format!("{local_app_data}\\MyApp\\toast-cache"))
+///     .with_max_bytes(16 * 1024 * 1024)
+///     .with_ttl(Duration::from_secs(24 * 60 * 60));
+/// // Once at startup, to delete what earlier runs left behind.
+/// cache.reconcile()?;
+/// # Ok::<(), winrt_toast_reborn::WinToastError>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheConfig {
+    dir: PathBuf,
+    max_bytes: u64,
+    ttl: Duration,
+}
+
+impl CacheConfig {
+    /// The default budget of [`CacheConfig::with_max_bytes`], 64 MB.
+    pub const DEFAULT_MAX_BYTES: u64 = 64 * 1024 * 1024;
+    /// The default of [`CacheConfig::with_ttl`], 7 days.
+    pub const DEFAULT_TTL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+    /// Keep the files in `dir`, which is created when the first file is written.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            max_bytes: Self::DEFAULT_MAX_BYTES,
+            ttl: Self::DEFAULT_TTL,
+        }
+    }
+
+    /// Keep the files in a `winrt-toast-reborn` directory in the temporary directory.
+    pub fn temp() -> Self {
+        Self::new(std::env::temp_dir().join("winrt-toast-reborn"))
+    }
+
+    /// The total size of the files to keep, in bytes.
+    ///
+    /// Beyond that, the least recently written files are deleted.
+    pub fn with_max_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    /// How long to keep a file after it was last written.
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// The directory the files are kept in.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Delete all cache files.
+    pub fn purge(&self) -> crate::Result<()> {
+        for (path, _) in self.files()? {
+            remove_file(&path)?;
+        }
+        Ok(())
+    }
+
+    /// Delete the cache files older than the TTL, then the least recently written ones
+    /// until the rest fits into the budget.
+    ///
+    /// This runs after every write. Call it at startup as well, to delete the files
+    /// that earlier runs left behind.
+    pub fn reconcile(&self) -> crate::Result<()> {
+        self.reconcile_except(None)
+    }
+
+    /// The path of the cache file for `bytes` with `extension`.
+    pub(crate) fn path_for(&self, bytes: &[u8], extension: &str) -> crate::Result<PathBuf> {
+        let name = format!("{:016x}.{}", fnv1a([bytes]), extension);
+        let path = self.dir.join(name);
+        if !extension.chars().all(|c| c.is_ascii_alphanumeric()) || extension.is_empty() {
+            return Err(WinToastError::InvalidPath(path));
+        }
+        Ok(path)
+    }
+
+    /// Write `bytes` into a cache file with `extension`, and return its path.
+    pub(crate) fn store(&self, bytes: &[u8], extension: &str) -> crate::Result<PathBuf> {
+        let path = self.path_for(bytes, extension)?;
+
+        fs::create_dir_all(&self.dir)?;
+        // Rewriting an existing file also renews it for the TTL and the budget.
+        fs::write(&path, bytes)?;
+        self.reconcile_except(Some(&path))?;
+        Ok(path)
+    }
+
+    fn reconcile_except(&self, keep: Option<&Path>) -> crate::Result<()> {
+        let now = SystemTime::now();
+        let mut files: Vec<_> = self
+            .files()?
+            .into_iter()
+            .filter(|(path, _)| Some(path.as_path()) != keep)
+            .collect();
+        // Newest first, so the files beyond the budget are at the end.
+        files.sort_by_key(|(_, file)| Reverse(file.modified));
+
+        let mut total = keep
+            .and_then(|path| fs::metadata(path).ok())
+            .map_or(0, |metadata| metadata.len());
+        for (path, file) in files {
+            let expired = now
+                .duration_since(file.modified)
+                .is_ok_and(|age| age > self.ttl);
+            if expired || total + file.len > self.max_bytes {
+                remove_file(&path)?;
+            } else {
+                total += file.len;
+            }
+        }
+        Ok(())
+    }
+
+    /// The cache files in the directory, with their size and modification time.
+    fn files(&self) -> crate::Result<Vec<(PathBuf, CacheFile)>> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut files = Vec::new();
+        for entry in entries {
+            let entry = entry?;
+            if !is_cache_file_name(&entry.file_name().to_string_lossy()) {
+                continue;
+            }
+            let metadata = entry.metadata()?;
+            if metadata.is_file() {
+                let file = CacheFile {
+                    len: metadata.len(),
+                    modified: metadata.modified()?,
+                };
+                files.push((entry.path(), file));
+            }
+        }
+        Ok(files)
+    }
+}
+
+struct CacheFile {
+    len: u64,
+    modified: SystemTime,
+}
+
+/// Whether `name` is of the form written by [`CacheConfig::store`], a 16-digit hash and an extension.
+fn is_cache_file_name(name: &str) -> bool {
+    let Some((stem, extension)) = name.split_once('.') else {
+        return false;
+    };
+    stem.len() == 16
+        && stem.chars().all(|c| c.is_ascii_hexdigit())
+        && !extension.is_empty()
+        && extension.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+/// Remove a file, ignoring that it is already gone, e.g. deleted by another process.
+fn remove_file(path: &Path) -> crate::Result<()> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+
+    use super::*;
+
+    /// A cache in a fresh directory of its own, removed when dropped.
+    struct TempCache(CacheConfig);
+
+    impl TempCache {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!(
+                "winrt-toast-reborn-test-{}-{}",
+                std::process::id(),
+                name
+            ));
+            let _ = fs::remove_dir_all(&dir);
+            Self(CacheConfig::new(dir))
+        }
+
+        fn names(&self) -> Vec<String> {
+            let mut names: Vec<_> = fs::read_dir(self.0.dir())
+                .unwrap()
+                .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+                .collect();
+            names.sort();
+            names
+        }
+    }
+
+    impl Drop for TempCache {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(self.0.dir());
+        }
+    }
+
+    fn set_age(path: &Path, age: Duration) {
+        let file = File::options().write(true).open(path).unwrap();
+        file.set_modified(SystemTime::now() - age).unwrap();
+    }
+
+    #[test]
+    fn stores_content_under_its_hash() {
+        let cache = TempCache::new("hash");
+        let first = cache.0.store(b"image", "png").unwrap();
+        let again = cache.0.store(b"image", "png").unwrap();
+        let other = cache.0.store(b"other", "png").unwrap();
+
+        assert_eq!(first, again);
+        assert_ne!(first, other);
+        assert_eq!(fs::read(&first).unwrap(), b"image");
+        assert!(is_cache_file_name(
+            &first.file_name().unwrap().to_string_lossy()
+        ));
+        assert_eq!(cache.names().len(), 2);
+    }
+
+    #[test]
+    fn rejects_extensions_that_are_not_alphanumeric() {
+        let cache = TempCache::new("extension");
+        for extension in ["", "png/../x", "p.ng", "png "] {
+            assert!(matches!(
+                cache.0.store(b"image", extension),
+                Err(WinToastError::InvalidPath(_))
+            ));
+        }
+        assert!(!cache.0.dir().exists());
+    }
+
+    #[test]
+    fn deletes_the_oldest_files_beyond_the_budget() {
+        let mut cache = TempCache::new("budget");
+        cache.0 = cache.0.clone().with_max_bytes(10);
+        let oldest = cache.0.store(b"aaaa", "png").unwrap();
+        set_age(&oldest, Duration::from_secs(30));
+        let older = cache.0.store(b"bbbb", "png").unwrap();
+        set_age(&older, Duration::from_secs(20));
+        let newest = cache.0.store(b"cccc", "png").unwrap();
+
+        assert!(!oldest.exists());
+        assert!(older.exists());
+        assert!(newest.exists());
+    }
+
+    #[test]
+    fn keeps_the_written_file_even_if_it_exceeds_the_budget() {
+        let mut cache = TempCache::new("large");
+        cache.0 = cache.0.clone().with_max_bytes(2);
+        let small = cache.0.store(b"a", "png").unwrap();
+        set_age(&small, Duration::from_secs(10));
+        let large = cache.0.store(b"larger than the budget", "png").unwrap();
+
+        assert!(!small.exists());
+        assert!(large.exists());
+    }
+
+    #[test]
+    fn deletes_expired_files() {
+        let mut cache = TempCache::new("ttl");
+        cache.0 = cache.0.clone().with_ttl(Duration::from_secs(60));
+        let expired = cache.0.store(b"old", "png").unwrap();
+        set_age(&expired, Duration::from_secs(120));
+        let fresh = cache.0.store(b"new", "png").unwrap();
+        set_age(&fresh, Duration::from_secs(30));
+
+        cache.0.reconcile().unwrap();
+        assert!(!expired.exists());
+        assert!(fresh.exists());
+    }
+
+    #[test]
+    fn leaves_other_files_alone() {
+        let cache = TempCache::new("other");
+        let stored = cache.0.store(b"image", "png").unwrap();
+        let notes = cache.0.dir().join("notes.txt");
+        fs::write(&notes, "keep me").unwrap();
+        set_age(&notes, Duration::from_secs(30 * 24 * 60 * 60));
+
+        cache.0.reconcile().unwrap();
+        assert!(notes.exists());
+        cache.0.purge().unwrap();
+        assert!(!stored.exists());
+        assert_eq!(cache.names(), ["notes.txt"]);
+    }
+
+    #[test]
+    fn reconciles_a_missing_directory() {
+        let cache = TempCache::new("missing");
+        cache.0.reconcile().unwrap();
+        cache.0.purge().unwrap();
+    }
+
+    #[test]
+    fn recognizes_cache_file_names() {
+        assert!(is_cache_file_name("0123456789abcdef.png"));
+        assert!(!is_cache_file_name("0123456789abcdef"));
+        assert!(!is_cache_file_name("0123456789abcdeg.png"));
+        assert!(!is_cache_file_name("0123456789abcdef.tar.gz"));
+        assert!(!is_cache_file_name("notes.txt"));
+    }
+}
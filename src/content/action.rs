@@ -1,25 +1,46 @@
+use std::borrow::Cow;
+
+use url::Url;
 use windows::Data::Xml::Dom::XmlElement;
 
-use crate::hs;
+use crate::content::{audio::Sound, ElementExt, RawAttributes};
 
 /// Specifies a button shown in a toast.
+///
+/// Actions from `&'static str` can be created without allocating with [`Action::from_static`].
 #[derive(Debug, Clone)]
 pub struct Action {
-    content: String,
-    arguments: String,
-    r#type: String,
+    content: Cow<'static, str>,
+    arguments: Cow<'static, str>,
+    r#type: Cow<'static, str>,
     activation_type: Option<ActivationType>,
     placement: Option<ActionPlacement>,
     input_id: Option<String>,
     button_style: Option<HintButtonStyle>,
+    after_activation: Option<AfterActivation>,
+    feedback_sound: Option<Sound>,
+    pub(crate) raw_attributes: RawAttributes,
 }
 
 impl Action {
+    /// The attributes of the `<action>` element that this crate writes itself.
+    pub const RESERVED_ATTRIBUTES: &'static [&'static str] = &[
+        "content",
+        "arguments",
+        "type",
+        "activationType",
+        "placement",
+        "hint-buttonStyle",
+        "hint-inputId",
+        "afterActivationBehavior",
+    ];
+
     /// Create a new action.
     ///
     /// `arguments`: An argument string that can be passed to the associated app
     /// to provide specifics about the action that it should execute
-    /// in response to the user action.
+    /// in response to the user action. Like [`Toast::launch`](crate::Toast::launch),
+    /// it is escaped when the XML is written and arrives unchanged.
     ///
     /// `typ`: An argument string that can be passed to the associated app to
     /// provide specifics about the action that it should execute in response to the user action.
@@ -27,18 +48,86 @@ impl Action {
         content: impl Into<String>,
         arguments: impl Into<String>,
         typ: impl Into<String>,
+    ) -> Self {
+        Self::with_strings(
+            Cow::Owned(content.into()),
+            Cow::Owned(arguments.into()),
+            Cow::Owned(typ.into()),
+        )
+    }
+
+    /// Create an action from static strings, without allocating, see [`Action::new`].
+    ///
+    /// ```
+    /// # use winrt_toast_reborn::Action;
+    /// const OPEN: Action = Action::from_static("Open", "open", "");
+    /// ```
+    pub const fn from_static(
+        content: &'static str,
+        arguments: &'static str,
+        typ: &'static str,
+    ) -> Self {
+        Self::with_strings(
+            Cow::Borrowed(content),
+            Cow::Borrowed(arguments),
+            Cow::Borrowed(typ),
+        )
+    }
+
+    const fn with_strings(
+        content: Cow<'static, str>,
+        arguments: Cow<'static, str>,
+        r#type: Cow<'static, str>,
     ) -> Self {
         Self {
-            content: content.into(),
-            arguments: arguments.into(),
-            r#type: typ.into(),
+            content,
+            arguments,
+            r#type,
             activation_type: None,
             placement: None,
             button_style: None,
+            after_activation: None,
+            feedback_sound: None,
             input_id: None,
+            raw_attributes: RawAttributes::new(),
         }
     }
 
+    /// Create a button that opens `url`, e.g. in the browser for `https` URLs.
+    ///
+    /// The URL is given as the arguments of an action with [`ActivationType::Protocol`],
+    /// so Windows launches the default handler of its scheme instead of activating the app.
+    pub fn link(content: impl Into<String>, url: Url) -> Self {
+        Self::new(content, url.as_str(), "").with_activation_type(ActivationType::Protocol)
+    }
+
+    /// A button that accepts an incoming call, for toasts with
+    /// [`Scenario::IncomingCall`](crate::Scenario::IncomingCall).
+    ///
+    /// The button activates the app in the foreground and has the
+    /// [`HintButtonStyle::Success`] style, and nothing else that changes its layout.
+    /// Windows 11 shows it as a wide green button, if the toast enables
+    /// [`Toast::use_button_style`](crate::Toast::use_button_style). Windows 10 ignores
+    /// the style and shows a plain button of the same width as the others.
+    ///
+    /// Icons set with the raw `imageUri` attribute turn call buttons into small round
+    /// buttons on both versions, see [`ValidationIssue::CallButtonIcon`](crate::ValidationIssue::CallButtonIcon).
+    pub fn call_accept(content: impl Into<String>, arguments: impl Into<String>) -> Self {
+        Self::new(content, arguments, "")
+            .with_activation_type(ActivationType::Foreground)
+            .with_button_style(HintButtonStyle::Success)
+    }
+
+    /// A button that declines an incoming call, see [`Action::call_accept`].
+    ///
+    /// The button activates the app in the background, so declining doesn't bring a window
+    /// to the front, and has the [`HintButtonStyle::Critical`] style, which Windows 11 shows in red.
+    pub fn call_decline(content: impl Into<String>, arguments: impl Into<String>) -> Self {
+        Self::new(content, arguments, "")
+            .with_activation_type(ActivationType::Background)
+            .with_button_style(HintButtonStyle::Critical)
+    }
+
     /// The activation type of the action.
     pub fn with_activation_type(mut self, activation_type: ActivationType) -> Self {
         self.activation_type = Some(activation_type);
@@ -57,30 +146,117 @@ impl Action {
         self
     }
 
+    /// What happens to the toast after this action was activated.
+    pub fn with_after_activation(mut self, after_activation: AfterActivation) -> Self {
+        self.after_activation = Some(after_activation);
+        self
+    }
+
+    /// Play `sound` when this action is activated, as auditory confirmation of a background action.
+    ///
+    /// Windows has no sounds for buttons, so this is emulated by this crate: the activation
+    /// handler of the [`ToastManager`](crate::ToastManager) plays the sound on a separate thread
+    /// before calling the `on_activated` callback, so it never delays it. The sound is only
+    /// played by a manager with an `on_activated` callback, and not at all with
+    /// [`ToastManager::disable_feedback_sounds`](crate::ToastManager::disable_feedback_sounds).
+    /// Looping sounds play once.
+    pub fn with_feedback_sound(mut self, sound: Sound) -> Self {
+        self.feedback_sound = Some(sound);
+        self
+    }
+
     /// The ID of the input element that this action is associated with.
     ///
     /// This is used to associate the action with a specific input element in the toast.
+    /// Windows shows the action next to the input, and pressing Enter in a text input
+    /// activates it, see [`Toast::quick_reply_enter_submits`](crate::Toast::quick_reply_enter_submits).
     pub fn with_input_id(mut self, input_id: impl Into<String>) -> Self {
         self.input_id = Some(input_id.into());
         self
     }
 
+    /// Set an arbitrary attribute, emitted verbatim after the attributes this crate supports.
+    ///
+    /// This is an escape hatch for attributes without typed support yet, such as new `hint-*` attributes.
+    /// Names that are not valid XML names, and the attributes written by this crate
+    /// (see [`Action::RESERVED_ATTRIBUTES`]), are reported by [`Toast::validate`](crate::Toast::validate).
+    pub fn with_raw_attribute(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.raw_attributes.set(name.into(), value.into());
+        self
+    }
+
+    /// The value of an attribute set with [`Action::with_raw_attribute`].
+    pub fn raw_attribute(&self, name: &str) -> Option<&str> {
+        self.raw_attributes.get(name)
+    }
+
+    pub(crate) fn content(&self) -> &str {
+        &self.content
+    }
+
+    pub(crate) fn arguments(&self) -> &str {
+        &self.arguments
+    }
+
+    /// Replace the arguments with `f` applied to them, unless they are for a protocol or the system.
+    pub(crate) fn map_arguments(&mut self, f: impl FnOnce(&str) -> String) {
+        if !matches!(
+            self.activation_type,
+            Some(ActivationType::Protocol | ActivationType::System)
+        ) {
+            self.arguments = Cow::Owned(f(&self.arguments));
+        }
+    }
+
+    /// Whether the toast stays on screen after this action was activated.
+    pub(crate) fn keeps_toast(&self) -> bool {
+        self.after_activation == Some(AfterActivation::PendingUpdate)
+            && self.activation_type == Some(ActivationType::Background)
+    }
+
+    /// The URL this action launches, `None` if it is not activated by protocol.
+    pub(crate) fn protocol_uri(&self) -> Option<&str> {
+        (self.activation_type == Some(ActivationType::Protocol)).then_some(&*self.arguments)
+    }
+
+    pub(crate) fn has_button_style(&self) -> bool {
+        self.button_style.is_some()
+    }
+
+    pub(crate) fn input_id(&self) -> Option<&str> {
+        self.input_id.as_deref()
+    }
+
+    /// The arguments of this action and the sound to play when it is activated.
+    pub(crate) fn feedback_sound(&self) -> Option<(&str, &Sound)> {
+        let sound = self.feedback_sound.as_ref()?;
+        Some((&*self.arguments, sound))
+    }
+
+    pub(crate) fn is_context_menu(&self) -> bool {
+        self.placement == Some(ActionPlacement::ContextMenu)
+    }
+
     pub(crate) fn write_to_element(&self, el: &XmlElement) -> crate::Result<()> {
-        el.SetAttribute(&hs("content"), &hs(&self.content))?;
-        el.SetAttribute(&hs("arguments"), &hs(&self.arguments))?;
-        el.SetAttribute(&hs("type"), &hs(&self.r#type))?;
+        el.set("content", &self.content)?;
+        el.set("arguments", &self.arguments)?;
+        el.set("type", &self.r#type)?;
         if let Some(activation_type) = self.activation_type {
-            el.SetAttribute(&hs("activationType"), &hs(activation_type.as_str()))?;
+            el.set("activationType", activation_type.as_str())?;
         }
         if let Some(placement) = self.placement {
-            el.SetAttribute(&hs("placement"), &hs(placement.as_str()))?;
+            el.set("placement", placement.as_str())?;
         }
         if let Some(button_style) = self.button_style {
-            el.SetAttribute(&hs("hint-buttonStyle"), &hs(button_style.as_str()))?;
+            el.set("hint-buttonStyle", button_style.as_str())?;
         }
         if let Some(input_id) = &self.input_id {
-            el.SetAttribute(&hs("hint-inputId"), &hs(input_id))?;
+            el.set("hint-inputId", input_id)?;
         }
+        if let Some(after_activation) = self.after_activation {
+            el.set("afterActivationBehavior", after_activation.as_str())?;
+        }
+        self.raw_attributes.write_to_element(el)?;
 
         Ok(())
     }
@@ -88,6 +264,7 @@ impl Action {
 
 /// The type of activation that will be used when the user interacts with a specific action
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum ActivationType {
     /// Default value. Your foreground app is launched.
     Foreground,
@@ -95,6 +272,8 @@ pub enum ActivationType {
     Background,
     /// Launch a different app using protocol activation.
     Protocol,
+    /// Let Windows handle the action, e.g. with the arguments `dismiss` or `snooze`.
+    System,
 }
 
 impl ActivationType {
@@ -103,6 +282,29 @@ impl ActivationType {
             ActivationType::Foreground => "foreground",
             ActivationType::Background => "background",
             ActivationType::Protocol => "protocol",
+            ActivationType::System => "system",
+        }
+    }
+}
+
+/// What happens to the toast after an action was activated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AfterActivation {
+    /// Default value. The toast is dismissed.
+    Default,
+    /// The toast stays on screen in a pending state until it is updated,
+    /// e.g. with [`ToastManager::update`](crate::ToastManager::update).
+    ///
+    /// This requires Windows 10 version 1709 (build 16299) or later, and only takes effect
+    /// for actions with [`ActivationType::Background`]; otherwise the toast is dismissed.
+    PendingUpdate,
+}
+
+impl AfterActivation {
+    fn as_str(&self) -> &'static str {
+        match self {
+            AfterActivation::Default => "default",
+            AfterActivation::PendingUpdate => "pendingUpdate",
         }
     }
 }
@@ -140,3 +342,125 @@ impl HintButtonStyle {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use windows::Data::Xml::Dom::XmlDocument;
+
+    use super::*;
+    use crate::{content::append_root, hs};
+
+    /// The value of each of `names` on the element `action` writes, empty if it is not set.
+    fn emitted(action: &Action, names: &[&str]) -> Vec<String> {
+        let doc = XmlDocument::new().unwrap();
+        let el = append_root(&doc, "action").unwrap();
+        action.write_to_element(&el).unwrap();
+        names
+            .iter()
+            .map(|name| el.GetAttribute(&hs(name)).unwrap().to_string())
+            .collect()
+    }
+
+    const CALL_ATTRIBUTES: &[&str] = &[
+        "arguments",
+        "activationType",
+        "hint-buttonStyle",
+        "placement",
+        "hint-inputId",
+        "afterActivationBehavior",
+        "imageUri",
+    ];
+
+    #[test]
+    fn emitted_call_accept() {
+        let accept = Action::call_accept("Accept", "accept");
+        assert_eq!(
+            emitted(&accept, CALL_ATTRIBUTES),
+            ["accept", "foreground", "Success", "", "", "", ""]
+        );
+    }
+
+    #[test]
+    fn emitted_call_decline() {
+        let decline = Action::call_decline("Decline", "decline");
+        assert_eq!(
+            emitted(&decline, CALL_ATTRIBUTES),
+            ["decline", "background", "Critical", "", "", "", ""]
+        );
+    }
+
+    #[test]
+    fn emitted_link() {
+        let url = Url::parse("https://example.com/a?b=c").unwrap();
+        let link = Action::link("Open", url);
+        assert_eq!(
+            emitted(&link, &["activationType", "arguments"]),
+            ["protocol", "https://example.com/a?b=c"]
+        );
+    }
+
+    #[test]
+    fn emitted_after_activation() {
+        let action = Action::new("Mark read", "read", "");
+        assert_eq!(emitted(&action, &["afterActivationBehavior"]), [""]);
+        for (after_activation, value) in [
+            (AfterActivation::Default, "default"),
+            (AfterActivation::PendingUpdate, "pendingUpdate"),
+        ] {
+            let action = action.clone().with_after_activation(after_activation);
+            assert_eq!(emitted(&action, &["afterActivationBehavior"]), [value]);
+        }
+    }
+
+    #[test]
+    fn links_have_a_protocol_uri() {
+        let url = Url::parse("https://example.com").unwrap();
+        assert_eq!(
+            Action::link("Open", url).protocol_uri(),
+            Some("https://example.com/")
+        );
+        assert_eq!(Action::new("Open", "open", "").protocol_uri(), None);
+    }
+
+    #[test]
+    fn map_arguments_leaves_protocol_and_system_arguments_alone() {
+        let url = Url::parse("https://example.com").unwrap();
+        let cases = [
+            (Action::new("Open", "open", ""), "tagged:open"),
+            (Action::call_accept("Accept", "accept"), "tagged:accept"),
+            (Action::call_decline("Decline", "decline"), "tagged:decline"),
+            (Action::link("Open", url), "https://example.com/"),
+            (
+                Action::new("Snooze", "snooze", "").with_activation_type(ActivationType::System),
+                "snooze",
+            ),
+        ];
+        for (mut action, arguments) in cases {
+            action.map_arguments(|arguments| format!("tagged:{}", arguments));
+            assert_eq!(action.arguments(), arguments);
+        }
+    }
+
+    #[test]
+    fn only_pending_background_actions_keep_the_toast() {
+        let action = |activation_type, after_activation| {
+            let action = Action::new("Mark read", "read", "");
+            let action = match activation_type {
+                Some(activation_type) => action.with_activation_type(activation_type),
+                None => action,
+            };
+            match after_activation {
+                Some(after_activation) => action.with_after_activation(after_activation),
+                None => action,
+            }
+        };
+        let pending = Some(AfterActivation::PendingUpdate);
+        let background = Some(ActivationType::Background);
+        assert!(action(background, pending).keeps_toast());
+        assert!(!action(background, Some(AfterActivation::Default)).keeps_toast());
+        assert!(!action(background, None).keeps_toast());
+        assert!(!action(Some(ActivationType::Foreground), pending).keeps_toast());
+        assert!(!action(None, pending).keeps_toast());
+        assert!(!Action::call_decline("Decline", "decline").keeps_toast());
+    }
+}
@@ -1,9 +1,10 @@
-use crate::hs;
-use std::fmt::Debug;
+use crate::content::ElementExt;
+use std::{fmt::Debug, time::Duration};
 use windows::Data::Xml::Dom::XmlElement;
 
 /// An enum representing the sounds available.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Sound {
     /// The default system sound.
     Default,
@@ -33,11 +34,23 @@ impl Sound {
             Sound::None => "",
         }
     }
+
+    /// The name of the system sound event, such as `Notification.Default`, or `None` for silence.
+    ///
+    /// This is the alias `PlaySoundW` plays the sound under.
+    pub(crate) fn event_alias(&self) -> Option<String> {
+        match self {
+            Sound::Looping(s) => Some(format!("Notification.Looping.{}", s.as_str())),
+            Sound::None => None,
+            s => Some(format!("Notification.{}", s.as_str())),
+        }
+    }
 }
 
 /// An enum representing the looping sounds available.
 #[allow(missing_docs)]
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum LoopingSound {
     Alarm,
     Alarm2,
@@ -89,61 +102,234 @@ impl LoopingSound {
 }
 
 /// Represents an audio element in a toast.
+///
+/// The toast audio element only supports a sound, looping and silence. Windows has no
+/// volume, fade or loop count controls for toast audio; the sound plays at the system
+/// notification volume. A loop count is emulated by showing the toast again silently,
+/// see [`Audio::with_loop_count`]. Apps that need more control can show the toast silently
+/// and play the sound themselves.
+///
+/// Silent audio, i.e. [`Audio::with_silent`] or [`Sound::None`], is written as
+/// `silent="true"` alone, so it never carries a sound or looping at the same time.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Audio {
     src: Sound,
+    #[cfg_attr(feature = "serde", serde(rename = "loop"))]
     loop_: bool,
     silent: bool,
+    loop_count: Option<u32>,
+    loop_duration: Option<Duration>,
 }
 
 impl Audio {
+    /// The estimated length of one loop of a [`Sound::Looping`] sound, see [`Audio::with_loop_count`].
+    pub const DEFAULT_LOOP_DURATION: Duration = Duration::from_secs(5);
+
     /// Create a new audio element.
     pub fn new(src: Sound) -> Self {
         Self {
             src,
             loop_: false,
             silent: false,
+            loop_count: None,
+            loop_duration: None,
         }
     }
 
     /// Set the audio to loop.
+    ///
+    /// Windows only loops audio in toasts with a long duration or the alarm and incoming call
+    /// scenarios, and looping is only meaningful with a [`Sound::Looping`] sound.
+    /// Looping is ignored for silent audio.
     pub fn with_looping(mut self) -> Self {
         self.loop_ = true;
         self
     }
 
+    /// Let the sound loop `count` times, then continue the toast silently.
+    ///
+    /// Windows can only loop a sound until the toast is dismissed, so this is emulated by the
+    /// [`ToastManager`](crate::ToastManager) that shows the toast: once `count` loops should
+    /// have played, it hides the toast and shows it again with silent audio, with the same
+    /// content, tag and group. This is approximate:
+    /// * The length of a loop is not known, and estimated as [`Audio::DEFAULT_LOOP_DURATION`]
+    ///   or the duration given with [`Audio::with_loop_duration`].
+    /// * The toast pops up again without sound, and `on_dismissed` is called for the hidden
+    ///   toast with [`DismissalReason::ApplicationHidden`](crate::DismissalReason::ApplicationHidden).
+    /// * The [`ToastHandle`](crate::ToastHandle) of the toast refers to the silent copy from then on.
+    /// * Nothing happens once the toast was activated or dismissed, after
+    ///   [`ToastManager::shutdown`](crate::ToastManager::shutdown), or if it wasn't shown
+    ///   with [`ToastManager::show`](crate::ToastManager::show) or its variants.
+    ///
+    /// A `count` of 0 is rejected by [`Toast::validate`](crate::Toast::validate) with
+    /// [`ValidationIssue::ZeroLoopCount`](crate::ValidationIssue::ZeroLoopCount), and otherwise
+    /// ignored. This implies [`Audio::with_looping`].
+    pub fn with_loop_count(mut self, count: u32) -> Self {
+        self.loop_ = true;
+        self.loop_count = Some(count);
+        self
+    }
+
+    /// The length of one loop of the sound, for [`Audio::with_loop_count`].
+    pub fn with_loop_duration(mut self, duration: Duration) -> Self {
+        self.loop_duration = Some(duration);
+        self
+    }
+
+    /// How long the sound plays before the toast is shown again silently,
+    /// if a loop count is set on audio that is not silent.
+    pub(crate) fn silence_after(&self) -> Option<Duration> {
+        if self.silent || matches!(self.src, Sound::None) {
+            return None;
+        }
+        let loop_count = self.loop_count.filter(|count| *count > 0)?;
+        let loop_duration = self.loop_duration.unwrap_or(Self::DEFAULT_LOOP_DURATION);
+        Some(loop_duration.saturating_mul(loop_count))
+    }
+
+    /// The loop count set with [`Audio::with_loop_count`].
+    pub(crate) fn loop_count(&self) -> Option<u32> {
+        self.loop_count
+    }
+
     /// Set the audio to be silent.
     pub fn with_silent(mut self) -> Self {
         self.silent = true;
         self
     }
 
+    /// A copy of this audio that never plays, with looping sounds downgraded.
+    pub(crate) fn silenced(&self) -> Self {
+        let src = match &self.src {
+            Sound::Looping(_) => Sound::Default,
+            src => src.clone(),
+        };
+        Self {
+            src,
+            loop_: false,
+            silent: true,
+            loop_count: None,
+            loop_duration: None,
+        }
+    }
+
     pub(crate) fn write_to_element(&self, el: &XmlElement) -> crate::Result<()> {
-        let mut silent = self.silent;
-        match &self.src {
-            Sound::None => silent = true,
-            Sound::Looping(s) => {
-                el.SetAttribute(
-                    &hs("src"),
-                    &hs(format!(
-                        "ms-winsoundevent:Notification.Looping.{}",
-                        s.as_str(),
-                    )),
-                )?;
-            }
-            _ => {
-                el.SetAttribute(
-                    &hs("src"),
-                    &hs(format!(
-                        "ms-winsoundevent:Notification.{}",
-                        self.src.as_str(),
-                    )),
-                )?;
-            }
+        for (name, value) in self.attributes() {
+            el.set(name, value)?;
         }
-        el.SetAttribute(&hs("loop"), &hs(if self.loop_ { "true" } else { "false" }))?;
-        el.SetAttribute(&hs("silent"), &hs(if silent { "true" } else { "false" }))?;
 
         Ok(())
     }
+
+    /// The attributes of the `<audio>` element, in the order they are written.
+    fn attributes(&self) -> Vec<(&'static str, String)> {
+        let src = match &self.src {
+            Sound::None => None,
+            Sound::Looping(s) => Some(format!(
+                "ms-winsoundevent:Notification.Looping.{}",
+                s.as_str(),
+            )),
+            src => Some(format!("ms-winsoundevent:Notification.{}", src.as_str())),
+        };
+
+        match src {
+            Some(src) if !self.silent => vec![
+                ("src", src),
+                ("loop", self.loop_.to_string()),
+                ("silent", "false".to_string()),
+            ],
+            _ => vec![("silent", "true".to_string())],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Toast, ValidationIssue};
+
+    #[test]
+    fn silence_after_multiplies_the_loop_duration() {
+        let audio = Audio::new(Sound::Looping(LoopingSound::Alarm)).with_loop_count(3);
+        assert_eq!(audio.silence_after(), Some(Duration::from_secs(15)));
+        let audio = audio.with_loop_duration(Duration::from_secs(2));
+        assert_eq!(audio.silence_after(), Some(Duration::from_secs(6)));
+    }
+
+    #[test]
+    fn silence_after_needs_a_positive_count_and_sound() {
+        let looping = Sound::Looping(LoopingSound::Call);
+        assert_eq!(Audio::new(looping.clone()).silence_after(), None);
+        let zero = Audio::new(looping.clone()).with_loop_count(0);
+        assert_eq!(zero.silence_after(), None);
+        let silent = Audio::new(looping).with_loop_count(2).with_silent();
+        assert_eq!(silent.silence_after(), None);
+        assert_eq!(
+            Audio::new(Sound::None).with_loop_count(2).silence_after(),
+            None
+        );
+    }
+
+    #[test]
+    fn zero_loop_count_is_invalid() {
+        let mut toast = Toast::new();
+        toast
+            .text1("Ringing")
+            .audio(Audio::new(Sound::Looping(LoopingSound::Call)).with_loop_count(0));
+        assert!(toast
+            .validation_issues()
+            .contains(&ValidationIssue::ZeroLoopCount));
+    }
+
+    #[test]
+    fn silenced_audio_has_no_loop_count() {
+        let audio = Audio::new(Sound::Looping(LoopingSound::Alarm2)).with_loop_count(2);
+        let silenced = audio.silenced();
+        assert_eq!(silenced.loop_count(), None);
+        assert_eq!(silenced.silence_after(), None);
+    }
+
+    #[test]
+    fn silent_audio_has_no_sound_or_looping() {
+        let silent = || vec![("silent", "true".to_string())];
+        let looping = Sound::Looping(LoopingSound::Call);
+        let cases = [
+            Audio::new(Sound::None),
+            Audio::new(Sound::None).with_looping(),
+            Audio::new(Sound::None).with_loop_count(3),
+            Audio::new(Sound::Default).with_silent(),
+            Audio::new(looping.clone()).with_looping().with_silent(),
+            Audio::new(looping).with_loop_count(3).silenced(),
+        ];
+        for audio in cases {
+            assert_eq!(audio.attributes(), silent(), "{:?}", audio);
+        }
+    }
+
+    #[test]
+    fn audible_audio_has_a_sound_and_looping() {
+        let call = "ms-winsoundevent:Notification.Looping.Call".to_string();
+        let attributes = |src: &str, looping: &str| {
+            vec![
+                ("src", src.to_string()),
+                ("loop", looping.to_string()),
+                ("silent", "false".to_string()),
+            ]
+        };
+        let looping = Sound::Looping(LoopingSound::Call);
+        assert_eq!(
+            Audio::new(looping.clone()).with_looping().attributes(),
+            attributes(&call, "true")
+        );
+        assert_eq!(
+            Audio::new(looping.clone()).with_loop_count(2).attributes(),
+            attributes(&call, "true")
+        );
+        assert_eq!(Audio::new(looping).attributes(), attributes(&call, "false"));
+        assert_eq!(
+            Audio::new(Sound::Default).attributes(),
+            attributes("ms-winsoundevent:Notification.Default", "false")
+        );
+    }
 }
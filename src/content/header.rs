@@ -1,6 +1,6 @@
 use windows::Data::Xml::Dom::XmlElement;
 
-use crate::hs;
+use crate::content::ElementExt;
 
 /// The type of activation this header will use when clicked.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -52,12 +52,19 @@ impl Header {
         self
     }
 
+    /// Replace the arguments with `f` applied to them, unless they are for a protocol.
+    pub(crate) fn map_arguments(&mut self, f: impl FnOnce(&str) -> String) {
+        if self.activation_type != Some(ActivationType::Protocol) {
+            self.arguments = f(&self.arguments);
+        }
+    }
+
     pub(crate) fn write_to_element(&self, el: &XmlElement) -> crate::Result<()> {
-        el.SetAttribute(&hs("id"), &hs(&self.id))?;
-        el.SetAttribute(&hs("title"), &hs(&self.title))?;
-        el.SetAttribute(&hs("arguments"), &hs(&self.arguments))?;
+        el.set("id", &self.id)?;
+        el.set("title", &self.title)?;
+        el.set("arguments", &self.arguments)?;
         if let Some(activation_type) = self.activation_type {
-            el.SetAttribute(&hs("activationType"), &hs(activation_type.as_str()))?;
+            el.set("activationType", activation_type.as_str())?;
         }
 
         Ok(())
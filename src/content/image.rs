@@ -1,9 +1,15 @@
-use std::path::Path;
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
 
 use url::Url;
 use windows::Data::Xml::Dom::XmlElement;
 
-use crate::hs;
+use crate::{
+    content::{ElementExt, RawAttributes},
+    limits, CacheConfig, ValidationIssue,
+};
 
 /// The placement of the image.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -39,15 +45,39 @@ impl ImageHintCrop {
 }
 
 /// Specifies an image used in the toast template.
+///
+/// # Remote images
+/// Windows downloads remote images before the toast is shown and has no placeholder
+/// or loading state for them: if the download is slow the toast is delayed,
+/// and if it fails the image is left out.
+/// When the artwork matters, download it yourself and use [`Image::new_local`] instead,
+/// showing a local placeholder meanwhile with
+/// [`ToastManager::show_with_placeholder`](crate::ToastManager::show_with_placeholder).
+///
+/// # Size
+/// Windows silently leaves out images larger than [`Image::MAX_FILE_SIZE`].
+/// [`Toast::validate`](crate::Toast::validate) reports local images over that size,
+/// resize them before showing them. With the `image-validation` feature, it also warns
+/// about hero and app logo images far from the recommended aspect ratio.
 #[derive(Debug, Clone)]
 pub struct Image {
     src: Url,
     placement: Option<ImagePlacement>,
     hint_crop: Option<ImageHintCrop>,
     alt: Option<String>,
+    pub(crate) raw_attributes: RawAttributes,
 }
 
 impl Image {
+    /// The attributes of the `<image>` element that this crate writes itself.
+    pub const RESERVED_ATTRIBUTES: &'static [&'static str] =
+        &["id", "src", "placement", "hint-crop", "alt"];
+
+    /// The largest image file Windows shows in a toast, 3 MB.
+    ///
+    /// This is [`limits::MAX_IMAGE_FILE_SIZE`](crate::limits::MAX_IMAGE_FILE_SIZE).
+    pub const MAX_FILE_SIZE: u64 = crate::limits::MAX_IMAGE_FILE_SIZE;
+
     /// Create an [`Image`] from a [`Url`].
     pub fn new(src: Url) -> Self {
         Self {
@@ -55,17 +85,50 @@ impl Image {
             placement: None,
             hint_crop: None,
             alt: None,
+            raw_attributes: RawAttributes::default(),
         }
     }
 
     /// Create an [`Image`] from a local path.
     ///
     /// This will return `Err` if the path is not absolute.
+    /// See [`path_to_media_url`](crate::path_to_media_url) for how the path is converted.
+    ///
+    /// If the file exists, its size is checked: returns [`ValidationIssue::ImageTooLarge`] if it
+    /// is larger than [`limits::MAX_IMAGE_FILE_SIZE`], which Windows would leave out, and the
+    /// I/O error if its size can't be read. A file that doesn't exist yet is accepted, and
+    /// reported by [`Toast::validate`](crate::Toast::validate) if it is still missing then.
     pub fn new_local(path: impl AsRef<Path>) -> crate::Result<Self> {
-        let url = Url::from_file_path(path).map_err(|_| crate::WinToastError::InvalidPath)?;
+        let path = path.as_ref();
+        let url = crate::path_to_media_url(path)?;
+        match path.metadata() {
+            Ok(metadata) if metadata.len() > limits::MAX_IMAGE_FILE_SIZE => {
+                let issue = ValidationIssue::ImageTooLarge(path.to_path_buf(), metadata.len());
+                return Err(issue.into());
+            }
+            Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e.into()),
+            _ => {}
+        }
         Ok(Self::new(url))
     }
 
+    /// Create an [`Image`] from the contents of an image file, such as a PNG downloaded by the app.
+    ///
+    /// Windows only shows images from files, so `bytes` are written to a file in the
+    /// directory of `cache` first, named after their hash and with `extension`,
+    /// e.g. `"png"`. Returns [`WinToastError::InvalidPath`](crate::WinToastError::InvalidPath)
+    /// if `extension` is not alphanumeric, and [`ValidationIssue::ImageTooLarge`] without
+    /// writing anything if `bytes` are larger than [`limits::MAX_IMAGE_FILE_SIZE`].
+    pub fn from_bytes(bytes: &[u8], extension: &str, cache: &CacheConfig) -> crate::Result<Self> {
+        let len = bytes.len() as u64;
+        if len > limits::MAX_IMAGE_FILE_SIZE {
+            let path = cache.path_for(bytes, extension)?;
+            return Err(ValidationIssue::ImageTooLarge(path, len).into());
+        }
+        let path = cache.store(bytes, extension)?;
+        Self::new_local(path)
+    }
+
     /// The placement of the image.
     pub fn with_placement(mut self, placement: ImagePlacement) -> Self {
         self.placement = Some(placement);
@@ -84,19 +147,190 @@ impl Image {
         self
     }
 
+    /// Set an arbitrary attribute, emitted verbatim after the attributes this crate supports.
+    ///
+    /// This is an escape hatch for attributes without typed support yet, such as new `hint-*` attributes.
+    /// Names that are not valid XML names, and the attributes written by this crate
+    /// (see [`Image::RESERVED_ATTRIBUTES`]), are reported by [`Toast::validate`](crate::Toast::validate).
+    pub fn with_raw_attribute(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.raw_attributes.set(name.into(), value.into());
+        self
+    }
+
+    /// The value of an attribute set with [`Image::with_raw_attribute`].
+    pub fn raw_attribute(&self, name: &str) -> Option<&str> {
+        self.raw_attributes.get(name)
+    }
+
+    /// The width and height of a local image, read from the header of its file.
+    ///
+    /// PNG, JPEG, GIF and BMP files are supported. Returns `Ok(None)` for remote images
+    /// and other formats, and an error if the file can't be read.
+    #[cfg(feature = "image-validation")]
+    pub fn dimensions(&self) -> crate::Result<Option<(u32, u32)>> {
+        match self.local_path() {
+            Some(path) => Ok(crate::dimensions::read(&path)?),
+            None => Ok(None),
+        }
+    }
+
+    pub(crate) fn placement(&self) -> Option<ImagePlacement> {
+        self.placement
+    }
+
+    /// The local file this image refers to, if it is not a remote image.
+    pub(crate) fn local_path(&self) -> Option<PathBuf> {
+        match self.src.scheme() {
+            "file" => self.src.to_file_path().ok(),
+            _ => None,
+        }
+    }
+
     pub(crate) fn write_to_element(&self, id: u8, el: &XmlElement) -> crate::Result<()> {
-        el.SetAttribute(&hs("id"), &hs(format!("{}", id)))?;
-        el.SetAttribute(&hs("src"), &hs(&self.src))?;
+        el.set("id", format!("{}", id))?;
+        el.set("src", &self.src)?;
         if let Some(placement) = self.placement {
-            el.SetAttribute(&hs("placement"), &hs(placement.as_str()))?;
+            el.set("placement", placement.as_str())?;
         }
         if let Some(crop) = self.hint_crop {
-            el.SetAttribute(&hs("hint-crop"), &hs(crop.as_str()))?;
+            el.set("hint-crop", crop.as_str())?;
         }
         if let Some(alt) = &self.alt {
-            el.SetAttribute(&hs("alt"), &hs(alt))?;
+            el.set("alt", alt)?;
         }
+        self.raw_attributes.write_to_element(el)?;
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{self, File};
+
+    use super::*;
+    use crate::{Toast, WinToastError};
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "winrt-toast-reborn-image-test-{}-{}",
+            std::process::id(),
+            name
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn validation_issues(image: Image) -> Vec<ValidationIssue> {
+        let mut toast = Toast::new();
+        toast.text1("Hello").image(1, image);
+        toast.validation_issues()
+    }
+
+    #[test]
+    fn new_local_rejects_files_that_are_too_large() {
+        let dir = temp_dir("large");
+        let path = dir.join("large.png");
+        let file = File::create(&path).unwrap();
+        file.set_len(limits::MAX_IMAGE_FILE_SIZE + 1).unwrap();
+
+        let result = Image::new_local(&path);
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(matches!(
+            result,
+            Err(WinToastError::Validation(ValidationIssue::ImageTooLarge(p, len)))
+                if p == path && len == limits::MAX_IMAGE_FILE_SIZE + 1
+        ));
+    }
+
+    #[test]
+    fn image_too_large_names_its_unit() {
+        let issue = ValidationIssue::ImageTooLarge(PathBuf::from("a.png"), 4_000_000);
+        assert_eq!(
+            issue.to_string(),
+            format!(
+                "The image file \"a.png\" is 4000000 bytes large, but at most {} bytes are supported",
+                limits::MAX_IMAGE_FILE_SIZE
+            )
+        );
+    }
+
+    #[test]
+    fn new_local_accepts_files_at_the_limit_and_missing_files() {
+        let dir = temp_dir("limit");
+        let path = dir.join("limit.png");
+        let file = File::create(&path).unwrap();
+        file.set_len(limits::MAX_IMAGE_FILE_SIZE).unwrap();
+
+        let result = Image::new_local(&path);
+        let missing = Image::new_local(dir.join("missing.png"));
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(result.is_ok());
+        assert!(missing.is_ok());
+    }
+
+    #[test]
+    fn from_bytes_rejects_large_images_without_writing_them() {
+        let dir = temp_dir("bytes");
+        let cache = CacheConfig::new(dir.join("cache"));
+        let bytes = vec![0; limits::MAX_IMAGE_FILE_SIZE as usize + 1];
+
+        let result = Image::from_bytes(&bytes, "png", &cache);
+        let written = cache.dir().exists();
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(matches!(
+            result,
+            Err(WinToastError::Validation(ValidationIssue::ImageTooLarge(
+                ..
+            )))
+        ));
+        assert!(!written);
+    }
+
+    #[test]
+    fn each_validation_checks_the_files_again() {
+        let dir = temp_dir("removed");
+        let path = dir.join("removed.png");
+        File::create(&path).unwrap();
+        let mut toast = Toast::new();
+        toast
+            .text1("Hello")
+            .image(1, Image::new_local(&path).unwrap());
+
+        let before = toast.validation_issues();
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(before.is_empty(), "{:?}", before);
+        assert_eq!(
+            toast.validation_issues(),
+            [ValidationIssue::MissingImageFile(path)]
+        );
+    }
+
+    #[test]
+    fn validation_reports_missing_files_as_missing() {
+        let dir = temp_dir("missing");
+        let path = dir.join("missing.png");
+        let issues = validation_issues(Image::new_local(&path).unwrap());
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(issues, [ValidationIssue::MissingImageFile(path)]);
+    }
+
+    #[test]
+    fn validation_reports_unreadable_files_with_their_error() {
+        let dir = temp_dir("unreadable");
+        // A path through a file is not a directory, so its metadata can't be read.
+        let parent = dir.join("file.png");
+        fs::write(&parent, b"").unwrap();
+        let path = parent.join("image.png");
+        let image = Image::new(Url::from_file_path(&path).unwrap());
+
+        let issues = validation_issues(image);
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(matches!(
+            &issues[..],
+            [ValidationIssue::UnreadableImageFile(p, kind)]
+                if *p == path && *kind != io::ErrorKind::NotFound
+        ));
+    }
+}
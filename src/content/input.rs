@@ -1,14 +1,22 @@
-use crate::hs;
+use crate::content::ElementExt;
 use windows::Data::Xml::Dom::XmlElement;
 
 /// Specifies an input field used in the toast template.
+///
+/// Windows reports the value of an input only when an action of the toast is activated,
+/// in [`ActivatedAction::values`](crate::ActivatedAction::values): there is no event for
+/// changes while the user types, and a toast can't enable or disable its buttons by itself.
+/// Check the submitted value in the `on_activated` callback instead, and show the toast again,
+/// e.g. with the input prefilled by [`Input::with_default_input`], if it is not acceptable.
+/// Binding an action to the input with [`Action::with_input_id`](crate::Action::with_input_id)
+/// at least lets the user submit with Enter.
 #[derive(Debug, Clone)]
 pub struct Input {
-    id: String,
-    type_: InputType,
+    pub(crate) id: String,
+    pub(crate) type_: InputType,
     place_holder: Option<String>,
     title: Option<String>,
-    default_input: Option<String>,
+    pub(crate) default_input: Option<String>,
 }
 
 impl Input {
@@ -24,34 +32,47 @@ impl Input {
     }
 
     /// The placeholder content of the input.
+    ///
+    /// Only text inputs have a placeholder; it is not emitted for selection inputs.
     pub fn with_placeholder(mut self, content: impl Into<String>) -> Self {
         self.place_holder = Some(content.into());
         self
     }
 
-    /// The title of the input.
+    /// The title of the input, shown as a label above it.
+    ///
+    /// This works for both text and selection inputs.
     pub fn with_title(mut self, title: impl Into<String>) -> Self {
         self.title = Some(title.into());
         self
     }
 
     /// The default input of the input.
+    ///
+    /// For a selection input, this is the id of the selection that is selected at first.
+    /// An id that none of the selections of the toast has leaves the input blank, which
+    /// [`Toast::validate`](crate::Toast::validate) reports as
+    /// [`ValidationIssue::UnknownDefaultSelection`](crate::ValidationIssue::UnknownDefaultSelection).
     pub fn with_default_input(mut self, default_input: impl Into<String>) -> Self {
         self.default_input = Some(default_input.into());
         self
     }
 
+    /// Write the attributes in the order of the toast schema: `id`, `type`, `title`,
+    /// `placeHolderContent` and `defaultInput`.
     pub(crate) fn write_to_element(&self, el: &XmlElement) -> crate::Result<()> {
-        el.SetAttribute(&hs("id"), &hs(&self.id))?;
-        el.SetAttribute(&hs("type"), &hs(self.type_.as_str()))?;
-        if let Some(place_holder_content) = &self.place_holder {
-            el.SetAttribute(&hs("placeHolderContent"), &hs(place_holder_content))?;
-        }
+        el.set("id", &self.id)?;
+        el.set("type", self.type_.as_str())?;
         if let Some(title) = &self.title {
-            el.SetAttribute(&hs("title"), &hs(title))?;
+            el.set("title", title)?;
+        }
+        if let Some(place_holder_content) = &self.place_holder {
+            if self.type_ == InputType::Text {
+                el.set("placeHolderContent", place_holder_content)?;
+            }
         }
         if let Some(default_input) = &self.default_input {
-            el.SetAttribute(&hs("defaultInput"), &hs(default_input))?;
+            el.set("defaultInput", default_input)?;
         }
 
         Ok(())
@@ -59,7 +80,7 @@ impl Input {
 }
 
 /// The type of input field.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum InputType {
     /// A text input field.
     Text,
@@ -92,10 +113,81 @@ impl Selection {
         }
     }
 
+    /// The id of the selection, reported as the value of the input when it is selected.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// The content of the selection, shown in the dropdown.
+    pub fn content(&self) -> &str {
+        &self.content
+    }
+
     pub(crate) fn write_to_element(&self, el: &XmlElement) -> crate::Result<()> {
-        el.SetAttribute(&hs("id"), &hs(&self.id))?;
-        el.SetAttribute(&hs("content"), &hs(&self.content))?;
+        el.set("id", &self.id)?;
+        el.set("content", &self.content)?;
 
         Ok(())
     }
 }
+
+/// An ordered set of [`Selection`]s with an optional default, to attach to any number of toasts
+/// with [`Toast::selection_input`](crate::Toast::selection_input).
+///
+/// ```
+/// # use winrt_toast_reborn::{content::input::InputType, Input, Selection, SelectionGroup, Toast};
+/// let meals = SelectionGroup::new()
+///     .with_selection(Selection::new("breakfast", "Breakfast"))
+///     .with_selection(Selection::new("lunch", "Lunch"))
+///     .with_default("lunch");
+///
+/// let mut toast = Toast::new();
+/// toast
+///     .text1("What would you like?")
+///     .selection_input(Input::new("meal", InputType::Selection), &meals);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SelectionGroup {
+    selections: Vec<Selection>,
+    default: Option<String>,
+}
+
+impl SelectionGroup {
+    /// Create an empty selection group.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a selection after the ones already in the group.
+    pub fn with_selection(mut self, selection: Selection) -> Self {
+        self.selections.push(selection);
+        self
+    }
+
+    /// The id of the selection that is selected at first.
+    ///
+    /// It should be the id of one of the selections, this is checked when the toast is validated.
+    pub fn with_default(mut self, id: impl Into<String>) -> Self {
+        self.default = Some(id.into());
+        self
+    }
+
+    /// The selections of the group, in order.
+    pub fn selections(&self) -> &[Selection] {
+        &self.selections
+    }
+
+    /// The id of the selection that is selected at first, if any.
+    pub fn default_id(&self) -> Option<&str> {
+        self.default.as_deref()
+    }
+}
+
+impl FromIterator<Selection> for SelectionGroup {
+    fn from_iter<I: IntoIterator<Item = Selection>>(iter: I) -> Self {
+        Self {
+            selections: iter.into_iter().collect(),
+            default: None,
+        }
+    }
+}
@@ -8,5 +8,94 @@ pub mod header;
 pub mod image;
 /// Input element
 pub mod input;
+/// Progress bar element
+pub mod progress;
+mod raw;
 /// Text element
 pub mod text;
+
+pub(crate) use raw::{is_xml_char, is_xml_name, sanitize_xml, RawAttributes};
+
+use windows::Data::Xml::Dom::{XmlDocument, XmlElement};
+
+use crate::{hs, WinToastError};
+
+/// Writing to an element, with errors that name the element and attribute, see [`WinToastError::Render`].
+pub(crate) trait ElementExt {
+    /// Set the attribute `name` to `value`.
+    fn set(&self, name: &str, value: impl AsRef<str>) -> crate::Result<()>;
+    /// Set the text content.
+    fn set_text(&self, text: &str) -> crate::Result<()>;
+    /// Create an element `name` and append it as the last child.
+    fn append_element(&self, name: &str) -> crate::Result<XmlElement>;
+    /// Create an element `name` and insert it as the first child.
+    fn prepend_element(&self, name: &str) -> crate::Result<XmlElement>;
+}
+
+impl ElementExt for XmlElement {
+    fn set(&self, name: &str, value: impl AsRef<str>) -> crate::Result<()> {
+        self.SetAttribute(&hs(name), &hs(value))
+            .map_err(|source| render_error(self, Some(name), source))
+    }
+
+    fn set_text(&self, text: &str) -> crate::Result<()> {
+        self.SetInnerText(&hs(text))
+            .map_err(|source| render_error(self, None, source))
+    }
+
+    fn append_element(&self, name: &str) -> crate::Result<XmlElement> {
+        let append = || {
+            let el = self.OwnerDocument()?.CreateElement(&hs(name))?;
+            self.AppendChild(&el)?;
+            Ok(el)
+        };
+        append().map_err(|source| create_error(name, source))
+    }
+
+    fn prepend_element(&self, name: &str) -> crate::Result<XmlElement> {
+        let prepend = || {
+            let el = self.OwnerDocument()?.CreateElement(&hs(name))?;
+            match self.FirstChild() {
+                Ok(first) => self.InsertBefore(&el, &first)?,
+                Err(_) => self.AppendChild(&el)?,
+            };
+            Ok(el)
+        };
+        prepend().map_err(|source| create_error(name, source))
+    }
+}
+
+/// Create the root element `name` of `doc`, with errors that name the element.
+pub(crate) fn append_root(doc: &XmlDocument, name: &str) -> crate::Result<XmlElement> {
+    let append = || {
+        let el = doc.CreateElement(&hs(name))?;
+        doc.AppendChild(&el)?;
+        Ok(el)
+    };
+    append().map_err(|source| create_error(name, source))
+}
+
+fn create_error(name: &str, source: windows::core::Error) -> WinToastError {
+    WinToastError::Render {
+        element: format!("<{}>", name),
+        attribute: None,
+        source,
+    }
+}
+
+fn render_error(
+    el: &XmlElement,
+    attribute: Option<&str>,
+    source: windows::core::Error,
+) -> WinToastError {
+    let tag = el.TagName().unwrap_or_default();
+    let element = match el.GetAttribute(&hs("id")) {
+        Ok(id) if !id.is_empty() => format!("<{} id={}>", tag, id),
+        _ => format!("<{}>", tag),
+    };
+    WinToastError::Render {
+        element,
+        attribute: attribute.map(str::to_string),
+        source,
+    }
+}
@@ -0,0 +1,105 @@
+use windows::Data::Xml::Dom::XmlElement;
+
+use crate::content::ElementExt;
+
+/// The value of a progress bar.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProgressValue {
+    /// The fraction of the work that is done, from `0.0` to `1.0`.
+    ///
+    /// Values outside that range are clamped when the XML is written, and NaN is written as `0.0`.
+    Fixed(f32),
+    /// The progress is unknown, so Windows shows an animation instead of a fraction.
+    Indeterminate,
+    /// The value stored under this key in the [`ToastData`](crate::ToastData) of the toast,
+    /// e.g. `"progressValue"` for the placeholder `{progressValue}`.
+    ///
+    /// This is how a shown progress bar moves: set the value with
+    /// [`ShowOptions::with_data`](crate::ShowOptions::with_data) or a template, and change it with
+    /// [`ToastManager::update`](crate::ToastManager::update). The value is a number from `0.0`
+    /// to `1.0`, or `indeterminate`.
+    Bound(String),
+}
+
+impl ProgressValue {
+    fn to_attribute(&self) -> String {
+        match self {
+            ProgressValue::Fixed(value) if value.is_nan() => "0".to_string(),
+            ProgressValue::Fixed(value) => value.clamp(0.0, 1.0).to_string(),
+            ProgressValue::Indeterminate => "indeterminate".to_string(),
+            ProgressValue::Bound(key) => format!("{{{}}}", key),
+        }
+    }
+}
+
+/// Specifies a progress bar shown in a toast, like the ones of file transfers in Windows.
+///
+/// The title, status and value string can contain data-bound placeholders like
+/// `{progressStatus}`, and the value can be bound with [`ProgressValue::Bound`], so the
+/// progress bar can be updated after the toast is shown.
+///
+/// See <https://learn.microsoft.com/en-us/windows/apps/design/shell/tiles-and-notifications/toast-progress-bar>
+#[derive(Debug, Clone)]
+pub struct Progress {
+    title: Option<String>,
+    value: ProgressValue,
+    value_string_override: Option<String>,
+    status: String,
+}
+
+impl Progress {
+    /// Create a progress bar with `value` and the `status` shown below it, e.g. `"Downloading..."`.
+    pub fn new(value: ProgressValue, status: impl Into<String>) -> Self {
+        Self {
+            title: None,
+            value,
+            value_string_override: None,
+            status: status.into(),
+        }
+    }
+
+    /// The title shown above the progress bar.
+    pub fn with_title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// The text shown instead of the percentage, e.g. `"3/10 files"`.
+    pub fn with_value_string_override(mut self, value_string: impl Into<String>) -> Self {
+        self.value_string_override = Some(value_string.into());
+        self
+    }
+
+    pub(crate) fn write_to_element(&self, el: &XmlElement) -> crate::Result<()> {
+        if let Some(title) = &self.title {
+            el.set("title", title)?;
+        }
+        el.set("value", self.value.to_attribute())?;
+        if let Some(value_string) = &self.value_string_override {
+            el.set("valueStringOverride", value_string)?;
+        }
+        el.set("status", &self.status)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixed_values_are_clamped() {
+        assert_eq!(ProgressValue::Fixed(0.25).to_attribute(), "0.25");
+        assert_eq!(ProgressValue::Fixed(1.5).to_attribute(), "1");
+        assert_eq!(ProgressValue::Fixed(-1.0).to_attribute(), "0");
+        assert_eq!(ProgressValue::Fixed(f32::NAN).to_attribute(), "0");
+    }
+
+    #[test]
+    fn indeterminate_and_bound_values() {
+        assert_eq!(ProgressValue::Indeterminate.to_attribute(), "indeterminate");
+        let bound = ProgressValue::Bound("progressValue".to_string());
+        assert_eq!(bound.to_attribute(), "{progressValue}");
+    }
+}
@@ -0,0 +1,155 @@
+use std::borrow::Cow;
+
+use windows::Data::Xml::Dom::XmlElement;
+
+use crate::content::ElementExt;
+
+/// Attributes set through the raw attribute escape hatches, emitted verbatim.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct RawAttributes(Vec<(String, String)>);
+
+impl RawAttributes {
+    pub(crate) const fn new() -> Self {
+        Self(Vec::new())
+    }
+
+    /// Set `name` to `value`, replacing an earlier value of the same attribute.
+    pub(crate) fn set(&mut self, name: String, value: String) {
+        match self.0.iter_mut().find(|(n, _)| *n == name) {
+            Some((_, v)) => *v = value,
+            None => self.0.push((name, value)),
+        }
+    }
+
+    /// Set the attributes of `other`, replacing earlier values of the same attributes.
+    pub(crate) fn extend(&mut self, other: &RawAttributes) {
+        for (name, value) in &other.0 {
+            self.set(name.clone(), value.clone());
+        }
+    }
+
+    pub(crate) fn get(&self, name: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// The names that are not valid XML attribute names.
+    pub(crate) fn invalid_names(&self) -> impl Iterator<Item = &str> {
+        self.0
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .filter(|name| !is_xml_name(name))
+    }
+
+    /// The names that are among `reserved`, the attributes written by this crate itself.
+    pub(crate) fn reserved_names<'a>(
+        &'a self,
+        reserved: &'a [&str],
+    ) -> impl Iterator<Item = &'a str> + 'a {
+        self.0
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .filter(|name| reserved.contains(name))
+    }
+
+    pub(crate) fn write_to_element(&self, el: &XmlElement) -> crate::Result<()> {
+        for (name, value) in &self.0 {
+            el.set(name, value)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Whether `c` is allowed in an XML 1.0 document.
+pub(crate) fn is_xml_char(c: char) -> bool {
+    matches!(c, '\t' | '\n' | '\r' | '\u{20}'..='\u{D7FF}' | '\u{E000}'..='\u{FFFD}' | '\u{10000}'..)
+}
+
+/// `s` with the characters that XML can't hold replaced by U+FFFD, borrowed if there are none.
+pub(crate) fn sanitize_xml(s: &str) -> Cow<'_, str> {
+    if s.chars().all(is_xml_char) {
+        return Cow::Borrowed(s);
+    }
+    let sanitized = s
+        .chars()
+        .map(|c| {
+            if is_xml_char(c) {
+                c
+            } else {
+                char::REPLACEMENT_CHARACTER
+            }
+        })
+        .collect();
+    Cow::Owned(sanitized)
+}
+
+/// Whether `name` is a valid XML name, such as `hint-foo` or `ns:attr`.
+pub(crate) fn is_xml_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    let Some(first) = chars.next() else {
+        return false;
+    };
+    (first.is_alphabetic() || first == '_' || first == ':')
+        && chars.all(|c| c.is_alphanumeric() || matches!(c, '-' | '.' | '_' | ':'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sanitize_xml_borrows_valid_text() {
+        let text = "Tabs\tand newlines\r\n, emoji 🎉 and \u{E000}";
+        assert!(matches!(sanitize_xml(text), Cow::Borrowed(t) if t == text));
+    }
+
+    #[test]
+    fn sanitize_xml_replaces_characters_xml_cannot_hold() {
+        assert_eq!(
+            sanitize_xml("a\0b\u{8}c\u{1B}[0m"),
+            "a\u{FFFD}b\u{FFFD}c\u{FFFD}[0m"
+        );
+        assert_eq!(sanitize_xml("\u{FFFE}\u{FFFF}"), "\u{FFFD}\u{FFFD}");
+    }
+
+    #[test]
+    fn xml_names() {
+        for name in ["hint-foo", "ns:attr", "_private", "a.b", "größe"] {
+            assert!(is_xml_name(name), "{}", name);
+        }
+        for name in ["", "1st", "-hint", "has space", "a=b", "quote\""] {
+            assert!(!is_xml_name(name), "{}", name);
+        }
+    }
+
+    #[test]
+    fn set_replaces_earlier_values_in_place() {
+        let mut attributes = RawAttributes::new();
+        attributes.set("a".to_string(), "1".to_string());
+        attributes.set("b".to_string(), "2".to_string());
+        attributes.set("a".to_string(), "3".to_string());
+        assert_eq!(attributes.get("a"), Some("3"));
+        assert_eq!(
+            attributes.0,
+            [("a".into(), "3".into()), ("b".into(), "2".into())]
+        );
+    }
+
+    #[test]
+    fn invalid_and_reserved_names_are_found() {
+        let mut attributes = RawAttributes::new();
+        attributes.set("hint-ok".to_string(), String::new());
+        attributes.set("bad name".to_string(), String::new());
+        attributes.set("launch".to_string(), String::new());
+        assert_eq!(attributes.invalid_names().collect::<Vec<_>>(), ["bad name"]);
+        assert_eq!(
+            attributes
+                .reserved_names(&["launch", "duration"])
+                .collect::<Vec<_>>(),
+            ["launch"]
+        );
+    }
+}
@@ -1,6 +1,8 @@
+use std::borrow::Cow;
+
 use windows::Data::Xml::Dom::XmlElement;
 
-use crate::hs;
+use crate::content::{sanitize_xml, ElementExt, RawAttributes};
 
 /// The placement of the text.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -23,19 +25,156 @@ impl TextPlacement {
     }
 }
 
+/// The style of the text, controlling its size, color and weight.
+///
+/// Styles only take effect on text inside adaptive groups and subgroups;
+/// Windows ignores them on the top-level text elements of a toast.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(missing_docs)]
+pub enum TextStyle {
+    Caption,
+    CaptionSubtle,
+    Body,
+    BodySubtle,
+    Base,
+    BaseSubtle,
+    Subtitle,
+    SubtitleSubtle,
+    Title,
+    TitleSubtle,
+    TitleNumeral,
+    Subheader,
+    SubheaderSubtle,
+    SubheaderNumeral,
+    Header,
+    HeaderSubtle,
+    HeaderNumeral,
+}
+
+impl TextStyle {
+    fn as_str(&self) -> &'static str {
+        match self {
+            TextStyle::Caption => "caption",
+            TextStyle::CaptionSubtle => "captionSubtle",
+            TextStyle::Body => "body",
+            TextStyle::BodySubtle => "bodySubtle",
+            TextStyle::Base => "base",
+            TextStyle::BaseSubtle => "baseSubtle",
+            TextStyle::Subtitle => "subtitle",
+            TextStyle::SubtitleSubtle => "subtitleSubtle",
+            TextStyle::Title => "title",
+            TextStyle::TitleSubtle => "titleSubtle",
+            TextStyle::TitleNumeral => "titleNumeral",
+            TextStyle::Subheader => "subheader",
+            TextStyle::SubheaderSubtle => "subheaderSubtle",
+            TextStyle::SubheaderNumeral => "subheaderNumeral",
+            TextStyle::Header => "header",
+            TextStyle::HeaderSubtle => "headerSubtle",
+            TextStyle::HeaderNumeral => "headerNumeral",
+        }
+    }
+}
+
+/// The horizontal alignment of the text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextAlign {
+    /// The alignment is determined by the system.
+    Auto,
+    /// Align to the left.
+    Left,
+    /// Center the text.
+    Center,
+    /// Align to the right.
+    Right,
+}
+
+impl TextAlign {
+    fn as_str(&self) -> &'static str {
+        match self {
+            TextAlign::Auto => "auto",
+            TextAlign::Left => "left",
+            TextAlign::Center => "center",
+            TextAlign::Right => "right",
+        }
+    }
+}
+
 /// Specifies text used in the toast template.
+///
+/// Attributes that are not set are not emitted, so Windows applies its defaults.
+///
+/// Toast text is plain text. Windows supports no inline markup such as HTML or Markdown, and
+/// no inline hyperlinks: markup is shown literally, and URLs are not clickable. To open a link,
+/// add an [`Action`](crate::Action) with
+/// [`ActivationType::Protocol`](crate::content::action::ActivationType::Protocol) and the URL
+/// as its arguments.
+///
+/// Characters that XML can't hold, i.e. control characters other than tab and line breaks,
+/// such as NUL, and the noncharacters U+FFFE and U+FFFF, are replaced with U+FFFD `�` when
+/// the toast is written, so arbitrary user text such as chat messages or log lines can't break
+/// the toast. Lone surrogates can't occur in a Rust string; text decoded with
+/// [`String::from_utf16_lossy`] has them replaced with U+FFFD already.
+///
+/// Text from `&'static str` can be created without allocating with [`Text::from_static`],
+/// also in `const` items.
 #[derive(Debug, Clone)]
 pub struct Text {
-    content: String,
+    pub(crate) content: Cow<'static, str>,
     placement: Option<TextPlacement>,
+    lang: Option<String>,
+    style: Option<TextStyle>,
+    align: Option<TextAlign>,
+    wrap: Option<bool>,
+    max_lines: Option<u32>,
+    min_lines: Option<u32>,
+    call_scenario_center_align: Option<bool>,
+    pub(crate) raw_attributes: RawAttributes,
 }
 
 impl Text {
+    /// The attributes of the `<text>` element that this crate writes itself.
+    pub const RESERVED_ATTRIBUTES: &'static [&'static str] = &[
+        "id",
+        "lang",
+        "placement",
+        "hint-style",
+        "hint-align",
+        "hint-wrap",
+        "hint-maxLines",
+        "hint-minLines",
+        "hint-callScenarioCenterAlign",
+    ];
+
     /// Create a new text element.
     pub fn new(content: impl Into<String>) -> Self {
+        Self::with_content(Cow::Owned(content.into()))
+    }
+
+    /// Create a text element from a static string, without allocating.
+    ///
+    /// ```
+    /// # use winrt_toast_reborn::Text;
+    /// const TITLE: Text = Text::from_static("Backup finished");
+    /// ```
+    ///
+    /// Only creating the text is free: like any text, it is copied into the XML document
+    /// of the toast when the toast is shown.
+    pub const fn from_static(content: &'static str) -> Self {
+        Self::with_content(Cow::Borrowed(content))
+    }
+
+    const fn with_content(content: Cow<'static, str>) -> Self {
         Self {
-            content: content.into(),
+            content,
             placement: None,
+            lang: None,
+            style: None,
+            align: None,
+            wrap: None,
+            max_lines: None,
+            min_lines: None,
+            call_scenario_center_align: None,
+            raw_attributes: RawAttributes::new(),
         }
     }
 
@@ -50,12 +189,104 @@ impl Text {
         self.with_placement(TextPlacement::Attribution)
     }
 
+    /// The target locale of the text, as a BCP-47 language tag such as `"en-US"`.
+    ///
+    /// This affects how the text is rendered, e.g. which font is used for CJK text.
+    pub fn with_lang(mut self, lang: impl Into<String>) -> Self {
+        self.lang = Some(lang.into());
+        self
+    }
+
+    /// The style of the text.
+    ///
+    /// Only takes effect on text inside adaptive groups and subgroups.
+    pub fn with_style(mut self, style: TextStyle) -> Self {
+        self.style = Some(style);
+        self
+    }
+
+    /// The horizontal alignment of the text.
+    pub fn with_align(mut self, align: TextAlign) -> Self {
+        self.align = Some(align);
+        self
+    }
+
+    /// Whether the text wraps onto multiple lines.
+    ///
+    /// Only takes effect on text inside adaptive groups and subgroups;
+    /// top-level text always wraps up to its maximum number of lines.
+    pub fn with_wrap(mut self, wrap: bool) -> Self {
+        self.wrap = Some(wrap);
+        self
+    }
+
+    /// The maximum number of lines the text is allowed to display.
+    pub fn with_max_lines(mut self, max_lines: u32) -> Self {
+        self.max_lines = Some(max_lines);
+        self
+    }
+
+    /// The minimum number of lines the text must display.
+    ///
+    /// Only takes effect on text inside adaptive groups and subgroups.
+    pub fn with_min_lines(mut self, min_lines: u32) -> Self {
+        self.min_lines = Some(min_lines);
+        self
+    }
+
+    /// Center the text when the toast uses the
+    /// [`Scenario::IncomingCall`](crate::Scenario::IncomingCall) layout.
+    pub fn with_call_scenario_center_align(mut self, center: bool) -> Self {
+        self.call_scenario_center_align = Some(center);
+        self
+    }
+
+    /// Set an arbitrary attribute, emitted verbatim after the attributes this crate supports.
+    ///
+    /// This is an escape hatch for attributes without typed support yet, such as new `hint-*` attributes.
+    /// Names that are not valid XML names, and the attributes written by this crate
+    /// (see [`Text::RESERVED_ATTRIBUTES`]), are reported by [`Toast::validate`](crate::Toast::validate).
+    pub fn with_raw_attribute(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.raw_attributes.set(name.into(), value.into());
+        self
+    }
+
+    /// The value of an attribute set with [`Text::with_raw_attribute`].
+    pub fn raw_attribute(&self, name: &str) -> Option<&str> {
+        self.raw_attributes.get(name)
+    }
+
     pub(crate) fn write_to_element(&self, id: u8, el: &XmlElement) -> crate::Result<()> {
-        el.SetAttribute(&hs("id"), &hs(format!("{}", id)))?;
-        el.SetInnerText(&hs(&self.content))?;
+        el.set("id", format!("{}", id))?;
+        el.set_text(&sanitize_xml(&self.content))?;
+        if let Some(lang) = &self.lang {
+            el.set("lang", lang)?;
+        }
         if let Some(placement) = self.placement {
-            el.SetAttribute(&hs("placement"), &hs(placement.as_str()))?;
+            el.set("placement", placement.as_str())?;
+        }
+        if let Some(style) = self.style {
+            el.set("hint-style", style.as_str())?;
+        }
+        if let Some(align) = self.align {
+            el.set("hint-align", align.as_str())?;
+        }
+        if let Some(wrap) = self.wrap {
+            el.set("hint-wrap", if wrap { "true" } else { "false" })?;
+        }
+        if let Some(max_lines) = self.max_lines {
+            el.set("hint-maxLines", max_lines.to_string())?;
+        }
+        if let Some(min_lines) = self.min_lines {
+            el.set("hint-minLines", min_lines.to_string())?;
+        }
+        if let Some(center) = self.call_scenario_center_align {
+            el.set(
+                "hint-callScenarioCenterAlign",
+                if center { "true" } else { "false" },
+            )?;
         }
+        self.raw_attributes.write_to_element(el)?;
 
         Ok(())
     }
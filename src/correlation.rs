@@ -0,0 +1,169 @@
+use url::form_urlencoded;
+use windows::{core::HSTRING, UI::Notifications::ToastNotification};
+
+use crate::{hs, Toast};
+
+/// Separates the arguments of the app from the embedded correlation id.
+///
+/// The private use character keeps the marker apart from arguments that apps write,
+/// and the id after it is form-encoded, so it never contains the marker itself.
+const MARKER: &str = "\u{F8FF}winrt-toast-reborn.correlation?";
+
+/// The key of the notification data entry holding the correlation id.
+const DATA_KEY: &str = "winrt-toast-reborn.correlation-id";
+
+/// `arguments` with the correlation `id` appended.
+fn embed(arguments: &str, id: &str) -> String {
+    let encoded = form_urlencoded::Serializer::new(String::new())
+        .append_pair("id", id)
+        .finish();
+    format!("{}{}{}", arguments, MARKER, encoded)
+}
+
+/// Split activation `arguments` into the arguments of the app and the embedded correlation id.
+pub(crate) fn split(arguments: &str) -> (&str, Option<String>) {
+    let Some(at) = arguments.rfind(MARKER) else {
+        return (arguments, None);
+    };
+    let id = form_urlencoded::parse(&arguments.as_bytes()[at + MARKER.len()..])
+        .find(|(key, _)| key == "id")
+        .map(|(_, id)| id.into_owned());
+    (&arguments[..at], id)
+}
+
+/// The notification data entry holding the correlation id of `toast`, if it has one.
+pub(crate) fn data_entry(toast: &Toast) -> Option<(HSTRING, String)> {
+    let id = toast.correlation_id.as_ref()?;
+    Some((hs(DATA_KEY), id.clone()))
+}
+
+/// The correlation id of a shown notification.
+///
+/// The id is read from the data of the notification, or else from the launch arguments it
+/// is embedded in, since Windows keeps no data for scheduled toasts.
+pub(crate) fn from_notification(notification: &ToastNotification) -> Option<String> {
+    let stored = notification
+        .Data()
+        .and_then(|data| data.Values())
+        .and_then(|values| values.Lookup(&hs(DATA_KEY)));
+    if let Ok(id) = stored {
+        return Some(id.to_string());
+    }
+    let launch = notification
+        .Content()
+        .and_then(|doc| doc.DocumentElement())
+        .and_then(|toast| toast.GetAttribute(&hs("launch")))
+        .ok()?;
+    split(&launch.to_string()).1
+}
+
+impl Toast {
+    /// This toast with its correlation id appended to the arguments of everything that
+    /// activates the app: the launch string, the header and the buttons.
+    ///
+    /// Returns `None` if the toast has no correlation id.
+    pub(crate) fn with_correlation_embedded(&self) -> Option<Toast> {
+        let mut toast = self.clone();
+        let id = toast.correlation_id.take()?;
+        toast.launch = Some(embed(toast.launch.as_deref().unwrap_or_default(), &id));
+        if let Some(header) = &mut toast.header {
+            header.map_arguments(|arguments| embed(arguments, &id));
+        }
+        for action in &mut toast.actions {
+            action.map_arguments(|arguments| embed(arguments, &id));
+        }
+        Some(toast)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{content::action::ActivationType, Action, Header};
+
+    const ID: &str = "order 42&x=?\u{F8FF}";
+
+    fn embedded(toast: &mut Toast) -> Toast {
+        toast
+            .correlation_id(ID)
+            .with_correlation_embedded()
+            .unwrap()
+    }
+
+    fn header_arguments(toast: &mut Toast) -> String {
+        let mut arguments = String::new();
+        toast.header.as_mut().unwrap().map_arguments(|a| {
+            arguments = a.to_string();
+            arguments.clone()
+        });
+        arguments
+    }
+
+    #[test]
+    fn splits_arguments_without_id() {
+        assert_eq!(split("action=open"), ("action=open", None));
+        assert_eq!(split(""), ("", None));
+    }
+
+    #[test]
+    fn round_trips_body_activations() {
+        let mut toast = Toast::new();
+        toast.launch("action=open&item=7");
+        let toast = embedded(&mut toast);
+
+        let launch = toast.launch.as_deref().unwrap();
+        assert_eq!(split(launch), ("action=open&item=7", Some(ID.to_string())));
+    }
+
+    #[test]
+    fn round_trips_body_activations_without_launch() {
+        let toast = embedded(&mut Toast::new());
+        assert_eq!(
+            split(toast.launch.as_deref().unwrap()),
+            ("", Some(ID.to_string()))
+        );
+    }
+
+    #[test]
+    fn round_trips_button_activations() {
+        let mut toast = Toast::new();
+        toast
+            .action(Action::new("Reply", "action=reply", ""))
+            .action(Action::new("Archive", "", ""));
+        let toast = embedded(&mut toast);
+
+        let split: Vec<_> = toast.actions.iter().map(|a| split(a.arguments())).collect();
+        assert_eq!(
+            split,
+            [
+                ("action=reply", Some(ID.to_string())),
+                ("", Some(ID.to_string()))
+            ]
+        );
+    }
+
+    #[test]
+    fn leaves_protocol_buttons_alone() {
+        let url = "https://example.com/?a=1";
+        let mut toast = Toast::new();
+        toast.action(Action::new("Open", url, "").with_activation_type(ActivationType::Protocol));
+        let toast = embedded(&mut toast);
+
+        assert_eq!(toast.actions[0].arguments(), url);
+    }
+
+    #[test]
+    fn round_trips_header_activations() {
+        let mut toast = Toast::new();
+        toast.header(Header::new("chat", "Chat", "conversation=5"));
+        let mut toast = embedded(&mut toast);
+
+        let arguments = header_arguments(&mut toast);
+        assert_eq!(split(&arguments), ("conversation=5", Some(ID.to_string())));
+    }
+
+    #[test]
+    fn embeds_nothing_without_id() {
+        assert!(Toast::new().with_correlation_embedded().is_none());
+    }
+}
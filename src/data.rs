@@ -0,0 +1,81 @@
+use std::collections::HashMap;
+
+use windows::UI::Notifications::NotificationData;
+
+use crate::hs;
+
+/// Values for the data-bound placeholders of a toast.
+///
+/// Text content and other supported attributes can contain placeholders like `{title}`,
+/// which Windows replaces with the value stored under the same key, here `"title"`.
+///
+/// # Example
+/// ```rust
+/// # use winrt_toast_reborn::{Toast, ToastData};
+/// let mut toast = Toast::new();
+/// toast.text1("{title}");
+///
+/// let mut data = ToastData::new();
+/// data.set("title", "Downloading");
+/// ```
+///
+/// Updates sent with [`ToastManager::update`](crate::ToastManager::update) may arrive out of
+/// order. Give each update a higher sequence number, e.g. with
+/// [`ToastData::increment_sequence_number`] on the same `ToastData`, so Windows drops the ones
+/// older than the data the toast already shows.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ToastData {
+    pub(crate) values: HashMap<String, String>,
+    sequence_number: Option<u32>,
+}
+
+impl ToastData {
+    /// Creates an empty set of values.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the value of the placeholder named `key`.
+    pub fn set(&mut self, key: impl Into<String>, value: impl Into<String>) -> &mut ToastData {
+        self.values.insert(key.into(), value.into());
+        self
+    }
+
+    /// The value of the placeholder named `key`.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.values.get(key).map(String::as_str)
+    }
+
+    /// Set the sequence number of the values.
+    ///
+    /// Windows ignores values whose sequence number is lower than that of the values the
+    /// toast already has. `0`, and no sequence number at all, always replace the values.
+    pub fn set_sequence_number(&mut self, sequence_number: u32) -> &mut ToastData {
+        self.sequence_number = Some(sequence_number);
+        self
+    }
+
+    /// Increase the sequence number by one, starting at `1`, see [`ToastData::set_sequence_number`].
+    pub fn increment_sequence_number(&mut self) -> &mut ToastData {
+        let next = self.sequence_number.unwrap_or_default().saturating_add(1);
+        self.set_sequence_number(next)
+    }
+
+    /// The sequence number of the values, if one was set.
+    pub fn sequence_number(&self) -> Option<u32> {
+        self.sequence_number
+    }
+
+    pub(crate) fn to_winrt(&self) -> crate::Result<NotificationData> {
+        let data = NotificationData::new()?;
+        if let Some(sequence_number) = self.sequence_number {
+            data.SetSequenceNumber(sequence_number)?;
+        }
+        let values = data.Values()?;
+        for (key, value) in &self.values {
+            values.Insert(&hs(key), &hs(value))?;
+        }
+
+        Ok(data)
+    }
+}
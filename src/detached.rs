@@ -0,0 +1,281 @@
+use std::{
+    panic::AssertUnwindSafe,
+    sync::mpsc::{self, Receiver, Sender, TryRecvError},
+    thread,
+};
+
+use crate::{Toast, ToastEvent, ToastHandle, WinToastError};
+
+/// Work for the worker thread of [`ToastManager::show_detached`].
+pub(crate) enum Job {
+    /// Show the toast and send the result.
+    Show(Box<Toast>, Reply),
+    /// Signal that all jobs queued before this one are done.
+    Barrier(Sender<()>),
+}
+
+/// Where the worker sends the result of showing a toast.
+pub(crate) enum Reply {
+    /// To a [`ShowTicket`].
+    Ticket(Sender<crate::Result<ToastHandle>>),
+    /// To the event channel of [`ToastManager::show_detached_events`](crate::ToastManager::show_detached_events),
+    /// which also receives the events of the toast.
+    Events(Sender<ToastEvent>),
+}
+
+impl Reply {
+    fn events(&self) -> Option<&Sender<ToastEvent>> {
+        match self {
+            Reply::Ticket(_) => None,
+            Reply::Events(events) => Some(events),
+        }
+    }
+
+    fn send(self, shown: crate::Result<ToastHandle>) {
+        match self {
+            Reply::Ticket(ticket) => {
+                let _ = ticket.send(shown);
+            }
+            Reply::Events(events) => {
+                let _ = events.send(shown.map_or_else(ToastEvent::Failed, ToastEvent::Shown));
+            }
+        }
+    }
+}
+
+/// Start the worker thread that shows toasts with `show`, in the order they are sent.
+///
+/// `show` is given the event channel of the toast, if there is one.
+/// The worker exits once the returned sender and all its clones are dropped.
+pub(crate) fn spawn<F>(show: F) -> Sender<Job>
+where
+    F: Fn(&Toast, Option<&Sender<ToastEvent>>) -> crate::Result<ToastHandle> + Send + 'static,
+{
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        for job in receiver {
+            match job {
+                Job::Show(toast, reply) => {
+                    let shown =
+                        std::panic::catch_unwind(AssertUnwindSafe(|| show(&toast, reply.events())));
+                    reply.send(shown.unwrap_or(Err(WinToastError::ShowAborted)));
+                }
+                Job::Barrier(done) => {
+                    let _ = done.send(());
+                }
+            }
+        }
+    });
+    sender
+}
+
+/// The pending result of a toast shown with [`ToastManager::show_detached`].
+#[derive(Debug)]
+pub struct ShowTicket {
+    receiver: Receiver<crate::Result<ToastHandle>>,
+}
+
+impl ShowTicket {
+    pub(crate) fn new(receiver: Receiver<crate::Result<ToastHandle>>) -> Self {
+        Self { receiver }
+    }
+
+    /// Block until the toast was shown, and return the result of showing it.
+    pub fn wait(self) -> crate::Result<ToastHandle> {
+        self.receiver
+            .recv()
+            .unwrap_or(Err(WinToastError::ShowAborted))
+    }
+
+    /// The result of showing the toast if it is done, otherwise this ticket back.
+    pub fn try_wait(self) -> std::result::Result<crate::Result<ToastHandle>, Self> {
+        match self.receiver.try_recv() {
+            Ok(result) => Ok(result),
+            Err(TryRecvError::Empty) => Err(self),
+            Err(TryRecvError::Disconnected) => Ok(Err(WinToastError::ShowAborted)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use windows::core::HSTRING;
+
+    use super::*;
+
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    /// A worker that records the tag of each toast it shows, after `delay`.
+    fn recording_worker(delay: Duration) -> (Sender<Job>, Arc<Mutex<Vec<String>>>) {
+        let shown = Arc::new(Mutex::new(Vec::new()));
+        let recorded = shown.clone();
+        let worker = spawn(move |toast: &Toast, _: Option<&Sender<ToastEvent>>| {
+            thread::sleep(delay);
+            let tag = toast.tag.clone().unwrap_or_default();
+            if tag == "panic" {
+                panic!("show failed");
+            }
+            recorded.lock().unwrap().push(tag);
+            Ok(ToastHandle::new(HSTRING::new(), None, None, None, None))
+        });
+        (worker, shown)
+    }
+
+    fn show(worker: &Sender<Job>, tag: &str) -> ShowTicket {
+        let (sender, receiver) = mpsc::channel();
+        let mut toast = Toast::new();
+        toast.tag(tag);
+        worker
+            .send(Job::Show(Box::new(toast), Reply::Ticket(sender)))
+            .unwrap();
+        ShowTicket::new(receiver)
+    }
+
+    fn show_to(worker: &Sender<Job>, tag: &str, events: &Sender<ToastEvent>) {
+        let mut toast = Toast::new();
+        toast.tag(tag);
+        let reply = Reply::Events(events.clone());
+        worker.send(Job::Show(Box::new(toast), reply)).unwrap();
+    }
+
+    fn barrier(worker: &Sender<Job>) -> Receiver<()> {
+        let (done, wait) = mpsc::channel();
+        worker.send(Job::Barrier(done)).unwrap();
+        wait
+    }
+
+    #[test]
+    fn shows_in_call_order() {
+        let (worker, shown) = recording_worker(Duration::ZERO);
+        let tickets: Vec<_> = (0..20).map(|i| show(&worker, &i.to_string())).collect();
+        for ticket in tickets {
+            ticket.wait().unwrap();
+        }
+        let expected: Vec<_> = (0..20).map(|i| i.to_string()).collect();
+        assert_eq!(*shown.lock().unwrap(), expected);
+    }
+
+    #[test]
+    fn each_thread_keeps_its_order() {
+        let (worker, shown) = recording_worker(Duration::from_millis(1));
+        let threads: Vec<_> = (0..4)
+            .map(|t| {
+                let worker = worker.clone();
+                thread::spawn(move || {
+                    for i in 0..10 {
+                        let _ = show(&worker, &format!("{t}-{i}"));
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        barrier(&worker).recv_timeout(TIMEOUT).unwrap();
+
+        let shown = shown.lock().unwrap();
+        assert_eq!(shown.len(), 40);
+        for t in 0..4 {
+            let order: Vec<_> = shown
+                .iter()
+                .filter_map(|tag| tag.strip_prefix(&format!("{t}-")))
+                .collect();
+            let expected: Vec<_> = (0..10).map(|i| i.to_string()).collect();
+            assert_eq!(order, expected);
+        }
+    }
+
+    #[test]
+    fn barrier_waits_for_earlier_shows_only() {
+        let (worker, shown) = recording_worker(Duration::from_millis(20));
+        let _ = show(&worker, "before");
+        let wait = barrier(&worker);
+        let after = show(&worker, "after");
+
+        wait.recv_timeout(TIMEOUT).unwrap();
+        assert_eq!(*shown.lock().unwrap(), ["before"]);
+        after.wait().unwrap();
+        assert_eq!(*shown.lock().unwrap(), ["before", "after"]);
+    }
+
+    #[test]
+    fn barrier_races_with_shows_without_deadlock() {
+        let (worker, shown) = recording_worker(Duration::from_millis(1));
+        let showing: Vec<_> = (0..4)
+            .map(|t| {
+                let worker = worker.clone();
+                thread::spawn(move || {
+                    (0..10)
+                        .map(|i| show(&worker, &format!("{t}-{i}")))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        let waiting: Vec<_> = (0..4)
+            .map(|_| {
+                let worker = worker.clone();
+                thread::spawn(move || barrier(&worker).recv_timeout(TIMEOUT))
+            })
+            .collect();
+
+        for thread in waiting {
+            thread.join().unwrap().unwrap();
+        }
+        for thread in showing {
+            for ticket in thread.join().unwrap() {
+                ticket.wait().unwrap();
+            }
+        }
+        assert_eq!(shown.lock().unwrap().len(), 40);
+    }
+
+    #[test]
+    fn panicking_show_aborts_only_its_toast() {
+        let (worker, shown) = recording_worker(Duration::ZERO);
+        let failed = show(&worker, "panic");
+        let next = show(&worker, "next");
+
+        assert!(matches!(failed.wait(), Err(WinToastError::ShowAborted)));
+        next.wait().unwrap();
+        assert_eq!(*shown.lock().unwrap(), ["next"]);
+    }
+
+    #[test]
+    fn reports_completion_through_the_event_channel() {
+        let (worker, shown) = recording_worker(Duration::ZERO);
+        let (events, received) = mpsc::channel();
+        show_to(&worker, "first", &events);
+        show_to(&worker, "panic", &events);
+        let ticket = show(&worker, "ticket");
+        show_to(&worker, "last", &events);
+        drop(events);
+
+        let received: Vec<_> = received.iter().collect();
+        assert!(matches!(
+            received[..],
+            [
+                ToastEvent::Shown(_),
+                ToastEvent::Failed(WinToastError::ShowAborted),
+                ToastEvent::Shown(_)
+            ]
+        ));
+        ticket.wait().unwrap();
+        assert_eq!(*shown.lock().unwrap(), ["first", "ticket", "last"]);
+    }
+
+    #[test]
+    fn pending_tickets_resolve_when_the_worker_is_gone() {
+        let (sender, receiver) = mpsc::channel();
+        drop(sender);
+        let ticket = ShowTicket::new(receiver);
+        assert!(matches!(
+            ticket.try_wait(),
+            Ok(Err(WinToastError::ShowAborted))
+        ));
+    }
+}
@@ -0,0 +1,308 @@
+use std::fmt;
+
+use windows::UI::Notifications;
+
+use crate::{limits, FocusSessionState, ShowReport, ValidationIssue};
+
+/// What was known about the app and the toast when Windows failed to show it,
+/// attached to [`WinToastError::ShowFailed`](crate::WinToastError::ShowFailed).
+///
+/// See [`ShowDiagnostics::hints`] for what to check.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct ShowDiagnostics {
+    /// The AUM_ID the toast was shown for.
+    pub aum_id: String,
+    /// Whether the AUM_ID is registered with [`register`](crate::register) for the current user.
+    ///
+    /// `None` if that can't be told: without the `registry` feature, for apps with package
+    /// identity, or if the registry can't be read. AUM_IDs of Start menu shortcuts are not
+    /// in the registry, so `Some(false)` is expected for them.
+    pub registered: Option<bool>,
+    /// Whether the user, group policy or the manifest allows the toasts of the app,
+    /// `None` if the setting can't be read.
+    pub setting: Option<NotificationSetting>,
+    /// The size of the XML of the toast, in bytes of UTF-8.
+    pub xml_size: usize,
+}
+
+impl ShowDiagnostics {
+    /// What to check, most likely first, e.g. `"register the AUM_ID with register()"`.
+    pub fn hints(&self) -> Vec<&'static str> {
+        let mut hints = Vec::new();
+        if self.registered == Some(false) {
+            hints.push(
+                "register the AUM_ID with register(), unless a shortcut or package declares it",
+            );
+        }
+        match self.setting {
+            Some(NotificationSetting::DisabledForApplication) => {
+                hints.push("turn notifications for the app on in Settings > System > Notifications")
+            }
+            Some(NotificationSetting::DisabledForUser) => {
+                hints.push("turn notifications on in Settings > System > Notifications")
+            }
+            Some(NotificationSetting::DisabledByGroupPolicy) => {
+                hints.push("notifications are turned off by group policy")
+            }
+            Some(NotificationSetting::DisabledByManifest) => {
+                hints.push("the app manifest does not declare toast support")
+            }
+            _ => {}
+        }
+        if self.xml_size > limits::MAX_XML_SIZE {
+            hints.push("shrink the toast, its XML is larger than limits::MAX_XML_SIZE");
+        }
+        hints
+    }
+}
+
+impl fmt::Display for ShowDiagnostics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "AUM_ID {:?}", self.aum_id)?;
+        match self.registered {
+            Some(true) => write!(f, ", registered")?,
+            Some(false) => write!(f, ", not registered")?,
+            None => {}
+        }
+        if let Some(setting) = self.setting {
+            write!(f, ", notifications {:?}", setting)?;
+        }
+        write!(f, ", {} bytes of XML", self.xml_size)?;
+        for hint in self.hints() {
+            write!(f, "; {}", hint)?;
+        }
+        Ok(())
+    }
+}
+
+/// The outcome of [`ToastManager::diagnose`](crate::ToastManager::diagnose): what was known
+/// about the app, the toast that was attempted, and whether it was shown.
+///
+/// The report is meant to be shown to users, e.g. behind a "send a test notification" button,
+/// see [`DiagnosticReport::hints`] and the `Display` implementation.
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct DiagnosticReport {
+    /// The AUM_ID of the manager.
+    pub aum_id: String,
+    /// Whether the AUM_ID is registered, as in [`ShowDiagnostics::registered`].
+    pub registered: Option<bool>,
+    /// Whether the toasts of the app are allowed, `None` if the setting can't be read,
+    /// e.g. because no notifier can be created for the AUM_ID.
+    pub setting: Option<NotificationSetting>,
+    /// Whether a focus session was running, which keeps the toast out of sight
+    /// but still puts it in the Action Center.
+    pub focus_session: FocusSessionState,
+    /// The XML of the toast with the adjustments of the manager applied, as it was shown.
+    ///
+    /// `None` if the toast was not handed to Windows, e.g. because it failed validation
+    /// or its XML could not be built; the reason is in [`DiagnosticReport::result`].
+    pub xml: Option<String>,
+    /// The problems found by validating the toast, including warnings.
+    pub issues: Vec<ValidationIssue>,
+    /// The result of showing the toast with
+    /// [`ToastManager::show_reported`](crate::ToastManager::show_reported).
+    pub result: crate::Result<ShowReport>,
+}
+
+impl DiagnosticReport {
+    /// Whether the toast was handed to Windows, rather than failing, being queued by
+    /// [`ToastManager::defer_while_locked`](crate::ToastManager::defer_while_locked) or being
+    /// passed to [`ToastManager::on_undeliverable`](crate::ToastManager::on_undeliverable).
+    ///
+    /// Windows may still keep it off screen, see [`DiagnosticReport::hints`].
+    pub fn is_shown(&self) -> bool {
+        matches!(&self.result, Ok(report) if !report.deferred && report.undelivered.is_none())
+    }
+
+    /// What to check, most likely first, like [`ShowDiagnostics::hints`].
+    pub fn hints(&self) -> Vec<&'static str> {
+        let diagnostics = ShowDiagnostics {
+            aum_id: self.aum_id.clone(),
+            registered: self.registered,
+            setting: self.setting,
+            xml_size: self.xml.as_ref().map_or(0, String::len),
+        };
+        let mut hints = diagnostics.hints();
+        if self.issues.iter().any(|issue| !issue.is_warning()) {
+            hints.push("fix the problems the validation of the toast found");
+        }
+        if self.focus_session == FocusSessionState::Active {
+            hints
+                .push("a focus session is running, so the toast only appears in the Action Center");
+        }
+        hints
+    }
+}
+
+impl fmt::Display for DiagnosticReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.result {
+            Ok(report) if report.deferred => write!(f, "queued until the session is unlocked")?,
+            Ok(report) if report.undelivered.is_some() => {
+                write!(f, "not shown: notifications are disabled")?
+            }
+            Ok(_) => write!(f, "shown")?,
+            Err(error) => write!(f, "not shown: {}", error)?,
+        }
+        write!(f, "; AUM_ID {:?}", self.aum_id)?;
+        match self.registered {
+            Some(true) => write!(f, ", registered")?,
+            Some(false) => write!(f, ", not registered")?,
+            None => {}
+        }
+        if let Some(setting) = self.setting {
+            write!(f, ", notifications {:?}", setting)?;
+        }
+        for issue in &self.issues {
+            write!(f, "; {}", issue)?;
+        }
+        for hint in self.hints() {
+            write!(f, "; {}", hint)?;
+        }
+        Ok(())
+    }
+}
+
+/// Whether toasts of an app are shown, see [`ShowDiagnostics::setting`].
+///
+/// See <https://learn.microsoft.com/en-us/uwp/api/windows.ui.notifications.notificationsetting>
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum NotificationSetting {
+    /// Toasts of the app are shown.
+    Enabled,
+    /// The user turned off toasts of the app.
+    DisabledForApplication,
+    /// The user turned off all toasts.
+    DisabledForUser,
+    /// Group policy turned off toasts.
+    DisabledByGroupPolicy,
+    /// The manifest of the app does not declare toast support.
+    DisabledByManifest,
+    /// A setting added in a later version of Windows, with its raw value.
+    Other(i32),
+}
+
+impl NotificationSetting {
+    pub(crate) fn from_winrt(setting: Notifications::NotificationSetting) -> Self {
+        use Notifications::NotificationSetting as Winrt;
+        match setting {
+            Winrt::Enabled => NotificationSetting::Enabled,
+            Winrt::DisabledForApplication => NotificationSetting::DisabledForApplication,
+            Winrt::DisabledForUser => NotificationSetting::DisabledForUser,
+            Winrt::DisabledByGroupPolicy => NotificationSetting::DisabledByGroupPolicy,
+            Winrt::DisabledByManifest => NotificationSetting::DisabledByManifest,
+            Winrt(other) => NotificationSetting::Other(other),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diagnostics() -> ShowDiagnostics {
+        ShowDiagnostics {
+            aum_id: "App".to_string(),
+            registered: Some(true),
+            setting: Some(NotificationSetting::Enabled),
+            xml_size: 100,
+        }
+    }
+
+    fn report() -> DiagnosticReport {
+        DiagnosticReport {
+            aum_id: "App".to_string(),
+            registered: Some(true),
+            setting: Some(NotificationSetting::Enabled),
+            focus_session: FocusSessionState::Inactive,
+            xml: Some("<toast/>".to_string()),
+            issues: Vec::new(),
+            result: Ok(ShowReport::default()),
+        }
+    }
+
+    #[test]
+    fn no_hints_when_nothing_is_wrong() {
+        assert!(diagnostics().hints().is_empty());
+        assert!(report().hints().is_empty());
+    }
+
+    #[test]
+    fn hints_are_ordered_by_likelihood() {
+        let diagnostics = ShowDiagnostics {
+            registered: Some(false),
+            setting: Some(NotificationSetting::DisabledForUser),
+            xml_size: limits::MAX_XML_SIZE + 1,
+            ..diagnostics()
+        };
+        let hints = diagnostics.hints();
+        assert_eq!(hints.len(), 3);
+        assert!(hints[0].starts_with("register the AUM_ID"));
+        assert!(hints[1].starts_with("turn notifications on"));
+        assert!(hints[2].starts_with("shrink the toast"));
+    }
+
+    #[test]
+    fn unknown_registration_and_setting_give_no_hints() {
+        let diagnostics = ShowDiagnostics {
+            registered: None,
+            setting: None,
+            ..diagnostics()
+        };
+        assert!(diagnostics.hints().is_empty());
+    }
+
+    #[test]
+    fn each_disabled_setting_has_a_hint() {
+        for setting in [
+            NotificationSetting::DisabledForApplication,
+            NotificationSetting::DisabledForUser,
+            NotificationSetting::DisabledByGroupPolicy,
+            NotificationSetting::DisabledByManifest,
+        ] {
+            let diagnostics = ShowDiagnostics {
+                setting: Some(setting),
+                ..diagnostics()
+            };
+            assert_eq!(diagnostics.hints().len(), 1, "{:?}", setting);
+        }
+    }
+
+    #[test]
+    fn report_hints_cover_validation_and_focus_sessions() {
+        let warned = DiagnosticReport {
+            issues: vec![ValidationIssue::ButtonStyleIgnored],
+            ..report()
+        };
+        assert!(warned.hints().is_empty(), "warnings need no fixing");
+
+        let failed = DiagnosticReport {
+            registered: Some(false),
+            issues: vec![ValidationIssue::EmptyToast],
+            focus_session: FocusSessionState::Active,
+            ..report()
+        };
+        let hints = failed.hints();
+        assert_eq!(hints.len(), 3);
+        assert!(hints[0].starts_with("register the AUM_ID"));
+        assert!(hints[1].starts_with("fix the problems"));
+        assert!(hints[2].starts_with("a focus session is running"));
+    }
+
+    #[test]
+    fn display_lists_the_hints() {
+        let diagnostics = ShowDiagnostics {
+            registered: Some(false),
+            ..diagnostics()
+        };
+        let shown = diagnostics.to_string();
+        assert!(
+            shown.starts_with("AUM_ID \"App\", not registered, notifications Enabled, 100 bytes of XML; register the AUM_ID"),
+            "{}",
+            shown
+        );
+    }
+}
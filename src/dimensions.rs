@@ -0,0 +1,173 @@
+use std::{
+    fs::File,
+    io::{self, Read, Seek, SeekFrom},
+    path::Path,
+};
+
+use crate::{content::image::ImagePlacement, limits};
+
+/// The width divided by the height recommended for images with `placement`.
+///
+/// See <https://learn.microsoft.com/en-us/windows/apps/design/shell/tiles-and-notifications/toast-schema#toastgenericheroimage>
+pub(crate) fn recommended_ratio(placement: ImagePlacement) -> f64 {
+    match placement {
+        ImagePlacement::Hero => 2.0,
+        ImagePlacement::AppLogoOverride => 1.0,
+    }
+}
+
+/// Whether `width` by `height` is within half the recommended ratio of `placement`,
+/// in either direction.
+pub(crate) fn fits(placement: ImagePlacement, (width, height): (u32, u32)) -> bool {
+    if height == 0 {
+        return false;
+    }
+    let ratio = f64::from(width) / f64::from(height) / recommended_ratio(placement);
+    (1.0 / 1.5..=1.5).contains(&ratio)
+}
+
+/// The width and height of the image file at `path`, read from its header.
+///
+/// PNG, JPEG, GIF and BMP files are supported; `None` is returned for other formats.
+/// Only the header is read: the first bytes of the file, and for JPEG the marker of each
+/// segment up to the first start of frame.
+pub(crate) fn read(path: &Path) -> io::Result<Option<(u32, u32)>> {
+    read_from(File::open(path)?)
+}
+
+fn read_from(mut reader: impl Read + Seek) -> io::Result<Option<(u32, u32)>> {
+    let mut header = [0; 26];
+    let len = read_up_to(&mut reader, &mut header)?;
+    let header = &header[..len];
+
+    if header.starts_with(b"\x89PNG\r\n\x1a\n") {
+        return Ok(be32(header, 16).zip(be32(header, 20)));
+    }
+    if header.starts_with(b"GIF8") {
+        return Ok(le16(header, 6).zip(le16(header, 8)));
+    }
+    if header.starts_with(b"BM") {
+        return Ok(le32(header, 18).zip(le32(header, 22)));
+    }
+    if !header.starts_with(b"\xff\xd8") {
+        return Ok(None);
+    }
+
+    // JPEG: walk the segments up to the first start of frame, within the size Windows shows.
+    let mut at = 2;
+    while at < limits::MAX_IMAGE_FILE_SIZE {
+        reader.seek(SeekFrom::Start(at))?;
+        let mut segment = [0; 9];
+        let len = read_up_to(&mut reader, &mut segment)?;
+        let segment = &segment[..len];
+        let (Some(0xff), Some(&marker)) = (segment.first(), segment.get(1)) else {
+            return Ok(None);
+        };
+        match marker {
+            0xff => at += 1,
+            0x01 | 0xd0..=0xd7 => at += 2,
+            0xc0..=0xcf if !matches!(marker, 0xc4 | 0xc8 | 0xcc) => {
+                return Ok(be16(segment, 7).zip(be16(segment, 5)));
+            }
+            _ => match be16(segment, 2) {
+                Some(len) => at += 2 + u64::from(len),
+                None => return Ok(None),
+            },
+        }
+    }
+    Ok(None)
+}
+
+/// Fill `buf` as far as `reader` has bytes, and return how many were read.
+fn read_up_to(reader: &mut impl Read, buf: &mut [u8]) -> io::Result<usize> {
+    let mut len = 0;
+    while len < buf.len() {
+        match reader.read(&mut buf[len..]) {
+            Ok(0) => break,
+            Ok(n) => len += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(len)
+}
+
+fn be16(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u16::from_be_bytes(bytes.get(at..at + 2)?.try_into().ok()?).into())
+}
+
+fn le16(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u16::from_le_bytes(bytes.get(at..at + 2)?.try_into().ok()?).into())
+}
+
+fn be32(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(bytes.get(at..at + 4)?.try_into().ok()?))
+}
+
+/// A signed little-endian 32-bit value, as BMP uses for its sizes; negative heights flip the image.
+fn le32(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(i32::from_le_bytes(bytes.get(at..at + 4)?.try_into().ok()?).unsigned_abs())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    fn parse(bytes: &[u8]) -> Option<(u32, u32)> {
+        read_from(Cursor::new(bytes)).ok().flatten()
+    }
+
+    #[test]
+    fn png() {
+        let mut png = b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR".to_vec();
+        png.extend(364u32.to_be_bytes());
+        png.extend(180u32.to_be_bytes());
+        assert_eq!(parse(&png), Some((364, 180)));
+        assert_eq!(parse(&png[..22]), None);
+    }
+
+    #[test]
+    fn gif() {
+        assert_eq!(parse(b"GIF89a\x10\x00\x20\x00"), Some((16, 32)));
+    }
+
+    #[test]
+    fn bmp_with_negative_height() {
+        let mut bmp = b"BM".to_vec();
+        bmp.resize(18, 0);
+        bmp.extend(100i32.to_le_bytes());
+        bmp.extend((-50i32).to_le_bytes());
+        assert_eq!(parse(&bmp), Some((100, 50)));
+    }
+
+    #[test]
+    fn jpeg_skips_segments_before_the_frame() {
+        let mut jpeg = b"\xff\xd8".to_vec();
+        // An APP0 segment of 16 bytes, then a DHT segment, which is no frame despite its marker.
+        jpeg.extend(b"\xff\xe0\x00\x10JFIF\0\x01\x01\0\0\x01\0\x01\0\0");
+        jpeg.extend(b"\xff\xc4\x00\x04\0\0");
+        // A baseline start of frame: length, precision, height 120, width 240.
+        jpeg.extend(b"\xff\xc0\x00\x11\x08\x00\x78\x00\xf0\x03");
+        assert_eq!(parse(&jpeg), Some((240, 120)));
+    }
+
+    #[test]
+    fn truncated_and_unknown_files() {
+        assert_eq!(parse(b"\xff\xd8\xff\xe0\x00"), None);
+        assert_eq!(parse(b"\xff\xd8\x00"), None);
+        assert_eq!(parse(b"RIFF\0\0\0\0WEBP"), None);
+        assert_eq!(parse(b""), None);
+    }
+
+    #[test]
+    fn ratios_within_half_of_the_recommendation_fit() {
+        assert!(fits(ImagePlacement::Hero, (364, 180)));
+        assert!(fits(ImagePlacement::Hero, (300, 200)));
+        assert!(!fits(ImagePlacement::Hero, (100, 100)));
+        assert!(fits(ImagePlacement::AppLogoOverride, (48, 48)));
+        assert!(!fits(ImagePlacement::AppLogoOverride, (200, 50)));
+        assert!(!fits(ImagePlacement::AppLogoOverride, (10, 0)));
+    }
+}
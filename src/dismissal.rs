@@ -0,0 +1,136 @@
+use std::sync::{Arc, Mutex, PoisonError};
+
+use crate::{catch_panic, DismissalReason, Result, ToastDismissed};
+
+/// A callback shared by a manager and its clones, called by one thread at a time.
+type Callback<T> = Arc<Mutex<Box<dyn Fn(T) + Send>>>;
+
+/// The callbacks of [`ToastManager::on_dismissed`](crate::ToastManager::on_dismissed) and of the
+/// single reasons, such as [`ToastManager::on_user_dismissed`](crate::ToastManager::on_user_dismissed),
+/// called from the one `Dismissed` handler of a toast.
+#[derive(Clone, Default)]
+pub(crate) struct DismissalCallbacks {
+    any: Option<Callback<Result<ToastDismissed>>>,
+    by_reason: Vec<(DismissalReason, Callback<ToastDismissed>)>,
+}
+
+impl DismissalCallbacks {
+    /// Replace the callback for every dismissal.
+    pub(crate) fn set_any(&mut self, f: impl Fn(Result<ToastDismissed>) + Send + 'static) {
+        self.any = Some(Arc::new(Mutex::new(Box::new(f))));
+    }
+
+    /// Add a callback for the dismissals with `reason`.
+    pub(crate) fn add(
+        &mut self,
+        reason: DismissalReason,
+        f: impl Fn(ToastDismissed) + Send + 'static,
+    ) {
+        self.by_reason
+            .push((reason, Arc::new(Mutex::new(Box::new(f)))));
+    }
+
+    /// Call the callback for every dismissal first, then the callbacks for the reason of
+    /// `dismissed` in the order they were added. Panics in the callbacks are caught.
+    pub(crate) fn dispatch(&self, dismissed: Result<ToastDismissed>) {
+        let reason = dismissed.as_ref().ok().map(|dismissed| dismissed.reason);
+        let copy = dismissed.as_ref().ok().cloned();
+        if let Some(any) = &self.any {
+            let any = any.lock().unwrap_or_else(PoisonError::into_inner);
+            catch_panic(|| any(dismissed));
+        }
+        let Some(copy) = copy else {
+            return;
+        };
+        for (_, f) in self.by_reason.iter().filter(|(r, _)| Some(*r) == reason) {
+            let f = f.lock().unwrap_or_else(PoisonError::into_inner);
+            catch_panic(|| f(copy.clone()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::WinToastError;
+
+    fn recording() -> (DismissalCallbacks, Arc<Mutex<Vec<String>>>) {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let mut callbacks = DismissalCallbacks::default();
+        let log = calls.clone();
+        callbacks.set_any(move |dismissed| {
+            let name = match dismissed {
+                Ok(dismissed) => format!("any {:?}", dismissed.reason),
+                Err(_) => "any error".to_string(),
+            };
+            log.lock().unwrap().push(name);
+        });
+        for (reason, name) in [
+            (DismissalReason::UserCanceled, "user"),
+            (DismissalReason::TimedOut, "timed out"),
+            (DismissalReason::ApplicationHidden, "hidden"),
+            (DismissalReason::UserCanceled, "user again"),
+        ] {
+            let log = calls.clone();
+            callbacks.add(reason, move |_| log.lock().unwrap().push(name.to_string()));
+        }
+        (callbacks, calls)
+    }
+
+    fn fired(reason: DismissalReason) -> Vec<String> {
+        let (callbacks, calls) = recording();
+        callbacks.dispatch(Ok(ToastDismissed::new(reason)));
+        let calls = calls.lock().unwrap().clone();
+        calls
+    }
+
+    #[test]
+    fn user_dismissals_call_the_general_callback_first() {
+        assert_eq!(
+            fired(DismissalReason::UserCanceled),
+            ["any UserCanceled", "user", "user again"]
+        );
+    }
+
+    #[test]
+    fn each_reason_calls_only_its_callbacks() {
+        assert_eq!(
+            fired(DismissalReason::TimedOut),
+            ["any TimedOut", "timed out"]
+        );
+        assert_eq!(
+            fired(DismissalReason::ApplicationHidden),
+            ["any ApplicationHidden", "hidden"]
+        );
+        assert_eq!(fired(DismissalReason::Other(7)), ["any Other(7)"]);
+    }
+
+    #[test]
+    fn the_general_callback_comes_first_whenever_it_was_registered() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let mut callbacks = DismissalCallbacks::default();
+        let log = calls.clone();
+        callbacks.add(DismissalReason::TimedOut, move |_| {
+            log.lock().unwrap().push("timed out")
+        });
+        let log = calls.clone();
+        callbacks.set_any(move |_| log.lock().unwrap().push("any"));
+        callbacks.dispatch(Ok(ToastDismissed::new(DismissalReason::TimedOut)));
+        assert_eq!(*calls.lock().unwrap(), ["any", "timed out"]);
+    }
+
+    #[test]
+    fn errors_only_reach_the_general_callback() {
+        let (callbacks, calls) = recording();
+        callbacks.dispatch(Err(WinToastError::InvalidDismissalReason));
+        assert_eq!(*calls.lock().unwrap(), ["any error"]);
+    }
+
+    #[test]
+    fn panics_do_not_stop_the_other_callbacks() {
+        let (mut callbacks, calls) = recording();
+        callbacks.set_any(|_| panic!("general callback"));
+        callbacks.dispatch(Ok(ToastDismissed::new(DismissalReason::TimedOut)));
+        assert_eq!(*calls.lock().unwrap(), ["timed out"]);
+    }
+}
@@ -0,0 +1,95 @@
+use std::thread;
+
+use windows::Win32::Media::Audio::{PlaySoundW, SND_ALIAS, SND_NODEFAULT, SND_SYNC};
+
+use crate::{correlation, hs, Toast};
+
+/// The prefix of the notification data entries holding the feedback sounds, followed by
+/// the arguments of the action. See [`Action::with_feedback_sound`](crate::Action::with_feedback_sound).
+const FEEDBACK_SOUND_KEY_PREFIX: &str = "winrt-toast-reborn.feedback-sound.";
+
+/// The key of the notification data entry holding the sound for the action with `arguments`.
+fn key(arguments: &str) -> String {
+    format!("{}{}", FEEDBACK_SOUND_KEY_PREFIX, arguments)
+}
+
+/// The sound event alias for an activation with `arguments`, looked up with `get` in the
+/// notification data of the toast.
+pub(crate) fn lookup(arguments: &str, get: impl FnOnce(&str) -> Option<String>) -> Option<String> {
+    let (arguments, _) = correlation::split(arguments);
+    get(&key(arguments))
+}
+
+impl Toast {
+    /// The feedback sounds of the actions, as pairs of the data key and the sound event alias.
+    pub(crate) fn feedback_sounds(&self) -> impl Iterator<Item = (String, String)> + '_ {
+        self.actions
+            .iter()
+            .filter_map(|action| action.feedback_sound())
+            .filter_map(|(arguments, sound)| Some((key(arguments), sound.event_alias()?)))
+    }
+}
+
+/// Play the system sound event `alias` on a new thread.
+pub(crate) fn play(alias: String) {
+    thread::spawn(move || unsafe {
+        let _ = PlaySoundW(&hs(alias), None, SND_ALIAS | SND_NODEFAULT | SND_SYNC);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::{content::audio::Sound, Action};
+
+    fn toast() -> Toast {
+        let mut toast = Toast::new();
+        toast
+            .text1("Order shipped")
+            .action(Action::new("Track", "track", "").with_feedback_sound(Sound::SMS))
+            .action(Action::new("Mute", "mute", "").with_feedback_sound(Sound::None))
+            .action(Action::new("Open", "open", ""));
+        toast
+    }
+
+    fn data(toast: &Toast) -> HashMap<String, String> {
+        toast.feedback_sounds().collect()
+    }
+
+    #[test]
+    fn only_actions_with_a_sound_are_stored() {
+        let data = data(&toast());
+        assert_eq!(data.len(), 1);
+        assert_eq!(data[&key("track")], "Notification.SMS");
+    }
+
+    #[test]
+    fn the_sound_of_the_activated_action_is_found() {
+        let data = data(&toast());
+        let get = |key: &str| data.get(key).cloned();
+        assert_eq!(lookup("track", get).as_deref(), Some("Notification.SMS"));
+        assert_eq!(lookup("open", get), None);
+        assert_eq!(lookup("mute", get), None);
+        assert_eq!(lookup("", get), None);
+    }
+
+    #[test]
+    fn the_sound_is_found_with_a_correlation_id() {
+        let mut toast = toast();
+        toast.correlation_id("order-42");
+        let data = data(&toast);
+        let embedded = toast.with_correlation_embedded().unwrap();
+        let arguments = embedded.actions[0].arguments();
+        assert_ne!(arguments, "track");
+        let get = |key: &str| data.get(key).cloned();
+        assert_eq!(lookup(arguments, get).as_deref(), Some("Notification.SMS"));
+    }
+
+    #[test]
+    fn keys_of_different_actions_differ() {
+        assert_ne!(key("a"), key("b"));
+        assert!(key("track").starts_with(FEEDBACK_SOUND_KEY_PREFIX));
+    }
+}
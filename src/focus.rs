@@ -0,0 +1,197 @@
+use windows::{
+    Win32::UI::Shell::{
+        SHQueryUserNotificationState, QUNS_APP, QUNS_BUSY, QUNS_PRESENTATION_MODE, QUNS_QUIET_TIME,
+        QUNS_RUNNING_D3D_FULL_SCREEN,
+    },
+    UI::Shell::FocusSessionManager,
+};
+
+use crate::{Scenario, Toast};
+
+impl Toast {
+    /// Show this toast only in the Action Center, without a popup.
+    ///
+    /// See <https://learn.microsoft.com/en-us/uwp/api/windows.ui.notifications.toastnotification.suppresspopup>
+    pub fn suppress_popup(&mut self, suppress: bool) -> &mut Toast {
+        self.suppress_popup = suppress;
+        self
+    }
+
+    /// Deliver this toast to the Action Center only, without a popup, while the user
+    /// doesn't want to be disturbed.
+    ///
+    /// When set, [`ToastManager::show`](crate::ToastManager::show) asks Windows whether the user
+    /// accepts notifications right now, and applies [`Toast::suppress_popup`] if not, i.e. when
+    /// * a focus session is running, see [`focus_session_state`],
+    /// * a presentation is running, or an app is shown in full screen,
+    /// * it is the quiet time of a new user, the first hour after their first sign-in,
+    /// * Windows reports the user as busy for another reason.
+    ///
+    /// Windows doesn't tell apps whether Focus Assist or Do not disturb is on, so that isn't
+    /// detected; Windows holds back the popups itself then.
+    ///
+    /// Toasts with the [`Scenario::Alarm`], [`Scenario::IncomingCall`] or [`Scenario::Urgent`]
+    /// scenario are high-priority and always pop up. If the state can't be read, the toast
+    /// is shown as usual.
+    pub fn respect_focus_assist(&mut self, respect: bool) -> &mut Toast {
+        self.respect_focus_assist = respect;
+        self
+    }
+
+    /// Whether this toast should be delivered silently to the Action Center right now,
+    /// see [`Toast::respect_focus_assist`].
+    pub(crate) fn defers_to_focus_assist(&self) -> bool {
+        self.defers_to_focus_assist_with(user_is_busy, focus_session_state)
+    }
+
+    /// [`Toast::defers_to_focus_assist`] with the given probes, which are only called
+    /// when their answer matters.
+    fn defers_to_focus_assist_with(
+        &self,
+        user_is_busy: impl FnOnce() -> bool,
+        focus_session_state: impl FnOnce() -> FocusSessionState,
+    ) -> bool {
+        self.respect_focus_assist
+            && !self.suppress_popup
+            && !self.is_high_priority()
+            && (user_is_busy() || focus_session_state() == FocusSessionState::Active)
+    }
+
+    /// Whether this toast should be delivered silently to the Action Center during
+    /// a focus session in `state`, see
+    /// [`ToastManager::quiet_during_focus_sessions`](crate::ToastManager::quiet_during_focus_sessions).
+    pub(crate) fn quiets_for_focus_session(&self, state: FocusSessionState) -> bool {
+        state == FocusSessionState::Active && !self.is_high_priority()
+    }
+
+    /// Whether the scenario of this toast makes it break through focus assist.
+    fn is_high_priority(&self) -> bool {
+        matches!(
+            self.scenario,
+            Some(Scenario::Alarm | Scenario::IncomingCall | Scenario::Urgent)
+        )
+    }
+}
+
+/// Whether a Windows 11 focus session is running, see [`focus_session_state`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum FocusSessionState {
+    /// A focus session is running, so Windows shows no banners and plays no sounds.
+    Active,
+    /// No focus session is running.
+    Inactive,
+    /// The state can't be read, e.g. because this version of Windows has no focus sessions.
+    Unknown,
+}
+
+/// Whether a focus session, started from the Clock app or the notification center of
+/// Windows 11, is running right now.
+///
+/// Focus sessions suppress banners while notifications stay enabled for the app,
+/// so they are not visible in [`ShowDiagnostics::setting`](crate::ShowDiagnostics::setting).
+/// This reads the state from `Windows.UI.Shell.FocusSessionManager`, which is only
+/// available on Windows 11 version 22H2 and later; elsewhere the state is
+/// [`FocusSessionState::Unknown`].
+pub fn focus_session_state() -> FocusSessionState {
+    let active = FocusSessionManager::IsSupported().and_then(|supported| {
+        if !supported {
+            return Ok(None);
+        }
+        FocusSessionManager::GetDefault()?.IsFocusActive().map(Some)
+    });
+    match active {
+        Ok(Some(true)) => FocusSessionState::Active,
+        Ok(Some(false)) => FocusSessionState::Inactive,
+        _ => FocusSessionState::Unknown,
+    }
+}
+
+/// Whether the user doesn't want to be disturbed by notification popups right now,
+/// according to `SHQueryUserNotificationState`.
+///
+/// This covers full-screen apps, presentations and the quiet time, which is the first hour
+/// after a new user's first sign-in. It doesn't report Focus Assist.
+fn user_is_busy() -> bool {
+    let Ok(state) = (unsafe { SHQueryUserNotificationState() }) else {
+        return false;
+    };
+    matches!(
+        state,
+        QUNS_BUSY
+            | QUNS_RUNNING_D3D_FULL_SCREEN
+            | QUNS_PRESENTATION_MODE
+            | QUNS_QUIET_TIME
+            | QUNS_APP
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn respecting() -> Toast {
+        let mut toast = Toast::new();
+        toast.respect_focus_assist(true);
+        toast
+    }
+
+    fn defers(toast: &Toast, busy: bool, state: FocusSessionState) -> bool {
+        toast.defers_to_focus_assist_with(|| busy, || state)
+    }
+
+    #[test]
+    fn defers_while_busy_or_in_a_focus_session() {
+        let toast = respecting();
+        assert!(defers(&toast, true, FocusSessionState::Inactive));
+        assert!(defers(&toast, true, FocusSessionState::Unknown));
+        assert!(defers(&toast, false, FocusSessionState::Active));
+        assert!(!defers(&toast, false, FocusSessionState::Inactive));
+        assert!(!defers(&toast, false, FocusSessionState::Unknown));
+    }
+
+    #[test]
+    fn does_not_defer_unless_asked() {
+        let toast = Toast::new();
+        assert!(!defers(&toast, true, FocusSessionState::Active));
+    }
+
+    #[test]
+    fn does_not_defer_a_suppressed_popup() {
+        let mut toast = respecting();
+        toast.suppress_popup(true);
+        assert!(!defers(&toast, true, FocusSessionState::Active));
+    }
+
+    #[test]
+    fn high_priority_scenarios_always_pop_up() {
+        for scenario in [Scenario::Alarm, Scenario::IncomingCall, Scenario::Urgent] {
+            let mut toast = respecting();
+            toast.scenario(scenario);
+            assert!(!defers(&toast, true, FocusSessionState::Active));
+            assert!(!toast.quiets_for_focus_session(FocusSessionState::Active));
+        }
+        let mut toast = respecting();
+        toast.scenario(Scenario::Reminder);
+        assert!(defers(&toast, true, FocusSessionState::Active));
+    }
+
+    #[test]
+    fn probes_are_skipped_when_the_answer_is_known() {
+        let toast = Toast::new();
+        assert!(!toast.defers_to_focus_assist_with(
+            || panic!("busy probed"),
+            || panic!("focus session probed")
+        ));
+        let toast = respecting();
+        assert!(toast.defers_to_focus_assist_with(|| true, || panic!("focus session probed")));
+    }
+
+    #[test]
+    fn quiets_only_during_an_active_focus_session() {
+        let toast = Toast::new();
+        assert!(toast.quiets_for_focus_session(FocusSessionState::Active));
+        assert!(!toast.quiets_for_focus_session(FocusSessionState::Inactive));
+        assert!(!toast.quiets_for_focus_session(FocusSessionState::Unknown));
+    }
+}
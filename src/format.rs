@@ -0,0 +1,292 @@
+use std::time::{Duration, SystemTime};
+
+use windows::{
+    core::HSTRING,
+    Foundation::Collections::IIterable,
+    Globalization::{
+        DateTimeFormatting::DateTimeFormatter,
+        GeographicRegion,
+        NumberFormatting::{DecimalFormatter, PercentFormatter},
+    },
+};
+
+use crate::{hs, time};
+
+/// Formats numbers and times for toast text, following the conventions of a language.
+///
+/// [`Formatter::default`] uses the languages and region the user picked in Windows;
+/// [`Formatter::with_languages`] pins the languages, e.g. for output that must not depend
+/// on the machine.
+///
+/// # Example
+/// ```no_run
+/// # use winrt_toast_reborn::{format::Formatter, Toast};
+/// let formatter = Formatter::with_languages(["de-DE"]);
+/// let mut toast = Toast::new();
+/// toast
+///     .text1("Download finished")
+///     .text2(formatter.bytes(1_572_864)?); // "1,5 MiB"
+/// # Ok::<(), winrt_toast_reborn::WinToastError>(())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Formatter {
+    languages: Option<Vec<String>>,
+}
+
+impl Formatter {
+    /// A formatter for `languages`, given as BCP-47 language tags such as `"en-US"`,
+    /// in order of preference.
+    ///
+    /// The region of the first language, such as `US` in `"en-US"`, is used for the numbers;
+    /// without one, the region the user picked in Windows is used.
+    pub fn with_languages<S: Into<String>>(languages: impl IntoIterator<Item = S>) -> Self {
+        Self {
+            languages: Some(languages.into_iter().map(Into::into).collect()),
+        }
+    }
+
+    /// Format `at` relative to now, e.g. `just now`, `5 minutes ago`, `in 2 hours` or
+    /// `3 days ago`, in the largest whole unit up to days.
+    ///
+    /// Times a week or more away are formatted as a date in the language instead.
+    /// Windows has no formatter for relative phrases, so their words are English and only
+    /// their numbers follow the language. To have the Action Center show a relative time
+    /// in the user's language, set [`Toast::display_timestamp`](crate::Toast::display_timestamp) instead.
+    pub fn relative_time(&self, at: SystemTime) -> crate::Result<String> {
+        let now = SystemTime::now();
+        let (span, future) = match at.duration_since(now) {
+            Ok(span) => (span, true),
+            Err(e) => (e.duration(), false),
+        };
+        match RelativeTime::of(span) {
+            RelativeTime::Now => Ok("just now".to_string()),
+            RelativeTime::Units(count, unit) => {
+                let number = self.decimal_formatter(0)?.FormatDouble(count as f64)?;
+                Ok(relative_phrase(&number.to_string(), count, unit, future))
+            }
+            RelativeTime::Date => {
+                let at = time::to_winrt_datetime(at)?;
+                Ok(self
+                    .date_time_formatter("shortdate")?
+                    .Format(at)?
+                    .to_string())
+            }
+        }
+    }
+
+    /// Format a size in bytes with binary units, e.g. `1.5 MiB` for 1,572,864 bytes.
+    ///
+    /// The number follows the language, the units are always `B`, `KiB`, `MiB`, `GiB` and `TiB`.
+    pub fn bytes(&self, bytes: u64) -> crate::Result<String> {
+        const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+        if bytes < 1024 {
+            return Ok(format!(
+                "{} B",
+                self.decimal_formatter(0)?.FormatDouble(bytes as f64)?
+            ));
+        }
+
+        let mut value = bytes as f64 / 1024.0;
+        let mut unit = 0;
+        while value >= 1024.0 && unit < UNITS.len() - 1 {
+            value /= 1024.0;
+            unit += 1;
+        }
+        let number = self.decimal_formatter(1)?.FormatDouble(value)?;
+        Ok(format!("{} {}", number, UNITS[unit]))
+    }
+
+    /// Format `ratio` as a whole percentage, e.g. `42%` or `42 %` for `0.42`.
+    pub fn percent(&self, ratio: f64) -> crate::Result<String> {
+        let formatter = match self.languages()? {
+            Some(languages) => {
+                PercentFormatter::CreatePercentFormatter(&languages, &self.region()?)?
+            }
+            None => PercentFormatter::new()?,
+        };
+        formatter.SetFractionDigits(0)?;
+        Ok(formatter.FormatDouble(ratio)?.to_string())
+    }
+
+    fn date_time_formatter(&self, template: &str) -> crate::Result<DateTimeFormatter> {
+        Ok(match self.languages()? {
+            Some(languages) => {
+                DateTimeFormatter::CreateDateTimeFormatterLanguages(&hs(template), &languages)?
+            }
+            None => DateTimeFormatter::CreateDateTimeFormatter(&hs(template))?,
+        })
+    }
+
+    fn decimal_formatter(&self, fraction_digits: i32) -> crate::Result<DecimalFormatter> {
+        let formatter = match self.languages()? {
+            Some(languages) => {
+                DecimalFormatter::CreateDecimalFormatter(&languages, &self.region()?)?
+            }
+            None => DecimalFormatter::new()?,
+        };
+        formatter.SetFractionDigits(fraction_digits)?;
+        formatter.SetIsGrouped(true)?;
+        Ok(formatter)
+    }
+
+    fn languages(&self) -> crate::Result<Option<IIterable<HSTRING>>> {
+        let Some(languages) = &self.languages else {
+            return Ok(None);
+        };
+        let languages: Vec<HSTRING> = languages.iter().map(hs).collect();
+        Ok(Some(IIterable::try_from(languages)?))
+    }
+
+    /// The region of the first language, or the region the user picked in Windows,
+    /// as a two-letter code.
+    fn region(&self) -> crate::Result<HSTRING> {
+        let first = self.languages.as_deref().and_then(<[String]>::first);
+        match first.and_then(|language| language_region(language)) {
+            Some(region) => Ok(hs(region)),
+            None => Ok(GeographicRegion::new()?.CodeTwoLetter()?),
+        }
+    }
+}
+
+/// The two-letter region subtag of a BCP-47 language tag, such as `US` in `en-US`.
+fn language_region(language: &str) -> Option<&str> {
+    language
+        .split('-')
+        .skip(1)
+        .find(|subtag| subtag.len() == 2 && subtag.chars().all(|c| c.is_ascii_alphabetic()))
+}
+
+/// How far a time is from now, see [`Formatter::relative_time`].
+#[derive(Debug, PartialEq, Eq)]
+enum RelativeTime {
+    /// Less than a minute.
+    Now,
+    /// A whole number of the largest unit that fits, less than a week.
+    Units(u64, &'static str),
+    /// A week or more.
+    Date,
+}
+
+impl RelativeTime {
+    fn of(span: Duration) -> Self {
+        const MINUTE: u64 = 60;
+        const HOUR: u64 = 60 * MINUTE;
+        const DAY: u64 = 24 * HOUR;
+        const WEEK: u64 = 7 * DAY;
+        match span.as_secs() {
+            secs if secs < MINUTE => RelativeTime::Now,
+            secs if secs < HOUR => RelativeTime::Units(secs / MINUTE, "minute"),
+            secs if secs < DAY => RelativeTime::Units(secs / HOUR, "hour"),
+            secs if secs < WEEK => RelativeTime::Units(secs / DAY, "day"),
+            _ => RelativeTime::Date,
+        }
+    }
+}
+
+/// `in 5 minutes` or `5 minutes ago`, with `number` being `count` formatted.
+fn relative_phrase(number: &str, count: u64, unit: &str, future: bool) -> String {
+    let plural = if count == 1 { "" } else { "s" };
+    if future {
+        format!("in {} {}{}", number, unit, plural)
+    } else {
+        format!("{} {}{} ago", number, unit, plural)
+    }
+}
+
+/// Format `time` relative to today in the user's language, see [`Formatter::relative_time`].
+pub fn format_relative_time(at: SystemTime) -> crate::Result<String> {
+    Formatter::default().relative_time(at)
+}
+
+/// Format a size in bytes in the user's language, see [`Formatter::bytes`].
+pub fn format_bytes(bytes: u64) -> crate::Result<String> {
+    Formatter::default().bytes(bytes)
+}
+
+/// Format `ratio` as a percentage in the user's language, see [`Formatter::percent`].
+pub fn format_percent(ratio: f64) -> crate::Result<String> {
+    Formatter::default().percent(ratio)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn relative_time_uses_the_largest_whole_unit() {
+        let minutes = |m: u64| Duration::from_secs(m * 60);
+        assert_eq!(RelativeTime::of(Duration::from_secs(59)), RelativeTime::Now);
+        assert_eq!(
+            RelativeTime::of(minutes(1)),
+            RelativeTime::Units(1, "minute")
+        );
+        assert_eq!(
+            RelativeTime::of(minutes(59)),
+            RelativeTime::Units(59, "minute")
+        );
+        assert_eq!(
+            RelativeTime::of(minutes(90)),
+            RelativeTime::Units(1, "hour")
+        );
+        assert_eq!(
+            RelativeTime::of(minutes(24 * 60)),
+            RelativeTime::Units(1, "day")
+        );
+        assert_eq!(
+            RelativeTime::of(minutes(7 * 24 * 60 - 1)),
+            RelativeTime::Units(6, "day")
+        );
+        assert_eq!(RelativeTime::of(minutes(7 * 24 * 60)), RelativeTime::Date);
+    }
+
+    #[test]
+    fn relative_phrases_have_direction_and_number() {
+        assert_eq!(relative_phrase("1", 1, "hour", true), "in 1 hour");
+        assert_eq!(relative_phrase("5", 5, "minute", false), "5 minutes ago");
+    }
+
+    #[test]
+    fn region_is_read_from_the_language_tag() {
+        assert_eq!(language_region("en-US"), Some("US"));
+        assert_eq!(language_region("zh-Hans-CN"), Some("CN"));
+        assert_eq!(language_region("de"), None);
+        assert_eq!(language_region("es-419"), None);
+    }
+
+    // These call the formatters of Windows, pinned to a language so the output doesn't depend
+    // on the machine.
+
+    fn hours(hours: u64) -> Duration {
+        Duration::from_secs(hours * 60 * 60)
+    }
+
+    #[test]
+    fn locale_pinned_bytes() {
+        let en = Formatter::with_languages(["en-US"]);
+        assert_eq!(en.bytes(512).unwrap(), "512 B");
+        assert_eq!(en.bytes(1_572_864).unwrap(), "1.5 MiB");
+        assert_eq!(en.bytes(3 << 30).unwrap(), "3.0 GiB");
+        let de = Formatter::with_languages(["de-DE"]);
+        assert_eq!(de.bytes(1_572_864).unwrap(), "1,5 MiB");
+    }
+
+    #[test]
+    fn locale_pinned_percent() {
+        let en = Formatter::with_languages(["en-US"]);
+        assert_eq!(en.percent(0.42).unwrap(), "42%");
+    }
+
+    #[test]
+    fn locale_pinned_relative_time() {
+        let en = Formatter::with_languages(["en-US"]);
+        let now = SystemTime::now();
+        assert_eq!(en.relative_time(now).unwrap(), "just now");
+        assert_eq!(en.relative_time(now - hours(3)).unwrap(), "3 hours ago");
+        assert_eq!(en.relative_time(now + hours(49)).unwrap(), "in 2 days");
+        // Noon UTC, so the date is the same in every time zone.
+        let date = SystemTime::UNIX_EPOCH + Duration::from_secs(1_577_966_400);
+        // Windows marks the text direction of the date parts.
+        let formatted = en.relative_time(date).unwrap().replace('\u{200E}', "");
+        assert_eq!(formatted, "1/2/2020");
+    }
+}
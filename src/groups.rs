@@ -0,0 +1,264 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, Weak,
+    },
+};
+
+use windows::{
+    core::{IInspectable, Interface},
+    Foundation::TypedEventHandler,
+    UI::Notifications::{
+        ToastActivatedEventArgs, ToastDismissalReason, ToastDismissedEventArgs,
+        ToastFailedEventArgs, ToastNotification,
+    },
+};
+
+use crate::{catch_panic, correlation, Toast};
+
+type Callback = Box<dyn Fn(String) + Send + Sync>;
+
+/// The live toasts of each group, for [`ToastManager::on_group_empty`](crate::ToastManager::on_group_empty).
+///
+/// A toast is live from when it is shown until it reaches a terminal state, and each
+/// live toast is known by an ID unique within the tracker, along with its tag.
+pub(crate) struct GroupTracker {
+    live: Mutex<HashMap<String, HashMap<u64, Option<String>>>>,
+    next_id: AtomicU64,
+    callback: Callback,
+}
+
+impl GroupTracker {
+    pub(crate) fn new(callback: Callback) -> Self {
+        Self {
+            live: Mutex::default(),
+            next_id: AtomicU64::new(0),
+            callback,
+        }
+    }
+
+    /// Track `notification`, showing `toast` in `group`, until it reaches a terminal state:
+    /// * it is activated by an action that does not keep it on screen,
+    /// * it is dismissed by the user or hidden by the app, but not when it times out
+    ///   into the Action Center,
+    /// * it fails to show.
+    ///
+    /// The handlers only hold a weak reference, so they don't keep the tracker alive.
+    pub(crate) fn track(
+        self: &Arc<Self>,
+        notification: &ToastNotification,
+        toast: &Toast,
+        group: &str,
+    ) -> windows::core::Result<()> {
+        let id = self.add(group, toast.tag.clone());
+
+        let kept: Vec<String> = toast
+            .actions
+            .iter()
+            .filter(|action| action.keeps_toast())
+            .map(|action| action.arguments().to_string())
+            .collect();
+        let finish = {
+            let (tracker, group) = (Arc::downgrade(self), group.to_string());
+            move || {
+                if let Some(tracker) = Weak::upgrade(&tracker) {
+                    tracker.finish(&group, id);
+                }
+            }
+        };
+        let (activated, dismissed, failed) = (finish.clone(), finish.clone(), finish);
+
+        notification.Activated(&TypedEventHandler::new(
+            move |_, args: &Option<IInspectable>| {
+                let arguments = args
+                    .as_ref()
+                    .and_then(|args| args.cast::<ToastActivatedEventArgs>().ok())
+                    .and_then(|args| args.Arguments().ok())
+                    .unwrap_or_default()
+                    .to_string();
+                let (arguments, _) = correlation::split(&arguments);
+                if !kept.iter().any(|kept| kept == arguments) {
+                    activated();
+                }
+                Ok(())
+            },
+        ))?;
+        notification.Dismissed(&TypedEventHandler::new(
+            move |_, args: &Option<ToastDismissedEventArgs>| {
+                let reason = args.as_ref().and_then(|args| args.Reason().ok());
+                if reason != Some(ToastDismissalReason::TimedOut) {
+                    dismissed();
+                }
+                Ok(())
+            },
+        ))?;
+        notification.Failed(&TypedEventHandler::new(
+            move |_, _: &Option<ToastFailedEventArgs>| {
+                failed();
+                Ok(())
+            },
+        ))?;
+        Ok(())
+    }
+
+    /// Start tracking a toast with `tag` in `group`, and return its ID.
+    fn add(&self, group: &str, tag: Option<String>) -> u64 {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        self.live
+            .lock()
+            .unwrap()
+            .entry(group.to_string())
+            .or_default()
+            .insert(id, tag);
+        id
+    }
+
+    /// The toast `id` in `group` reached a terminal state.
+    fn finish(&self, group: &str, id: u64) {
+        self.remove_where(group, |entry_id, _| entry_id == id);
+    }
+
+    /// The toasts with `tag` in `group` were removed by the app.
+    pub(crate) fn remove_tag(&self, group: &str, tag: &str) {
+        self.remove_where(group, |_, entry_tag| entry_tag == Some(tag));
+    }
+
+    /// All toasts in `group` were removed by the app.
+    pub(crate) fn remove_group(&self, group: &str) {
+        self.remove_where(group, |_, _| true);
+    }
+
+    /// All toasts were removed by the app.
+    pub(crate) fn remove_all(&self) {
+        let groups: Vec<String> = self.live.lock().unwrap().drain().map(|(g, _)| g).collect();
+        for group in groups {
+            self.notify(group);
+        }
+    }
+
+    /// Stop tracking the toasts in `group` matching `f`, and call the callback if that
+    /// removed the last one.
+    fn remove_where(&self, group: &str, f: impl Fn(u64, Option<&str>) -> bool) {
+        let emptied = {
+            let mut live = self.live.lock().unwrap();
+            let Some(entries) = live.get_mut(group) else {
+                return;
+            };
+            let len = entries.len();
+            entries.retain(|id, tag| !f(*id, tag.as_deref()));
+            let emptied = entries.is_empty() && len > 0;
+            if entries.is_empty() {
+                live.remove(group);
+            }
+            emptied
+        };
+        // The lock is released before the callback, which may show or remove toasts.
+        if emptied {
+            self.notify(group.to_string());
+        }
+    }
+
+    fn notify(&self, group: String) {
+        catch_panic(|| (self.callback)(group));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recording() -> (Arc<GroupTracker>, Arc<Mutex<Vec<String>>>) {
+        let emptied = Arc::new(Mutex::new(Vec::new()));
+        let log = emptied.clone();
+        let tracker = GroupTracker::new(Box::new(move |group| log.lock().unwrap().push(group)));
+        (Arc::new(tracker), emptied)
+    }
+
+    #[test]
+    fn the_callback_fires_once_the_last_toast_finishes() {
+        let (tracker, emptied) = recording();
+        let first = tracker.add("chat", Some("1".into()));
+        let second = tracker.add("chat", Some("2".into()));
+        tracker.add("mail", None);
+
+        tracker.finish("chat", first);
+        assert!(emptied.lock().unwrap().is_empty());
+        tracker.finish("chat", second);
+        assert_eq!(*emptied.lock().unwrap(), ["chat"]);
+
+        tracker.finish("chat", second);
+        tracker.finish("chat", first);
+        assert_eq!(*emptied.lock().unwrap(), ["chat"]);
+    }
+
+    #[test]
+    fn removing_a_group_fires_once_even_if_its_toasts_finish_later() {
+        let (tracker, emptied) = recording();
+        let first = tracker.add("chat", Some("1".into()));
+        let second = tracker.add("chat", None);
+
+        tracker.remove_group("chat");
+        tracker.remove_group("chat");
+        tracker.finish("chat", first);
+        tracker.finish("chat", second);
+        assert_eq!(*emptied.lock().unwrap(), ["chat"]);
+    }
+
+    #[test]
+    fn removing_a_tag_fires_only_for_the_last_toast() {
+        let (tracker, emptied) = recording();
+        tracker.add("chat", Some("1".into()));
+        let second = tracker.add("chat", Some("2".into()));
+
+        tracker.remove_tag("chat", "1");
+        tracker.remove_tag("chat", "missing");
+        tracker.remove_tag("other", "2");
+        assert!(emptied.lock().unwrap().is_empty());
+
+        tracker.finish("chat", second);
+        assert_eq!(*emptied.lock().unwrap(), ["chat"]);
+    }
+
+    #[test]
+    fn a_group_filled_again_fires_again() {
+        let (tracker, emptied) = recording();
+        let id = tracker.add("chat", None);
+        tracker.finish("chat", id);
+        let id = tracker.add("chat", None);
+        tracker.finish("chat", id);
+        assert_eq!(*emptied.lock().unwrap(), ["chat", "chat"]);
+    }
+
+    #[test]
+    fn removing_all_fires_for_each_live_group() {
+        let (tracker, emptied) = recording();
+        tracker.add("chat", None);
+        tracker.add("chat", None);
+        let id = tracker.add("mail", None);
+        tracker.add("news", None);
+        tracker.finish("mail", id);
+
+        tracker.remove_all();
+        tracker.remove_all();
+        let mut groups = emptied.lock().unwrap().clone();
+        groups[1..].sort();
+        assert_eq!(groups, ["mail", "chat", "news"]);
+    }
+
+    #[test]
+    fn the_callback_may_track_toasts_again() {
+        let tracker = Arc::new_cyclic(|weak: &Weak<GroupTracker>| {
+            let weak = weak.clone();
+            GroupTracker::new(Box::new(move |group| {
+                let tracker = weak.upgrade().unwrap();
+                if group == "chat" {
+                    tracker.add("follow-up", None);
+                }
+            }))
+        });
+        let id = tracker.add("chat", None);
+        tracker.finish("chat", id);
+        assert!(tracker.live.lock().unwrap().contains_key("follow-up"));
+    }
+}
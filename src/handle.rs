@@ -0,0 +1,126 @@
+use std::sync::{Arc, Mutex};
+
+use windows::{
+    core::HSTRING,
+    UI::Notifications::{ToastNotification, ToastNotificationManager, ToastNotifier},
+};
+
+use crate::{groups::GroupTracker, hs, Result};
+
+/// A toast shown by [`ToastManager::show`](crate::ToastManager::show), to hide it or remove it
+/// from the Action Center later.
+///
+/// Dropping the handle leaves the toast as it is. The handle can be sent to other threads and
+/// kept as long as needed; once the toast is gone, hiding or removing it does nothing.
+#[derive(Clone)]
+pub struct ToastHandle {
+    app_id: HSTRING,
+    tag: Option<String>,
+    group: Option<String>,
+    shown: Arc<Mutex<Option<(ToastNotifier, ToastNotification)>>>,
+    groups: Option<Arc<GroupTracker>>,
+}
+
+impl std::fmt::Debug for ToastHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ToastHandle")
+            .field("app_id", &self.app_id)
+            .field("tag", &self.tag)
+            .field("group", &self.group)
+            .field("shown", &self.is_shown())
+            .finish()
+    }
+}
+
+impl ToastHandle {
+    pub(crate) fn new(
+        app_id: HSTRING,
+        tag: Option<String>,
+        group: Option<String>,
+        shown: Option<(ToastNotifier, ToastNotification)>,
+        groups: Option<Arc<GroupTracker>>,
+    ) -> Self {
+        Self {
+            app_id,
+            tag,
+            group,
+            shown: Arc::new(Mutex::new(shown)),
+            groups,
+        }
+    }
+
+    /// The tag the toast was shown with, including a tag set by the manager.
+    pub fn tag(&self) -> Option<&str> {
+        self.tag.as_deref()
+    }
+
+    /// The group the toast was shown with, including
+    /// [`ToastManager::default_group`](crate::ToastManager::default_group).
+    pub fn group(&self) -> Option<&str> {
+        self.group.as_deref()
+    }
+
+    /// Whether the toast was handed to Windows.
+    ///
+    /// This is `false` for toasts queued by
+    /// [`ToastManager::defer_while_locked`](crate::ToastManager::defer_while_locked) and for
+    /// toasts passed to [`ToastManager::on_undeliverable`](crate::ToastManager::on_undeliverable)
+    /// instead of being shown.
+    pub fn is_shown(&self) -> bool {
+        self.shown.lock().unwrap().is_some()
+    }
+
+    /// Take the toast off the screen, whether it is still a popup or already in the Action Center.
+    ///
+    /// The `on_dismissed` callback of the manager is called with
+    /// [`DismissalReason::ApplicationHidden`](crate::DismissalReason::ApplicationHidden).
+    /// Does nothing if the toast was not shown, see [`ToastHandle::is_shown`].
+    ///
+    /// A toast that was shown again silently after the loops of
+    /// [`Audio::with_loop_count`](crate::Audio::with_loop_count) is hidden as well.
+    pub fn hide(&self) -> Result<()> {
+        if let Some((notifier, notification)) = &*self.shown.lock().unwrap() {
+            notifier.Hide(notification)?;
+        }
+
+        Ok(())
+    }
+
+    /// Hide the toast and replace it with the one `show` shows, which this handle and its
+    /// clones refer to from then on. Does nothing if the toast was not shown.
+    pub(crate) fn replace(&self, show: impl FnOnce() -> Result<ToastHandle>) -> Result<()> {
+        let mut shown = self.shown.lock().unwrap();
+        let Some((notifier, notification)) = &*shown else {
+            return Ok(());
+        };
+        notifier.Hide(notification)?;
+        let replacement = show()?;
+        let replacement = replacement.shown.lock().unwrap().clone();
+        *shown = replacement;
+
+        Ok(())
+    }
+
+    /// Remove the toast from the Action Center by its tag and group.
+    ///
+    /// Unlike [`ToastHandle::hide`], this also works for a toast that was queued by
+    /// [`ToastManager::defer_while_locked`](crate::ToastManager::defer_while_locked) and shown
+    /// later. A toast without a tag can't be addressed in the Action Center, so it is hidden instead.
+    pub fn remove_from_history(&self) -> Result<()> {
+        let Some(tag) = &self.tag else {
+            return self.hide();
+        };
+        let history = ToastNotificationManager::History()?;
+        match &self.group {
+            Some(group) => {
+                history.RemoveGroupedTagWithId(&hs(tag), &hs(group), &self.app_id)?;
+                if let Some(groups) = &self.groups {
+                    groups.remove_tag(group, tag);
+                }
+            }
+            None => history.Remove(&hs(tag))?,
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,14 @@
+/// The 64-bit FNV-1a hash of `parts`, separated so that `["ab", "c"]` and `["a", "bc"]` differ.
+///
+/// Unlike the hashers in `std`, this is guaranteed to be the same across builds, which matters
+/// for ids and file names that outlive the process that created them.
+pub(crate) fn fnv1a<'a>(parts: impl IntoIterator<Item = &'a [u8]>) -> u64 {
+    let mut hash = 0xcbf2_9ce4_8422_2325_u64;
+    for part in parts {
+        for byte in part.iter().copied().chain([0xff]) {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+    hash
+}
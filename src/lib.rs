@@ -1,6 +1,16 @@
 //! A mostly usable binding to the Windows `ToastNotification` API.
 //!
 //! # Example
+//! Show a toast with a title and a body:
+//! ```no_run
+//! use winrt_toast_reborn::ToastManager;
+//!
+//! let manager = ToastManager::new(ToastManager::POWERSHELL_AUM_ID);
+//! manager.notify("Title", "Body").expect("Failed to show toast");
+//! ```
+//!
+//! Apps with their own AUM_ID [`register`] it once, e.g. at install time.
+//! Build a [`Toast`] for more than a title and a body:
 //! ```no_run
 //! use winrt_toast_reborn::{Toast, Text, Header, ToastManager};
 //! use winrt_toast_reborn::content::text::TextPlacement;
@@ -18,6 +28,23 @@
 //!
 //! manager.show(&toast).expect("Failed to show toast");
 //! ```
+//!
+//! # Features
+//! * `toast` (default): showing, updating and removing toasts, which is always available.
+//!   Depend on this crate with `default-features = false, features = ["toast"]` to get only that.
+//! * `registry` (default): the [`register`] and [`unregister`] functions, the only part of this crate that touches the registry.
+//!   Build with `default-features = false` to make sure no registry APIs are reachable.
+//! * `activator`: [`ComActivator`] and [`register_activator`], which deliver toast activations
+//!   through COM, including activations after the app exited. Implies `registry`.
+//! * `format`: the [`format`] module, which formats numbers and times for toast text
+//!   in the user's language.
+//! * `image-validation`: [`Toast::validate`] reads the size of local images from their
+//!   headers, and warns about hero and app logo images with an unusual aspect ratio.
+//! * `winrt-interop`: conversions between this crate's types and their `windows` crate counterparts.
+//! * `schema-validation`: [`Toast::check_schema`] and [`check_toast_xml`], which check toast XML
+//!   against a table of the toast content schema.
+//! * `serde`: `Serialize` and `Deserialize` for [`ToastStyle`] and the types it is made of,
+//!   so styles can be shipped as data.
 
 #![warn(missing_docs)]
 
@@ -30,17 +57,134 @@ pub use content::header::Header;
 pub use content::image::Image;
 pub use content::input::Input;
 pub use content::input::Selection;
+pub use content::input::SelectionGroup;
+pub use content::progress::{Progress, ProgressValue};
 pub use content::text::Text;
+
+use std::{ffi::OsString, path::PathBuf};
+
 use thiserror::Error;
 
 mod manager;
-pub use manager::{ActivatedAction, DismissalReason, ToastManager};
+pub use manager::{
+    ActivatedAction, DismissalReason, HistoryEntry, NotificationUpdateResult, ShowOptions,
+    ShowReport, ShutdownPolicy, ToastDismissed, ToastEvent, ToastFailed, ToastManager,
+    UndeliverableReason,
+};
 
 mod toast;
-pub use toast::{Scenario, Toast, ToastDuration};
+pub use toast::{Scenario, Toast, ToastDuration, ToastPriority};
 
+#[cfg(feature = "registry")]
 mod register;
-pub use register::register;
+#[cfg(feature = "activator")]
+pub use register::register_activator;
+#[cfg(feature = "registry")]
+pub use register::{
+    has_package_identity, list_registered, register, unregister, RegistrationInfo,
+    RegistrationResult, UnregistrationResult,
+};
+
+#[cfg(feature = "activator")]
+mod activator;
+#[cfg(feature = "activator")]
+pub use activator::ComActivator;
+
+mod apartment;
+pub use apartment::{ensure_apartment, Apartment};
+
+mod data;
+pub use data::ToastData;
+
+mod validation;
+pub use validation::ValidationIssue;
+
+mod aumid;
+
+mod tag;
+pub use tag::Tag;
+
+mod media;
+pub use media::path_to_media_url;
+
+mod template;
+pub use template::{LegacyTemplate, ToastTemplate};
+
+mod session;
+
+mod rendered;
+
+mod groups;
+
+mod focus;
+pub use focus::{focus_session_state, FocusSessionState};
+
+mod hash;
+
+#[cfg(feature = "image-validation")]
+mod dimensions;
+
+mod cache;
+pub use cache::CacheConfig;
+
+mod messaging;
+pub use messaging::ConversationToast;
+
+mod feedback;
+
+mod correlation;
+
+mod ringing;
+
+mod detached;
+pub use detached::ShowTicket;
+
+mod handle;
+pub use handle::ToastHandle;
+
+mod dismissal;
+
+mod metrics;
+pub use metrics::Metrics;
+
+mod overflow;
+pub use overflow::OverflowStrategy;
+
+mod quiet_hours;
+pub use quiet_hours::QuietHours;
+
+mod style;
+pub use style::ToastStyle;
+
+mod merge;
+
+mod diagnostics;
+pub use diagnostics::{DiagnosticReport, NotificationSetting, ShowDiagnostics};
+
+mod preview;
+
+mod placeholder;
+pub use placeholder::PendingImage;
+
+mod xml;
+
+#[cfg(feature = "schema-validation")]
+mod schema;
+#[cfg(feature = "schema-validation")]
+pub use schema::check_toast_xml;
+
+/// Locale-aware formatting of numbers and times for toast text.
+#[cfg(feature = "format")]
+pub mod format;
+
+/// Limits of the toast schema and of Windows, as checked by [`Toast::validate`].
+///
+/// Unless noted otherwise, the limits apply to all versions of Windows 10 and 11 that
+/// support adaptive toasts, i.e. Windows 10 version 1607 and later.
+pub mod limits;
+
+/// Conversions between [`SystemTime`](std::time::SystemTime) and the WinRT `DateTime`.
+pub mod time;
 
 /// Re-export of the `url` crate.
 pub use url;
@@ -52,6 +196,13 @@ pub(crate) fn hs(s: impl AsRef<str>) -> HSTRING {
     HSTRING::from(s)
 }
 
+/// Call a user callback, keeping a panic in it from unwinding into WinRT or COM.
+///
+/// The panic is still reported by the panic hook, which prints it to stderr by default.
+pub(crate) fn catch_panic(f: impl FnOnce()) {
+    let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(f));
+}
+
 /// The error type used in this crate.
 #[derive(Debug, Error)]
 #[non_exhaustive]
@@ -62,16 +213,229 @@ pub enum WinToastError {
     /// Error from the Windows Runtime.
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
-    /// The given path is not absolute, and therefore cannot be converted to a URL.
-    #[error("The given path is not absolute")]
-    InvalidPath,
-    /// The dismissal reason from OS is unknown
-    #[error("The dismissal reason from OS is unknown")]
+    /// The given path is not absolute, or cannot be converted to a URL.
+    #[error("The path {0:?} is not absolute, or cannot be converted to a URL")]
+    InvalidPath(PathBuf),
+    /// A toast passed to [`ToastManager::show_detached`] was not shown because the worker
+    /// thread stopped, e.g. because showing it panicked.
+    #[error("The detached show ended without a result")]
+    ShowAborted,
+    /// Windows doesn't know the AUM_ID, so it can't show toasts for it.
+    ///
+    /// Register it with [`register`](crate::register) before showing toasts, or use the
+    /// AUM_ID of a shortcut or package that an installer registered. The AUM_ID is given.
+    #[error("The AUM_ID {0:?} is not registered; call `register` first")]
+    UnregisteredAppId(String),
+    /// The AUM_ID is not usable, e.g. because of surrounding whitespace.
+    ///
+    /// The message names the AUM_ID and the problem, see [`ToastManager::try_new`].
+    #[error("Invalid AUM_ID {0}")]
+    InvalidAumid(String),
+    /// A tag was rejected by [`Tag::new`].
+    ///
+    /// The message names the tag and the problem.
+    #[error("Invalid tag {0}")]
+    InvalidTag(String),
+    /// A string is not valid Unicode, e.g. a path with an unpaired surrogate,
+    /// and cannot be represented where it is used without losing information.
+    #[error("{0:?} is not valid Unicode")]
+    NonUnicode(OsString),
+    /// The XML given to [`ToastManager::show_xml`], or produced for [`Toast::preview`], can't be parsed.
+    ///
+    /// The message of the error from Windows describes what is wrong, and the position
+    /// tells where, if Windows reports it.
+    #[error(
+        "Invalid toast XML{}: {source}",
+        position
+            .map(|(line, column)| format!(" at line {}, position {}", line, column))
+            .unwrap_or_default()
+    )]
+    InvalidXml {
+        /// The error from Windows.
+        source: windows::core::Error,
+        /// The line of the error and the position within the line, both starting at 1.
+        position: Option<(u32, u32)>,
+    },
+    /// The XML of a toast has an element or attribute that the toast schema doesn't allow
+    /// where it is, see [`Toast::check_schema`].
+    #[cfg(feature = "schema-validation")]
+    #[error("The toast XML doesn't match the schema: {0}")]
+    SchemaViolation(String),
+    /// Windows failed to build a toast before showing it, e.g. to write its XML document
+    /// or to create its notification.
+    ///
+    /// Retrying won't help, as the same toast fails the same way again, see
+    /// [`WinToastError::is_transient`]. Errors specific to the content of the toast,
+    /// such as [`WinToastError::Render`], are reported as they are.
+    #[error("Failed to build the toast ({stage:?}): {source}")]
+    BuildFailed {
+        /// The step of building the toast that failed.
+        stage: BuildStage,
+        /// The error from Windows, with its HRESULT.
+        source: windows::core::Error,
+    },
+    /// Windows failed to create the notifier for the AUM_ID, which may succeed on a retry.
+    ///
+    /// An AUM_ID that is not registered is reported as [`WinToastError::UnregisteredAppId`] instead.
+    #[error("The notification platform is unavailable: {0}")]
+    NotifierUnavailable(windows::core::Error),
+    /// Windows failed to create an XML document, which may succeed on a retry.
+    ///
+    /// This comes from the platform, not from the toast, e.g. when it runs out of memory.
+    #[error("Windows failed to create an XML document: {0}")]
+    DocumentUnavailable(windows::core::Error),
+    /// Windows failed to show or schedule a toast that was built, which may succeed on a retry.
+    #[error("Windows failed to show the toast: {source} ({diagnostics})")]
+    ShowFailed {
+        /// The error from Windows, with its HRESULT.
+        source: windows::core::Error,
+        /// What was known about the app and the toast, with hints on what to check.
+        diagnostics: Box<ShowDiagnostics>,
+    },
+    /// Windows failed to write a part of the XML document of a toast.
+    #[error(
+        "Failed to write {element}{}: {source}",
+        attribute.as_ref().map(|name| format!(" attribute {}", name)).unwrap_or_default()
+    )]
+    Render {
+        /// The element, such as `<image id=2>`.
+        element: String,
+        /// The attribute, or `None` for the text content.
+        attribute: Option<String>,
+        /// The error from Windows.
+        source: windows::core::Error,
+    },
+    /// The dismissal reason could not be read from the OS.
+    ///
+    /// Reasons that are unknown to this crate are reported as [`DismissalReason::Other`] instead.
+    #[error("The dismissal reason could not be read from the OS")]
     InvalidDismissalReason,
+    /// The toast failed validation.
+    #[error("Invalid toast: {0}")]
+    Validation(#[from] ValidationIssue),
+    /// The content does not fit the chosen legacy template.
+    #[error("The content does not fit the {0:?} template")]
+    TemplateMismatch(LegacyTemplate),
+    /// The `ToastTemplateType` is not one of the legacy templates this crate knows about.
+    #[error("Unknown toast template type {0}")]
+    UnknownTemplateType(i32),
+    /// The snooze interval picked by the user is not a positive number of minutes.
+    #[error("Invalid snooze interval: {0:?}")]
+    InvalidSnoozeInterval(String),
+    /// A toast was scheduled for a delivery time that is not in the future.
+    #[error("The delivery time {0:?} is not in the future")]
+    DeliveryTimeInPast(std::time::SystemTime),
+    /// The time cannot be represented as a Windows date and time, or the local time can't be read.
+    #[error("The time is out of the range supported by Windows, or the local time can't be read")]
+    TimeConversion,
+    /// The given hour and minute do not form a valid time of day.
+    #[error("Invalid time of day: {0:02}:{1:02}")]
+    InvalidTimeOfDay(u8, u8),
+    /// The thread was already initialized with a different COM apartment model.
+    #[error("The thread is already initialized with a different apartment than {0:?}")]
+    ApartmentConflict(Apartment),
     /// The toast is not initialized properly.
     #[error("Unknown error")]
     Unknown,
 }
 
+impl WinToastError {
+    /// Whether the error is from the notification platform rather than from the toast,
+    /// so that showing the same toast again may succeed.
+    ///
+    /// This is [`WinToastError::NotifierUnavailable`], [`WinToastError::DocumentUnavailable`]
+    /// and [`WinToastError::ShowFailed`]. Retry policies should give up on every other error.
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            WinToastError::NotifierUnavailable(_)
+                | WinToastError::DocumentUnavailable(_)
+                | WinToastError::ShowFailed { .. }
+        )
+    }
+
+    /// Attribute an error from Windows to the `stage` of building a toast.
+    pub(crate) fn in_stage(self, stage: BuildStage) -> Self {
+        match self {
+            WinToastError::Os(source) => WinToastError::BuildFailed { stage, source },
+            other => other,
+        }
+    }
+}
+
+/// A step of building a toast, see [`WinToastError::BuildFailed`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum BuildStage {
+    /// Writing the XML document of the toast. Failing to create the empty document is
+    /// [`WinToastError::DocumentUnavailable`] instead.
+    Document,
+    /// Creating the notification from the XML document, and setting its properties,
+    /// data and event handlers.
+    Notification,
+}
+
 /// The result type used in this crate.
 pub type Result<T> = std::result::Result<T, WinToastError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An error from Windows, created without calling into Windows.
+    fn os_error() -> windows::core::Error {
+        String::from_utf16(&[0xD800]).unwrap_err().into()
+    }
+
+    #[test]
+    fn errors_from_windows_are_attributed_to_their_stage() {
+        for stage in [BuildStage::Document, BuildStage::Notification] {
+            let error = WinToastError::Os(os_error()).in_stage(stage);
+            assert!(matches!(error, WinToastError::BuildFailed { stage: s, .. } if s == stage));
+        }
+
+        let render = WinToastError::Render {
+            element: "<text>".to_string(),
+            attribute: None,
+            source: os_error(),
+        };
+        let render = render.in_stage(BuildStage::Document);
+        assert!(matches!(render, WinToastError::Render { .. }));
+        let invalid = WinToastError::Validation(ValidationIssue::EmptyToast);
+        let invalid = invalid.in_stage(BuildStage::Notification);
+        assert!(matches!(invalid, WinToastError::Validation(_)));
+    }
+
+    #[test]
+    fn only_platform_errors_are_transient() {
+        let diagnostics = ShowDiagnostics {
+            aum_id: "App".to_string(),
+            registered: None,
+            setting: None,
+            xml_size: 0,
+        };
+        let transient = [
+            WinToastError::NotifierUnavailable(os_error()),
+            WinToastError::DocumentUnavailable(os_error()),
+            WinToastError::ShowFailed {
+                source: os_error(),
+                diagnostics: Box::new(diagnostics),
+            },
+        ];
+        for error in transient {
+            assert!(error.is_transient(), "{:?}", error);
+        }
+
+        let permanent = [
+            WinToastError::BuildFailed {
+                stage: BuildStage::Document,
+                source: os_error(),
+            },
+            WinToastError::Os(os_error()),
+            WinToastError::Validation(ValidationIssue::EmptyToast),
+        ];
+        for error in permanent {
+            assert!(!error.is_transient(), "{:?}", error);
+        }
+    }
+}
@@ -0,0 +1,52 @@
+use std::{ops::RangeInclusive, time::Duration};
+
+/// The length of an AUM_ID, in UTF-16 code units.
+pub const MAX_AUM_ID_LEN: usize = 129;
+
+/// The number of text elements in the visual of a toast.
+///
+/// Later text elements are not shown, see [`Toast::texts`](crate::Toast::texts).
+pub const MAX_TEXTS: usize = 3;
+
+/// The number of buttons and context menu items in a toast.
+///
+/// Windows does not show a toast with more actions at all.
+pub const MAX_ACTIONS: usize = 5;
+
+/// The number of selections of a selection input.
+pub const MAX_SELECTIONS_PER_INPUT: usize = 5;
+
+/// The length of the tag of a toast, in UTF-16 code units.
+///
+/// Windows 10 before version 1703 only supported 16 code units.
+pub const MAX_TAG_LEN: usize = 64;
+
+/// The length of the group of a toast, in UTF-16 code units.
+///
+/// Windows 10 before version 1703 only supported 16 code units.
+pub const MAX_GROUP_LEN: usize = 64;
+
+/// The size of an image file shown in a toast, in bytes.
+///
+/// Windows leaves out larger images. Images from the web are limited to 1 MB on metered
+/// connections, which is not checked.
+pub const MAX_IMAGE_FILE_SIZE: u64 = 3 * 1024 * 1024;
+
+/// How long a toast with [`ToastDuration::Short`](crate::ToastDuration::Short), the default,
+/// is shown before it moves to the Action Center.
+///
+/// The user can lengthen it in the accessibility settings.
+pub const SHORT_DURATION: Duration = Duration::from_secs(7);
+
+/// How long a toast with [`ToastDuration::Long`](crate::ToastDuration::Long)
+/// is shown before it moves to the Action Center.
+pub const LONG_DURATION: Duration = Duration::from_secs(25);
+
+/// The versions of the toast schema known to this crate, see
+/// [`Toast::schema_version`](crate::Toast::schema_version).
+pub const SCHEMA_VERSIONS: RangeInclusive<u32> = 1..=4;
+
+/// The size of the XML of a toast, in bytes, that Windows documents as the limit for
+/// push notifications. Local toasts near this size may fail to show as well,
+/// see [`ShowDiagnostics`](crate::ShowDiagnostics).
+pub const MAX_XML_SIZE: usize = 5 * 1024;
@@ -1,15 +1,46 @@
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    num::ParseIntError,
+    str::ParseBoolError,
+    sync::{atomic::Ordering, mpsc, Arc, Mutex, OnceLock},
+    time::{Duration, Instant, SystemTime},
+};
+
 use windows::{
     core::{IInspectable, Interface, HSTRING},
-    Data::Xml::Dom::XmlDocument,
-    Foundation::{DateTime, IReference, PropertyValue, TypedEventHandler},
-    Globalization::Calendar,
+    Data::Xml::Dom::{XmlDocument, XmlElement},
+    Foundation::{
+        Collections::{IKeyValuePair, ValueSet},
+        DateTime, IPropertyValue, IReference, PropertyType, PropertyValue, TypedEventHandler,
+    },
+    Win32::Foundation::ERROR_NOT_FOUND,
     UI::Notifications::{
-        ToastActivatedEventArgs, ToastDismissalReason, ToastDismissedEventArgs,
-        ToastFailedEventArgs, ToastNotification, ToastNotificationManager,
+        NotificationData, NotificationSetting, ScheduledToastNotification, ToastActivatedEventArgs,
+        ToastDismissalReason, ToastDismissedEventArgs, ToastFailedEventArgs, ToastNotification,
+        ToastNotificationManager, ToastNotificationPriority, ToastNotifier,
     },
 };
 
-use crate::{hs, Result, Toast, WinToastError};
+use crate::{
+    aumid, catch_panic,
+    content::audio::Sound,
+    correlation,
+    detached::{self, ShowTicket},
+    dismissal::DismissalCallbacks,
+    feedback,
+    groups::GroupTracker,
+    hs,
+    metrics::{Counters, InputLimits},
+    overflow::FULL_TITLE_KEY,
+    placeholder::{self, PendingImage},
+    rendered::RenderedXml,
+    ringing::{Ringing, Timers},
+    session::{self, LockDeferral, Queued},
+    tag, time, xml, Audio, BuildStage, CacheConfig, DiagnosticReport, Image, Metrics, QuietHours,
+    Result, ShowDiagnostics, Tag, Toast, ToastData, ToastHandle, ToastPriority, ToastTemplate,
+    ValidationIssue, WinToastError,
+};
 
 /// Represents an action that was activated by the user.
 /// This is passed to the `on_activated` callback.
@@ -18,19 +49,96 @@ use crate::{hs, Result, Toast, WinToastError};
 ///
 /// * `arg`: The argument string that was passed to the action.
 /// * `value`: The string that was passed to the input field.
+/// * `values`: The strings that were passed to all input fields.
+///
+/// New fields may be added in minor releases, so this can't be built with a struct
+/// expression outside this crate; use [`ActivatedAction::new`] instead, e.g. in tests.
 #[derive(Debug, Clone)]
+#[non_exhaustive]
 pub struct ActivatedAction {
     /// The argument string that was passed to the action.
     pub arg: String,
-    /// The string that was passed to the input field.
-    /// This is only present if the action was associated with an input field.
+    /// The string that was passed to the input field given to `on_activated`.
+    /// This is only present if the toast has that input field.
+    /// A submitted empty input is `Some("")`.
     pub value: Option<String>,
+    /// The strings that were passed to all input fields, keyed by input id.
+    /// Empty inputs are kept as empty strings. Values of other types are converted to strings:
+    /// booleans become `"true"` or `"false"`, and numbers are formatted as by [`ToString`].
+    pub values: HashMap<String, String>,
+    /// The full title of the toast, if it was too long and attached with
+    /// [`OverflowStrategy::Attach`](crate::OverflowStrategy::Attach).
+    pub full_title: Option<String>,
+    /// The correlation id of the toast, see [`Toast::correlation_id`].
+    pub correlation_id: Option<String>,
+}
+
+impl ActivatedAction {
+    /// Create an activated action with the argument `arg` and the input `values`.
+    pub fn new(arg: impl Into<String>, values: HashMap<String, String>) -> Self {
+        Self {
+            arg: arg.into(),
+            value: None,
+            values,
+            full_title: None,
+            correlation_id: None,
+        }
+    }
+
+    /// Set the value of the input field given to `on_activated`.
+    pub fn with_value(mut self, value: impl Into<String>) -> Self {
+        self.value = Some(value.into());
+        self
+    }
+
+    /// Set the full title attached to the toast.
+    pub fn with_full_title(mut self, full_title: impl Into<String>) -> Self {
+        self.full_title = Some(full_title.into());
+        self
+    }
+
+    /// Set the correlation id of the toast.
+    pub fn with_correlation_id(mut self, correlation_id: impl Into<String>) -> Self {
+        self.correlation_id = Some(correlation_id.into());
+        self
+    }
+
+    /// The value of the input with `id`, matched case-sensitively.
+    pub fn value_str(&self, id: &str) -> Option<&str> {
+        self.values.get(id).map(String::as_str)
+    }
+
+    /// The value of the input with `id` parsed as a boolean, i.e. `"true"` or `"false"`.
+    ///
+    /// Returns `None` if there is no such input, see [`ActivatedAction::value_str`].
+    pub fn value_bool(&self, id: &str) -> Option<std::result::Result<bool, ParseBoolError>> {
+        self.value_str(id).map(str::parse)
+    }
+
+    /// The value of the input with `id` parsed as an integer, ignoring surrounding whitespace.
+    ///
+    /// Returns `None` if there is no such input, see [`ActivatedAction::value_str`].
+    pub fn value_int(&self, id: &str) -> Option<std::result::Result<i64, ParseIntError>> {
+        self.value_str(id).map(|value| value.trim().parse())
+    }
+
+    /// The input values as `(id, value)` pairs, sorted by id.
+    pub fn values_sorted(&self) -> Vec<(&str, &str)> {
+        let mut values: Vec<_> = self
+            .values
+            .iter()
+            .map(|(id, value)| (id.as_str(), value.as_str()))
+            .collect();
+        values.sort_unstable();
+        values
+    }
 }
 
 /// Specifies the reason that a toast notification is no longer being shown
 ///
 /// See <https://docs.microsoft.com/en-us/uwp/api/windows.ui.notifications.toastdismissalreason>
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum DismissalReason {
     /// The user dismissed the toast notification.
     UserCanceled,
@@ -40,17 +148,543 @@ pub enum DismissalReason {
     /// The maximum time to show a toast notification is 7 seconds except in the case of long-duration toasts,
     /// in which case it is 25 seconds.
     TimedOut,
+    /// A reason added in a later version of Windows, with its raw value.
+    Other(i32),
 }
 
 impl DismissalReason {
-    fn from_winrt(reason: ToastDismissalReason) -> Result<Self> {
+    fn from_winrt(reason: ToastDismissalReason) -> Self {
         match reason {
-            ToastDismissalReason::UserCanceled => Ok(DismissalReason::UserCanceled),
-            ToastDismissalReason::ApplicationHidden => Ok(DismissalReason::ApplicationHidden),
-            ToastDismissalReason::TimedOut => Ok(DismissalReason::TimedOut),
-            _ => Err(WinToastError::InvalidDismissalReason),
+            ToastDismissalReason::UserCanceled => DismissalReason::UserCanceled,
+            ToastDismissalReason::ApplicationHidden => DismissalReason::ApplicationHidden,
+            ToastDismissalReason::TimedOut => DismissalReason::TimedOut,
+            ToastDismissalReason(other) => DismissalReason::Other(other),
+        }
+    }
+}
+
+/// The outcome of updating the data of a toast notification.
+///
+/// See <https://docs.microsoft.com/en-us/uwp/api/windows.ui.notifications.notificationupdateresult>
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotificationUpdateResult {
+    /// The notification was updated.
+    Succeeded,
+    /// The notification could not be updated.
+    Failed,
+    /// No notification with the given tag and group was found.
+    NotificationNotFound,
+}
+
+impl NotificationUpdateResult {
+    fn from_winrt(result: windows::UI::Notifications::NotificationUpdateResult) -> Self {
+        use windows::UI::Notifications::NotificationUpdateResult as Winrt;
+        match result {
+            Winrt::Succeeded => NotificationUpdateResult::Succeeded,
+            Winrt::NotificationNotFound => NotificationUpdateResult::NotificationNotFound,
+            _ => NotificationUpdateResult::Failed,
+        }
+    }
+}
+
+/// The properties of a toast shown from XML with [`ToastManager::show_xml`].
+#[derive(Debug, Clone, Default)]
+pub struct ShowOptions {
+    tag: Option<String>,
+    group: Option<String>,
+    expires_in: Option<Duration>,
+    data: Option<ToastData>,
+}
+
+impl ShowOptions {
+    /// Create options without tag, group, expiration or data.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The tag of the toast, see [`Toast::tag`].
+    pub fn with_tag(mut self, tag: impl Into<String>) -> Self {
+        self.tag = Some(tag.into());
+        self
+    }
+
+    /// The group of the toast, see [`Toast::group`].
+    pub fn with_group(mut self, group: impl Into<String>) -> Self {
+        self.group = Some(group.into());
+        self
+    }
+
+    /// The expiration time of the toast, see [`Toast::expires_in`].
+    pub fn with_expires_in(mut self, expires_in: Duration) -> Self {
+        self.expires_in = Some(expires_in);
+        self
+    }
+
+    /// The values of the data-bound placeholders in the XML, see [`ToastData`].
+    pub fn with_data(mut self, data: ToastData) -> Self {
+        self.data = Some(data);
+        self
+    }
+}
+
+/// Something that happened to a toast shown with [`ToastManager::show_collecting`] or
+/// [`ToastManager::show_detached_events`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ToastEvent {
+    /// Windows accepted the toast, see [`ToastManager::show_detached_events`].
+    Shown(ToastHandle),
+    /// The toast was activated, as passed to the `on_activated` callback.
+    Activated(Option<ActivatedAction>),
+    /// The toast is no longer being shown, as passed to the `on_dismissed` callback.
+    Dismissed(Result<ToastDismissed>),
+    /// The toast could not be shown, with the error passed to the `on_failed` callback.
+    Failed(WinToastError),
+}
+
+/// What [`ToastManager::show_reported`] did with a toast, beyond showing it as given.
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct ShowReport {
+    /// The tag of the toast, if it has one.
+    pub tag: Option<Tag>,
+    /// The options that changed the toast, named after their builder methods,
+    /// e.g. `"quiet_hours"` when the toast was silenced, or `"respect_focus_assist"`
+    /// when its popup was suppressed.
+    pub applied_defaults: Vec<&'static str>,
+    /// The warnings found while validating the toast, which was shown anyway.
+    pub degradations: Vec<ValidationIssue>,
+    /// Whether the toast replaced one with the same tag and group that was still in the
+    /// Action Center, as Windows does for such toasts instead of showing both.
+    ///
+    /// This is read from the history of the Action Center right before the toast is shown,
+    /// and is `false` if the history can't be read.
+    pub deduped: bool,
+    /// Whether the toast was queued until the session is unlocked instead of being shown,
+    /// see [`ToastManager::defer_while_locked`].
+    pub deferred: bool,
+    /// Why the toast was passed to [`ToastManager::on_undeliverable`] instead of being shown,
+    /// if it was. This is [`UndeliverableReason::Disabled`] for a toast of an app whose
+    /// notifications are disabled; the other reasons are returned as errors.
+    pub undelivered: Option<UndeliverableReason>,
+}
+
+impl ShowReport {
+    /// The report on `toast` before it is shown: its tag and the warnings among `issues`.
+    fn new(toast: &Toast, issues: &[ValidationIssue]) -> Self {
+        Self {
+            tag: toast.tag.clone().and_then(|tag| Tag::new(tag).ok()),
+            degradations: issues.iter().filter(|i| i.is_warning()).cloned().collect(),
+            ..Self::default()
+        }
+    }
+
+    /// Whether the toast replaced one in the Action Center, given whether one with its tag
+    /// and group was there before; a toast that was not shown replaced nothing.
+    fn replaced(&self, shown_before: bool) -> bool {
+        shown_before && !self.deferred && self.undelivered.is_none()
+    }
+}
+
+/// What [`ToastManager::shutdown`] does with the work of a manager that is still pending.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ShutdownPolicy {
+    /// Show the toasts queued by [`ToastManager::defer_while_locked`] right away,
+    /// even if the session is still locked.
+    Flush,
+    /// Drop the queued toasts, passing them to [`ToastManager::on_undeliverable`] with
+    /// [`UndeliverableReason::Cancelled`].
+    ///
+    /// Toasts that were already shown, as a popup or in the Action Center, are left alone,
+    /// also those of other apps and processes using the same AUM_ID; use
+    /// [`ToastManager::clear`] to remove them.
+    CancelAll,
+    /// Leave everything as it is, which is also what dropping the manager does,
+    /// except for stopping the loop count timers, see [`ToastManager::shutdown`].
+    ///
+    /// Queued toasts are still shown by the clones of this manager, and dropped once the
+    /// last of them is gone. Toasts in the Action Center stay there.
+    #[default]
+    Detach,
+}
+
+/// Why a toast won't be shown, passed to [`ToastManager::on_undeliverable`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum UndeliverableReason {
+    /// Notifications are disabled for this app, by the user, a group policy or the manifest.
+    Disabled,
+    /// The toast failed validation, see [`ToastManager::validate`].
+    Invalid(ValidationIssue),
+    /// Windows failed to show the toast. The message of the error is given.
+    Failed(String),
+    /// The toast expired while it was held back by [`ToastManager::defer_while_locked`].
+    ExpiredWhileLocked,
+    /// The toast was held back by [`ToastManager::defer_while_locked`] and dropped by
+    /// [`ShutdownPolicy::CancelAll`].
+    Cancelled,
+}
+
+type UndeliverableHandler = Arc<dyn Fn(&Toast, UndeliverableReason) + Send + Sync>;
+type ActivationCallback = Arc<Mutex<Box<dyn FnMut(Option<ActivatedAction>) + Send>>>;
+
+/// A toast notification that is no longer being shown.
+/// This is passed to the `on_dismissed` callback.
+///
+/// New fields may be added in minor releases, use [`ToastDismissed::new`] to build one outside this crate.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct ToastDismissed {
+    /// Why the toast is no longer being shown.
+    pub reason: DismissalReason,
+    /// The tag of the toast, if it has one that passes [`Tag::new`].
+    pub tag: Option<Tag>,
+    /// The group of the toast, if it has one.
+    pub group: Option<String>,
+    /// When the toast expires from the Action Center, if it has an expiration time.
+    pub expires_at: Option<SystemTime>,
+    /// The correlation id of the toast, see [`Toast::correlation_id`].
+    pub correlation_id: Option<String>,
+}
+
+impl ToastDismissed {
+    /// Create a dismissal for `reason`, of a toast without tag, group, expiration time
+    /// and correlation id.
+    pub fn new(reason: DismissalReason) -> Self {
+        Self {
+            reason,
+            tag: None,
+            group: None,
+            expires_at: None,
+            correlation_id: None,
+        }
+    }
+
+    /// Set the tag of the dismissed toast.
+    pub fn with_tag(mut self, tag: Tag) -> Self {
+        self.tag = Some(tag);
+        self
+    }
+
+    /// Set the group of the dismissed toast.
+    pub fn with_group(mut self, group: impl Into<String>) -> Self {
+        self.group = Some(group.into());
+        self
+    }
+
+    /// Set the expiration time of the dismissed toast.
+    pub fn with_expires_at(mut self, expires_at: SystemTime) -> Self {
+        self.expires_at = Some(expires_at);
+        self
+    }
+
+    /// Set the correlation id of the dismissed toast.
+    pub fn with_correlation_id(mut self, correlation_id: impl Into<String>) -> Self {
+        self.correlation_id = Some(correlation_id.into());
+        self
+    }
+}
+
+/// A toast notification that Windows failed to show.
+/// This is passed to the `on_failed` callback.
+///
+/// New fields may be added in minor releases, use [`ToastFailed::new`] to build one outside this crate.
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct ToastFailed {
+    /// Why the toast could not be shown.
+    pub error: WinToastError,
+    /// The tag of the toast, if it has one that passes [`Tag::new`].
+    pub tag: Option<Tag>,
+    /// The group of the toast, if it has one.
+    pub group: Option<String>,
+    /// The correlation id of the toast, see [`Toast::correlation_id`].
+    pub correlation_id: Option<String>,
+}
+
+impl ToastFailed {
+    /// Create a failure with `error`, of a toast without tag, group and correlation id.
+    pub fn new(error: WinToastError) -> Self {
+        Self {
+            error,
+            tag: None,
+            group: None,
+            correlation_id: None,
+        }
+    }
+
+    /// Set the tag of the failed toast.
+    pub fn with_tag(mut self, tag: Tag) -> Self {
+        self.tag = Some(tag);
+        self
+    }
+
+    /// Set the group of the failed toast.
+    pub fn with_group(mut self, group: impl Into<String>) -> Self {
+        self.group = Some(group.into());
+        self
+    }
+
+    /// Set the correlation id of the failed toast.
+    pub fn with_correlation_id(mut self, correlation_id: impl Into<String>) -> Self {
+        self.correlation_id = Some(correlation_id.into());
+        self
+    }
+}
+
+/// A toast notification of this app that is still in the Action Center.
+/// This is returned by [`ToastManager::history`].
+///
+/// New fields may be added in minor releases.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct HistoryEntry {
+    /// The tag of the toast, if it has one that passes [`Tag::new`].
+    pub tag: Option<Tag>,
+    /// The group of the toast, if it has one.
+    pub group: Option<String>,
+    /// When the toast expires from the Action Center, if it has an expiration time.
+    pub expires_at: Option<SystemTime>,
+    /// The remote ID of the toast, if it has one, see [`Toast::remote_id`].
+    pub remote_id: Option<String>,
+    /// The XML of the toast, as it was shown.
+    pub xml: String,
+    /// The texts of the toast in document order, with their placeholders unfilled.
+    pub texts: Vec<String>,
+    /// The current values of the data-bound placeholders of the toast, see [`ToastData`].
+    pub data: HashMap<String, String>,
+}
+
+impl HistoryEntry {
+    fn from_winrt(notification: &ToastNotification) -> Self {
+        let content = notification.Content().ok();
+        let texts = content
+            .as_ref()
+            .and_then(|doc| doc.GetElementsByTagName(&hs("text")).ok())
+            .map(|texts| {
+                texts
+                    .into_iter()
+                    .filter_map(|text| text.InnerText().ok())
+                    .map(|text| text.to_string())
+                    .collect()
+            })
+            .unwrap_or_default();
+        let data = notification
+            .Data()
+            .and_then(|data| data.Values())
+            .and_then(|values| values.First())
+            .map(|iter| {
+                iter.filter_map(|pair| Some((pair.Key().ok()?, pair.Value().ok()?)))
+                    .map(|(key, value)| (key.to_string(), value.to_string()))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Self {
+            tag: Tag::from_os(notification.Tag()),
+            group: non_empty(notification.Group()),
+            expires_at: expiration_time(notification),
+            remote_id: non_empty(notification.RemoteId()),
+            xml: content
+                .and_then(|doc| doc.GetXml().ok())
+                .map(|xml| xml.to_string())
+                .unwrap_or_default(),
+            texts,
+            data,
+        }
+    }
+}
+
+/// Keep `toast` from playing any sound, downgrading looping sounds.
+fn silence(toast: &mut Toast) {
+    toast.audio = Some(match &toast.audio {
+        Some(audio) => audio.silenced(),
+        None => Audio::new(Sound::Default).with_silent(),
+    });
+}
+
+/// Put `toast` in the `default` group if it has no group of its own, and return whether it was.
+fn with_default_group(toast: &mut Cow<'_, Toast>, default: Option<&str>) -> bool {
+    match (&toast.group, default) {
+        (None, Some(group)) => {
+            toast.to_mut().group = Some(group.to_string());
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Bind the first button of `toast` to its text input if no action is bound to it,
+/// and return whether it was.
+fn anchor_input(toast: &mut Cow<'_, Toast>) -> bool {
+    let Some(input_id) = toast.unanchored_text_input().map(|input| input.id.clone()) else {
+        return false;
+    };
+    let Some(i) = toast.actions.iter().position(|a| !a.is_context_menu()) else {
+        return false;
+    };
+    let action = &mut toast.to_mut().actions[i];
+    *action = action.clone().with_input_id(input_id);
+    true
+}
+
+/// Read input values with `read`, up to the limits.
+///
+/// Values beyond the limits are dropped or truncated and counted, without reading the dropped
+/// ones, and control characters are removed from the keys.
+fn limit_input_values<T>(
+    entries: impl IntoIterator<Item = T>,
+    read: impl Fn(T) -> Option<(String, String)>,
+    limits: &InputLimits,
+    counters: &Counters,
+) -> HashMap<String, String> {
+    let InputLimits {
+        max_values,
+        max_value_len,
+    } = *limits;
+    let mut values = HashMap::new();
+    let (mut dropped, mut truncated) = (0, 0);
+    for entry in entries {
+        if values.len() >= max_values {
+            dropped += 1;
+            continue;
+        }
+        let Some((key, mut value)) = read(entry) else {
+            continue;
+        };
+        let key: String = key.chars().filter(|c| !c.is_control()).collect();
+        if let Some((end, _)) = value.char_indices().nth(max_value_len) {
+            value.truncate(end);
+            truncated += 1;
+        }
+        values.insert(key, value);
+    }
+    counters
+        .dropped_input_values
+        .fetch_add(dropped, Ordering::Relaxed);
+    counters
+        .truncated_input_values
+        .fetch_add(truncated, Ordering::Relaxed);
+    values
+}
+
+/// Call `update` for each tag of the `shown` toasts in `group`, skipping toasts without a tag.
+fn fan_out<T>(
+    shown: impl IntoIterator<Item = (Option<Tag>, Option<String>)>,
+    group: &str,
+    mut update: impl FnMut(&Tag) -> T,
+) -> Vec<(Tag, T)> {
+    shown
+        .into_iter()
+        .filter(|(_, shown_group)| shown_group.as_deref() == Some(group))
+        .filter_map(|(tag, _)| tag)
+        .map(|tag| {
+            let result = update(&tag);
+            (tag, result)
+        })
+        .collect()
+}
+
+/// Receive the events of a toast until it is resolved or `deadline` passed, see
+/// [`ToastManager::show_collecting`]. Activations with the arguments in `kept` don't resolve it.
+fn collect_events(
+    receiver: &mpsc::Receiver<ToastEvent>,
+    deadline: Instant,
+    kept: &[&str],
+) -> Vec<ToastEvent> {
+    let mut events = Vec::new();
+    while let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+        let Ok(event) = receiver.recv_timeout(remaining) else {
+            break;
+        };
+        let resolved = match &event {
+            ToastEvent::Activated(Some(action)) => !kept.contains(&action.arg.as_str()),
+            _ => true,
+        };
+        events.push(event);
+        if resolved {
+            break;
         }
     }
+    events
+}
+
+/// Call `remove` with the tag and group of a toast, unless it has no tag to be found by.
+fn remove_tagged(
+    tag: Option<String>,
+    group: Option<String>,
+    remove: impl FnOnce(&str, Option<&str>) -> Result<()>,
+) -> Result<()> {
+    match tag {
+        Some(tag) => remove(&tag, group.as_deref()),
+        None => Ok(()),
+    }
+}
+
+/// Fail with the first issue that is not a warning, ignoring empty toasts if `allow_empty`.
+fn check_issues(
+    issues: impl IntoIterator<Item = ValidationIssue>,
+    allow_empty: bool,
+) -> Result<()> {
+    let allowed = |issue: &ValidationIssue| allow_empty && *issue == ValidationIssue::EmptyToast;
+    match issues
+        .into_iter()
+        .find(|issue| !issue.is_warning() && !allowed(issue))
+    {
+        Some(issue) => Err(WinToastError::Validation(issue)),
+        None => Ok(()),
+    }
+}
+
+/// The snooze interval of a selection id that is a positive whole number of minutes.
+fn snooze_interval(value: &str) -> Result<Duration> {
+    value
+        .trim()
+        .parse::<u32>()
+        .ok()
+        .filter(|minutes| *minutes > 0)
+        .map(|minutes| Duration::from_secs(minutes as u64 * 60))
+        .ok_or_else(|| WinToastError::InvalidSnoozeInterval(value.to_string()))
+}
+
+/// The string, or `None` if it is empty or could not be read.
+fn non_empty(s: windows::core::Result<HSTRING>) -> Option<String> {
+    s.ok().filter(|s| !s.is_empty()).map(|s| s.to_string())
+}
+
+/// The expiration time of a notification, if it has one.
+fn expiration_time(notification: &ToastNotification) -> Option<SystemTime> {
+    expires_at(notification.ExpirationTime().ok()?.Value().ok())
+}
+
+/// The expiration time read from a notification, `None` if it has none or it is out of range.
+fn expires_at(expiration_time: Option<DateTime>) -> Option<SystemTime> {
+    time::from_winrt_datetime(expiration_time?).ok()
+}
+
+/// Convert a boxed input value to a string.
+///
+/// Strings are taken as they are, booleans become `"true"` or `"false"`, numbers are formatted
+/// with [`ToString`], and a UTF-16 character becomes a one-character string.
+/// Returns `None` for other types, and for values that can't be read.
+fn property_to_string(value: &IInspectable) -> Option<String> {
+    let value = value.cast::<IPropertyValue>().ok()?;
+    let string = match value.Type().ok()? {
+        PropertyType::String => value.GetString().ok()?.to_string(),
+        PropertyType::Boolean => value.GetBoolean().ok()?.to_string(),
+        PropertyType::UInt8 => value.GetUInt8().ok()?.to_string(),
+        PropertyType::Int16 => value.GetInt16().ok()?.to_string(),
+        PropertyType::UInt16 => value.GetUInt16().ok()?.to_string(),
+        PropertyType::Int32 => value.GetInt32().ok()?.to_string(),
+        PropertyType::UInt32 => value.GetUInt32().ok()?.to_string(),
+        PropertyType::Int64 => value.GetInt64().ok()?.to_string(),
+        PropertyType::UInt64 => value.GetUInt64().ok()?.to_string(),
+        PropertyType::Single => value.GetSingle().ok()?.to_string(),
+        PropertyType::Double => value.GetDouble().ok()?.to_string(),
+        PropertyType::Char16 => String::from_utf16_lossy(&[value.GetChar16().ok()?]),
+        _ => return None,
+    };
+    Some(string)
 }
 
 /// An interface that provides access to the toast notification manager.
@@ -66,8 +700,49 @@ impl DismissalReason {
 pub struct ToastManager {
     app_id: HSTRING,
     on_activated: Option<TypedEventHandler<ToastNotification, IInspectable>>,
+    activation_callback: Option<(String, ActivationCallback)>,
     on_dismissed: Option<TypedEventHandler<ToastNotification, ToastDismissedEventArgs>>,
+    dismissal_callbacks: DismissalCallbacks,
     on_failed: Option<TypedEventHandler<ToastNotification, ToastFailedEventArgs>>,
+    on_undeliverable: Option<UndeliverableHandler>,
+    quiet_hours: Option<QuietHours>,
+    default_group: Option<String>,
+    deferral: Option<Arc<LockDeferral>>,
+    auto_anchor_inputs: bool,
+    quiet_during_focus_sessions: bool,
+    allow_empty_toasts: bool,
+    feedback_sounds: bool,
+    auto_remove_on_activation: bool,
+    rendered_xml: Option<Arc<RenderedXml>>,
+    groups: Option<Arc<GroupTracker>>,
+    input_limits: InputLimits,
+    cache: CacheConfig,
+    counters: Arc<Counters>,
+    notifier: Arc<OnceLock<ToastNotifier>>,
+    detached: Arc<OnceLock<mpsc::Sender<detached::Job>>>,
+    timers: Arc<Timers>,
+}
+
+// SAFETY: `on_activated`, `on_dismissed` and `on_failed` are the only fields that aren't
+// `Send` and `Sync` themselves. They are delegates created by `TypedEventHandler::new`, which
+// implements them in Rust with an atomic reference count and marshals them as agile, so they
+// may be cloned and released from any thread. Their closures are `Send`, only read what they
+// capture, and capture only `Send + Sync` data, so Windows may call them from any thread,
+// even at the same time. `deferral` isn't `Send` only because its queued toasts hold a
+// `ToastManager`. `notifier` holds an agile `ToastNotifier`, which is `Send` and `Sync`
+// itself, and the other fields hold plain Rust data behind `Arc`, `Mutex` or channels.
+unsafe impl Send for ToastManager {}
+unsafe impl Sync for ToastManager {}
+
+impl Drop for ToastManager {
+    /// Dropping a manager is the same as [`ShutdownPolicy::Detach`], except that the timers
+    /// of [`Audio::with_loop_count`](crate::Audio::with_loop_count) keep running.
+    fn drop(&mut self) {
+        // Only the clones of a manager share its notifier.
+        if Arc::strong_count(&self.notifier) == 1 {
+            log::debug!("{self:?} dropped, leaving its pending work to Windows and its timers");
+        }
+    }
 }
 
 impl std::fmt::Debug for ToastManager {
@@ -82,20 +757,173 @@ impl ToastManager {
         "{1AC14E77-02E7-4E5D-B744-2EB1AE5198B7}\\WindowsPowerShell\\v1.0\\powershell.exe";
 
     /// Create a new manager with
+    ///
+    /// The AUM_ID is not checked, use [`ToastManager::try_new`] for AUM_IDs from
+    /// configuration or user input.
     pub fn new(aum_id: impl AsRef<str>) -> Self {
         Self {
             app_id: hs(aum_id.as_ref()),
             on_activated: None,
+            activation_callback: None,
             on_dismissed: None,
+            dismissal_callbacks: DismissalCallbacks::default(),
             on_failed: None,
+            on_undeliverable: None,
+            quiet_hours: None,
+            default_group: None,
+            deferral: None,
+            auto_anchor_inputs: false,
+            quiet_during_focus_sessions: false,
+            allow_empty_toasts: false,
+            feedback_sounds: true,
+            auto_remove_on_activation: false,
+            rendered_xml: None,
+            groups: None,
+            input_limits: InputLimits::default(),
+            cache: CacheConfig::temp(),
+            counters: Arc::default(),
+            notifier: Arc::default(),
+            detached: Arc::default(),
+            timers: Arc::default(),
+        }
+    }
+
+    /// Create a new manager, checking the AUM_ID first.
+    ///
+    /// Windows creates a notifier for almost any AUM_ID, but silently drops the toasts of one
+    /// that doesn't match a registration. This returns [`WinToastError::InvalidAumid`] for
+    /// AUM_IDs that are empty, have surrounding whitespace, are longer than
+    /// [`limits::MAX_AUM_ID_LEN`](crate::limits::MAX_AUM_ID_LEN) characters, or contain
+    /// control characters, such as NUL, or `"`.
+    pub fn try_new(aum_id: impl AsRef<str>) -> Result<Self> {
+        aumid::validate_aum_id(aum_id.as_ref())?;
+        Ok(Self::new(aum_id))
+    }
+
+    /// The notifier for this AUM_ID, created on first use and shared with the clones of this manager.
+    fn notifier(&self) -> Result<ToastNotifier> {
+        self.winrt_notifier()
+            .map_err(|e| self.unregistered(e, WinToastError::NotifierUnavailable))
+    }
+
+    fn winrt_notifier(&self) -> windows::core::Result<ToastNotifier> {
+        if let Some(notifier) = self.notifier.get() {
+            return Ok(notifier.clone());
+        }
+        let start = Instant::now();
+        let notifier = ToastNotificationManager::CreateToastNotifierWithId(&self.app_id)?;
+        self.counters.record_notifier(start.elapsed());
+        Ok(self.notifier.get_or_init(|| notifier).clone())
+    }
+
+    /// Map the error Windows reports for an AUM_ID that is not registered,
+    /// `HRESULT_FROM_WIN32(ERROR_NOT_FOUND)`, to [`WinToastError::UnregisteredAppId`],
+    /// and other errors with `other`.
+    fn unregistered(
+        &self,
+        error: windows::core::Error,
+        other: impl FnOnce(windows::core::Error) -> WinToastError,
+    ) -> WinToastError {
+        if error.code() == ERROR_NOT_FOUND.to_hresult() {
+            WinToastError::UnregisteredAppId(self.app_id.to_string())
+        } else {
+            other(error)
+        }
+    }
+
+    /// Map an error from showing or scheduling the toast with the XML `doc` to
+    /// [`WinToastError::ShowFailed`], with diagnostics gathered now.
+    fn show_failed(
+        &self,
+        notifier: &ToastNotifier,
+        doc: &XmlDocument,
+        error: windows::core::Error,
+    ) -> WinToastError {
+        self.unregistered(error, |source| {
+            let diagnostics = ShowDiagnostics {
+                aum_id: self.app_id.to_string(),
+                registered: self.registered(),
+                setting: notifier
+                    .Setting()
+                    .ok()
+                    .map(crate::NotificationSetting::from_winrt),
+                xml_size: doc.GetXml().map_or(0, |xml| xml.to_string().len()),
+            };
+            WinToastError::ShowFailed {
+                source,
+                diagnostics: Box::new(diagnostics),
+            }
+        })
+    }
+
+    /// Whether the AUM_ID is registered, see [`ShowDiagnostics::registered`].
+    fn registered(&self) -> Option<bool> {
+        #[cfg(feature = "registry")]
+        return crate::register::is_registered(&self.app_id.to_string());
+        #[cfg(not(feature = "registry"))]
+        return None;
+    }
+
+    /// Show `toast` and report everything that helps to find out why it is not seen.
+    ///
+    /// This probes whether the AUM_ID is registered and whether notifications are allowed,
+    /// validates the toast, and shows it like [`ToastManager::show_reported`], keeping the XML
+    /// of the notification that was handed to Windows. Failures are part of the report instead
+    /// of being returned, so this always returns a report, see [`DiagnosticReport`].
+    pub fn diagnose(&self, toast: &Toast) -> DiagnosticReport {
+        let setting = self
+            .notifier()
+            .ok()
+            .and_then(|notifier| notifier.Setting().ok())
+            .map(crate::NotificationSetting::from_winrt);
+        let focus_session = crate::focus_session_state();
+        let issues = toast.validation_issues();
+        let mut xml = None;
+        let result = self.show_checked_with(toast, |notification| {
+            xml = Some(notification.Content()?.GetXml()?.to_string());
+            Ok(())
+        });
+
+        DiagnosticReport {
+            aum_id: self.app_id.to_string(),
+            registered: self.registered(),
+            setting,
+            focus_session,
+            xml,
+            issues,
+            result: result.map(|(report, _)| report),
         }
     }
 
+    /// Check whether the notification platform is usable for this AUM_ID.
+    ///
+    /// This probes that the `ToastNotificationManager` WinRT component can be activated,
+    /// that a notifier can be created for the AUM_ID, and that the notification history is reachable.
+    /// It does not check whether the user or group policy has turned notifications off,
+    /// and it does not show anything.
+    pub fn check_platform(&self) -> Result<()> {
+        ToastNotificationManager::CreateToastNotifierWithId(&self.app_id)
+            .map_err(|e| self.unregistered(e, WinToastError::NotifierUnavailable))?;
+        ToastNotificationManager::History()?;
+
+        Ok(())
+    }
+
+    /// Whether the notification platform is usable for this AUM_ID.
+    ///
+    /// See [`ToastManager::check_platform`] for what is probed.
+    pub fn platform_available(&self) -> bool {
+        self.check_platform().is_ok()
+    }
+
     /// Remove all notifications in `group`.
     pub fn remove_group(&self, group: &str) -> Result<()> {
         let history = ToastNotificationManager::History()?;
 
         history.RemoveGroupWithId(&hs(group), &self.app_id)?;
+        if let Some(groups) = &self.groups {
+            groups.remove_group(group);
+        }
 
         Ok(())
     }
@@ -105,6 +933,9 @@ impl ToastManager {
         let history = ToastNotificationManager::History()?;
 
         history.RemoveGroupedTagWithId(&hs(tag), &hs(group), &self.app_id)?;
+        if let Some(groups) = &self.groups {
+            groups.remove_tag(group, tag);
+        }
 
         Ok(())
     }
@@ -118,243 +949,1885 @@ impl ToastManager {
         Ok(())
     }
 
+    /// The toast notifications from this application that are still in the Action Center.
+    pub fn history(&self) -> Result<Vec<HistoryEntry>> {
+        let history = ToastNotificationManager::History()?;
+        let notifications = history.GetHistoryWithId(&self.app_id)?;
+
+        Ok(notifications
+            .into_iter()
+            .map(|notification| HistoryEntry::from_winrt(&notification))
+            .collect())
+    }
+
+    /// The toast with `tag`, and `group` if given, if it is still in the Action Center.
+    ///
+    /// Toasts whose tag is rejected by [`Tag::new`] are never found.
+    pub fn history_entry(&self, tag: &str, group: Option<&str>) -> Result<Option<HistoryEntry>> {
+        Ok(self.history()?.into_iter().find(|entry| {
+            entry.tag.as_deref() == Some(tag)
+                && (group.is_none() || entry.group.as_deref() == group)
+        }))
+    }
+
+    /// The toasts in `group` that are still in the Action Center.
+    pub fn history_in_group(&self, group: &str) -> Result<Vec<HistoryEntry>> {
+        let mut entries = self.history()?;
+        entries.retain(|entry| entry.group.as_deref() == Some(group));
+        Ok(entries)
+    }
+
     /// Clear all toast notifications from this application.
     pub fn clear(&self) -> Result<()> {
         let history = ToastNotificationManager::History()?;
 
         history.ClearWithId(&self.app_id)?;
+        if let Some(groups) = &self.groups {
+            groups.remove_all();
+        }
 
         Ok(())
     }
 
     /// Register a callback for when a toast notification is activated.
-    pub fn on_activated<F>(mut self, input_id: Option<&str>, mut f: F) -> Self
+    ///
+    /// Like all callbacks, it is called on a thread owned by Windows. A panic in it is caught
+    /// before it can unwind into Windows, and reported by the panic hook.
+    ///
+    /// This is the only time the values of the inputs of a toast are available: Windows has
+    /// no event for input changes before activation, see [`Input`](crate::Input).
+    pub fn on_activated<F>(mut self, input_id: Option<&str>, f: F) -> Self
     where
         F: FnMut(Option<ActivatedAction>) + Send + 'static,
     {
         let id = input_id.map_or("".to_string(), |s| s.to_string());
+        self.activation_callback = Some((id, Arc::new(Mutex::new(Box::new(f)))));
+        self.dispatch_activations()
+    }
+
+    /// Replace the `Activated` handler with one for the current callback and settings.
+    fn dispatch_activations(mut self) -> Self {
+        let Some((id, f)) = self.activation_callback.clone() else {
+            return self;
+        };
+        let (limits, counters) = (self.input_limits, self.counters.clone());
+        let (app_id, auto_remove) = (self.app_id.clone(), self.auto_remove_on_activation);
         self.on_activated = Some(TypedEventHandler::new(
-            move |_, args: &Option<IInspectable>| {
-                f(Self::get_activated_action(args, id.clone()));
+            move |sender: &Option<ToastNotification>, args: &Option<IInspectable>| {
+                Self::play_feedback_sound(sender, args);
+                let action = Self::get_activated_action(sender, args, &id, &limits, &counters);
+                catch_panic(|| {
+                    let mut f = f.lock().unwrap_or_else(|e| e.into_inner());
+                    f(action)
+                });
+                if let (true, Some(notification)) = (auto_remove, sender) {
+                    if !Self::keeps_toast(notification, args) {
+                        let _ = Self::remove_notification(&app_id, notification);
+                    }
+                }
                 Ok(())
             },
         ));
         self
     }
 
+    /// Whether the activated action of `notification` keeps it on screen,
+    /// see [`Action::with_after_activation`](crate::Action::with_after_activation).
+    pub(crate) fn keeps_toast(
+        notification: &ToastNotification,
+        args: &Option<IInspectable>,
+    ) -> bool {
+        let Some(arguments) = args
+            .as_ref()
+            .and_then(|args| args.cast::<ToastActivatedEventArgs>().ok())
+            .and_then(|args| args.Arguments().ok())
+        else {
+            return false;
+        };
+        let Ok(actions) = notification.Content().and_then(|doc| {
+            doc.SelectNodes(&hs(
+                "//action[@activationType='background'][@afterActivationBehavior='pendingUpdate']",
+            ))
+        }) else {
+            return false;
+        };
+        actions.into_iter().any(|action| {
+            action
+                .cast::<XmlElement>()
+                .and_then(|action| action.GetAttribute(&hs("arguments")))
+                .is_ok_and(|action_arguments| action_arguments == arguments)
+        })
+    }
+
+    /// Play the feedback sound of the activated action, if it has one.
+    ///
+    /// See [`Action::with_feedback_sound`](crate::Action::with_feedback_sound).
+    fn play_feedback_sound(sender: &Option<ToastNotification>, args: &Option<IInspectable>) {
+        let arguments = args
+            .as_ref()
+            .and_then(|args| args.cast::<ToastActivatedEventArgs>().ok())
+            .and_then(|args| args.Arguments().ok());
+        let alias = sender
+            .as_ref()
+            .zip(arguments)
+            .and_then(|(notification, arguments)| {
+                let values = notification.Data().ok()?.Values().ok()?;
+                feedback::lookup(&arguments.to_string(), |key| {
+                    values.Lookup(&hs(key)).ok().map(|alias| alias.to_string())
+                })
+            });
+        if let Some(alias) = alias {
+            feedback::play(alias);
+        }
+    }
+
+    /// Remove `notification` from the Action Center by its tag and group.
+    ///
+    /// Does nothing for notifications without a tag, since they can't be addressed.
+    fn remove_notification(app_id: &HSTRING, notification: &ToastNotification) -> Result<()> {
+        let (tag, group) = (
+            non_empty(notification.Tag()),
+            non_empty(notification.Group()),
+        );
+        remove_tagged(tag, group, |tag, group| {
+            let history = ToastNotificationManager::History()?;
+            match group {
+                Some(group) => history.RemoveGroupedTagWithId(&hs(tag), &hs(group), app_id)?,
+                None => history.Remove(&hs(tag))?,
+            }
+            Ok(())
+        })
+    }
+
     fn get_activated_action(
+        sender: &Option<ToastNotification>,
         inspect: &Option<IInspectable>,
-        input_id: String,
+        input_id: &str,
+        limits: &InputLimits,
+        counters: &Counters,
     ) -> Option<ActivatedAction> {
         let args = inspect
             .as_ref()
             .and_then(|arg| arg.cast::<ToastActivatedEventArgs>().ok());
 
-        let button_arg = args
+        let arguments = args
             .clone()
             .and_then(|args| args.Arguments().ok())
             .map(|s| s.to_string())
-            .filter(|s| !s.is_empty());
+            .unwrap_or_default();
+        let (arguments, correlation_id) = correlation::split(&arguments);
+        let button_arg =
+            Some(arguments.to_string()).filter(|s| !s.is_empty() || correlation_id.is_some());
 
-        let user_input = args
+        let values = args
             .and_then(|args| args.UserInput().ok())
-            .and_then(|value_set| value_set.Lookup(&hs(input_id)).ok())
-            .and_then(|args| args.cast::<IReference<HSTRING>>().ok())
-            .and_then(|args| args.Value().ok())
-            .map(|s| s.to_string())
-            .filter(|s| !s.is_empty());
+            .map(|value_set| Self::collect_user_input(&value_set, limits, counters))
+            .unwrap_or_default();
+
+        let full_title = sender
+            .as_ref()
+            .and_then(|notification| notification.Data().ok())
+            .and_then(|data| data.Values().ok())
+            .and_then(|values| values.Lookup(&hs(FULL_TITLE_KEY)).ok())
+            .map(|title| title.to_string());
 
         Some(ActivatedAction {
             arg: button_arg?,
-            value: user_input,
+            value: values.get(input_id).cloned(),
+            values,
+            full_title,
+            correlation_id,
         })
     }
 
+    /// Collect the values of all inputs, keeping empty ones.
+    ///
+    /// Values that are not strings are converted with [`property_to_string`].
+    fn collect_user_input(
+        value_set: &ValueSet,
+        limits: &InputLimits,
+        counters: &Counters,
+    ) -> HashMap<String, String> {
+        let Ok(iter) = value_set.First() else {
+            return HashMap::new();
+        };
+        let read = |pair: IKeyValuePair<HSTRING, IInspectable>| {
+            let value = pair.Value().ok().as_ref().and_then(property_to_string)?;
+            Some((pair.Key().ok()?.to_string(), value))
+        };
+        limit_input_values(iter, read, limits, counters)
+    }
+
     /// Register a callback for when a toast notification is dismissed.
+    ///
+    /// See [`ToastManager::on_user_dismissed`], [`ToastManager::on_timed_out`] and
+    /// [`ToastManager::on_app_hidden`] for callbacks of a single reason. When both are
+    /// registered, this callback is called first. Registering it again replaces it.
     pub fn on_dismissed<F>(mut self, f: F) -> Self
     where
-        F: Fn(Result<DismissalReason>) + Send + 'static,
+        F: Fn(Result<ToastDismissed>) + Send + 'static,
+    {
+        self.dismissal_callbacks.set_any(f);
+        self.dispatch_dismissals()
+    }
+
+    /// Register a callback for when the user dismisses a toast notification,
+    /// [`DismissalReason::UserCanceled`].
+    ///
+    /// The callbacks of a single reason are called after the callback of
+    /// [`ToastManager::on_dismissed`], in the order they were registered, and only for
+    /// dismissals that Windows reports with a reason. Registering more of them adds to the
+    /// ones registered before.
+    pub fn on_user_dismissed<F>(self, f: F) -> Self
+    where
+        F: Fn(ToastDismissed) + Send + 'static,
+    {
+        self.on_dismissed_because(DismissalReason::UserCanceled, f)
+    }
+
+    /// Register a callback for when a toast notification times out into the Action Center,
+    /// [`DismissalReason::TimedOut`].
+    ///
+    /// See [`ToastManager::on_user_dismissed`] for the order of the callbacks.
+    pub fn on_timed_out<F>(self, f: F) -> Self
+    where
+        F: Fn(ToastDismissed) + Send + 'static,
+    {
+        self.on_dismissed_because(DismissalReason::TimedOut, f)
+    }
+
+    /// Register a callback for when the app hides a toast notification, e.g. with
+    /// [`ToastHandle::hide`], [`DismissalReason::ApplicationHidden`].
+    ///
+    /// See [`ToastManager::on_user_dismissed`] for the order of the callbacks.
+    pub fn on_app_hidden<F>(self, f: F) -> Self
+    where
+        F: Fn(ToastDismissed) + Send + 'static,
+    {
+        self.on_dismissed_because(DismissalReason::ApplicationHidden, f)
+    }
+
+    fn on_dismissed_because<F>(mut self, reason: DismissalReason, f: F) -> Self
+    where
+        F: Fn(ToastDismissed) + Send + 'static,
     {
+        self.dismissal_callbacks.add(reason, f);
+        self.dispatch_dismissals()
+    }
+
+    /// Replace the `Dismissed` handler with one that dispatches to the current callbacks.
+    fn dispatch_dismissals(mut self) -> Self {
+        let callbacks = self.dismissal_callbacks.clone();
         self.on_dismissed = Some(TypedEventHandler::new(
-            move |_, args: &Option<ToastDismissedEventArgs>| {
-                f(Self::get_dismissal_reason(args));
+            move |sender: &Option<ToastNotification>, args: &Option<ToastDismissedEventArgs>| {
+                callbacks.dispatch(Self::get_dismissed(sender, args));
                 Ok(())
             },
         ));
         self
     }
 
-    fn get_dismissal_reason(args: &Option<ToastDismissedEventArgs>) -> Result<DismissalReason> {
-        let args = args.as_ref().and_then(|arg| arg.Reason().ok());
-        match args {
+    fn get_dismissed(
+        sender: &Option<ToastNotification>,
+        args: &Option<ToastDismissedEventArgs>,
+    ) -> Result<ToastDismissed> {
+        let reason = match args.as_ref().and_then(|arg| arg.Reason().ok()) {
             Some(reason) => DismissalReason::from_winrt(reason),
-            None => Err(WinToastError::InvalidDismissalReason),
-        }
+            None => return Err(WinToastError::InvalidDismissalReason),
+        };
+        let Some(notification) = sender else {
+            return Ok(ToastDismissed::new(reason));
+        };
+
+        Ok(ToastDismissed {
+            reason,
+            tag: Tag::from_os(notification.Tag()),
+            group: non_empty(notification.Group()),
+            expires_at: expiration_time(notification),
+            correlation_id: correlation::from_notification(notification),
+        })
+    }
+
+    /// Register a callback for when the last live toast of a group is gone.
+    ///
+    /// The toasts with a group shown with [`ToastManager::show`] and its variants are
+    /// tracked from then on. A toast stops being live when
+    /// * it is activated, unless the action keeps it on screen with
+    ///   [`AfterActivation::PendingUpdate`](crate::content::action::AfterActivation::PendingUpdate),
+    /// * the user dismisses it, or the app hides it; a toast that times out into the
+    ///   Action Center stays live,
+    /// * it fails to show,
+    /// * it is removed with [`ToastManager::remove_group`], [`ToastManager::remove_grouped_tag`]
+    ///   or [`ToastManager::clear`] of this manager or a clone.
+    ///
+    /// `f` is called with the group once per time the group becomes empty, so a
+    /// `remove_group` calls it once no matter how many toasts it removed, and not at all
+    /// for a group without live toasts. Windows does not report toasts that expire or are
+    /// cleared from the Action Center by the user, so those stay live.
+    ///
+    /// Tracking is shared with the clones made after registering the callback.
+    pub fn on_group_empty<F>(mut self, f: F) -> Self
+    where
+        F: Fn(String) + Send + Sync + 'static,
+    {
+        self.groups = Some(Arc::new(GroupTracker::new(Box::new(f))));
+        self
     }
 
     /// Register a callback for when a toast notification fails to display.
+    ///
+    /// The callback gets the error, and the tag, group and correlation id of the toast.
     pub fn on_failed<F>(mut self, f: F) -> Self
     where
-        F: Fn(WinToastError) + Send + 'static,
+        F: Fn(ToastFailed) + Send + 'static,
     {
         self.on_failed = Some(TypedEventHandler::new(
-            move |_, args: &Option<ToastFailedEventArgs>| {
-                f(Self::get_failed_error(args));
+            move |sender: &Option<ToastNotification>, args: &Option<ToastFailedEventArgs>| {
+                let failed = Self::get_failed(sender, args);
+                catch_panic(|| f(failed));
                 Ok(())
             },
         ));
         self
     }
 
-    fn get_failed_error(args: &Option<ToastFailedEventArgs>) -> WinToastError {
-        let err = args.as_ref().and_then(|e| e.ErrorCode().ok());
-        if let Some(e) = err {
-            if e.is_err() {
-                return WinToastError::Os(e.into());
-            }
+    fn get_failed(
+        sender: &Option<ToastNotification>,
+        args: &Option<ToastFailedEventArgs>,
+    ) -> ToastFailed {
+        let failed = ToastFailed::new(Self::get_failed_error(args));
+        let Some(notification) = sender else {
+            return failed;
+        };
+
+        ToastFailed {
+            tag: Tag::from_os(notification.Tag()),
+            group: non_empty(notification.Group()),
+            correlation_id: correlation::from_notification(notification),
+            ..failed
+        }
+    }
+
+    /// Register a callback for toasts that this manager determines won't be shown,
+    /// e.g. to show the message in the app instead.
+    ///
+    /// `f` is called with the toast and an [`UndeliverableReason`] when
+    /// * notifications are disabled for this app. The setting is only read when this
+    ///   callback is registered, and the toast is then not handed to Windows,
+    /// * [`ToastManager::show`], [`ToastManager::show_reported`] or
+    ///   [`ToastManager::show_collecting`] reject the toast in validation,
+    /// * showing the toast fails, either right away or later in Windows,
+    /// * a toast held back by [`ToastManager::defer_while_locked`] expires or is cancelled.
+    ///
+    /// Errors are still returned from the call that failed. Notifications being disabled is
+    /// not an error: the call succeeds without showing the toast, and reports it in
+    /// [`ShowReport::undelivered`] and [`ToastHandle::is_shown`]. Toasts shown with
+    /// [`ToastManager::show_template`] and [`ToastManager::show_xml`] are not reported.
+    pub fn on_undeliverable<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&Toast, UndeliverableReason) + Send + Sync + 'static,
+    {
+        self.on_undeliverable = Some(Arc::new(f));
+        self
+    }
+
+    /// Report that `toast` won't be shown to the callback of [`ToastManager::on_undeliverable`].
+    ///
+    /// Every path that drops a toast goes through here.
+    pub(crate) fn undeliverable(&self, toast: &Toast, reason: UndeliverableReason) {
+        if let Some(f) = &self.on_undeliverable {
+            catch_panic(|| f(toast, reason));
+        }
+    }
+
+    /// Validate a toast that is about to be shown, reporting it as undeliverable if it fails.
+    fn check_before_show(
+        &self,
+        toast: &Toast,
+        issues: impl IntoIterator<Item = ValidationIssue>,
+    ) -> Result<()> {
+        self.check_issues(issues).inspect_err(|error| {
+            if let WinToastError::Validation(issue) = error {
+                self.undeliverable(toast, UndeliverableReason::Invalid(issue.clone()));
+            }
+        })
+    }
+
+    fn get_failed_error(args: &Option<ToastFailedEventArgs>) -> WinToastError {
+        let err = args.as_ref().and_then(|e| e.ErrorCode().ok());
+        if let Some(e) = err {
+            if e.is_err() {
+                return WinToastError::Os(e.into());
+            }
         }
         WinToastError::Unknown
     }
 
-    /// Send a toast to Windows for display.
-    pub fn show(&self, toast: &Toast) -> Result<()> {
-        let notifier = ToastNotificationManager::CreateToastNotifierWithId(&self.app_id)?;
+    /// Show toasts silently during the given window of local time.
+    ///
+    /// While the window is active, any audio on a toast is forced to `silent="true"`
+    /// and looping sounds are downgraded, so the toast is still shown but never plays sound.
+    /// Toasts without audio get a silent audio element instead of the system default sound.
+    pub fn quiet_hours(mut self, range: QuietHours) -> Self {
+        self.quiet_hours = Some(range);
+        self
+    }
+
+    /// Hold back toasts while the workstation is locked, and show them once it is unlocked.
+    ///
+    /// While the session is locked, [`ToastManager::show`] and [`ToastManager::show_template`]
+    /// queue the toast and return `Ok(())`. A background listener is notified by Windows when
+    /// the session is unlocked, or checks the lock state every second if it can't be notified,
+    /// and shows the queued toasts in order after unlock; toasts whose [`Toast::expires_in`] passed while queued are dropped,
+    /// and the others keep only their remaining expiration time.
+    /// Errors from showing queued toasts are discarded.
+    ///
+    /// The listener stops once this manager and all of its clones are dropped;
+    /// toasts still queued at that point are never shown.
+    pub fn defer_while_locked(mut self, enabled: bool) -> Self {
+        self.deferral = enabled.then(LockDeferral::start);
+        self
+    }
 
-        let toast_doc = XmlDocument::new()?;
+    /// Keep the files this manager writes, such as the images of
+    /// [`ToastManager::image_from_bytes`], as configured by `cache`.
+    ///
+    /// The default is [`CacheConfig::temp`].
+    pub fn cache(mut self, cache: CacheConfig) -> Self {
+        self.cache = cache;
+        self
+    }
 
-        let toast_el = toast_doc.CreateElement(&hs("toast"))?;
-        toast_doc.AppendChild(&toast_el)?;
+    /// The cache of this manager, see [`ToastManager::cache`], e.g. to reconcile it at startup.
+    pub fn cache_config(&self) -> &CacheConfig {
+        &self.cache
+    }
 
-        if let Some(scenario) = &toast.scenario {
-            toast_el.SetAttribute(&hs("scenario"), &hs(scenario.as_str()))?;
+    /// Create an [`Image`] from the contents of an image file, written to the
+    /// cache of this manager, see [`Image::from_bytes`].
+    pub fn image_from_bytes(&self, bytes: &[u8], extension: &str) -> Result<Image> {
+        Image::from_bytes(bytes, extension, &self.cache)
+    }
+
+    /// Don't play the sounds set with [`Action::with_feedback_sound`](crate::Action::with_feedback_sound)
+    /// for the toasts shown by this manager.
+    pub fn disable_feedback_sounds(mut self) -> Self {
+        self.feedback_sounds = false;
+        self
+    }
+
+    /// Shut this manager down, deciding what happens to its pending work with `policy`.
+    ///
+    /// The queue of [`ToastManager::defer_while_locked`] is shared with the clones of this
+    /// manager, so [`ShutdownPolicy::Flush`] and [`ShutdownPolicy::CancelAll`] also empty it
+    /// for them. Toasts queued by a clone while this runs are left to the clone.
+    /// Both also wait for the toasts passed to [`ToastManager::show_detached`] before.
+    /// Errors from showing flushed toasts are discarded.
+    ///
+    /// Every policy stops the timers of [`Audio::with_loop_count`](crate::Audio::with_loop_count)
+    /// for this manager and its clones, so toasts that are still ringing are not shown again
+    /// silently; they keep their sound until they are dismissed.
+    ///
+    /// Callbacks are registered with each shown toast, so those of toasts already shown
+    /// keep firing after the shutdown.
+    pub fn shutdown(self, policy: ShutdownPolicy) -> Result<()> {
+        self.timers.cancel();
+        if policy != ShutdownPolicy::Detach {
+            self.wait_for_detached();
         }
+        let Some(deferral) = &self.deferral else {
+            return Ok(());
+        };
 
-        if let Some(launch) = &toast.launch {
-            toast_el.SetAttribute(&hs("launch"), &hs(launch))?;
+        match policy {
+            ShutdownPolicy::Flush => {
+                for (manager, queued) in deferral.take(Instant::now()) {
+                    let _ = queued.show(&manager.without_deferral());
+                }
+                Ok(())
+            }
+            ShutdownPolicy::CancelAll => {
+                for (manager, queued) in deferral.take(Instant::now()) {
+                    manager.undeliverable(queued.toast(), UndeliverableReason::Cancelled);
+                }
+                Ok(())
+            }
+            ShutdownPolicy::Detach => Ok(()),
         }
+    }
+
+    /// Put the toasts that don't set a group with [`Toast::group`] into `group`.
+    ///
+    /// A group set on the toast always takes precedence. With all toasts in one group,
+    /// [`ToastManager::remove_group`] removes everything this app has shown.
+    /// This applies to [`ToastManager::show`] and its variants, and to [`ToastManager::show_template`].
+    pub fn default_group(mut self, group: impl Into<String>) -> Self {
+        self.default_group = Some(group.into());
+        self
+    }
 
-        if let Some(duration) = &toast.duration {
-            toast_el.SetAttribute(&hs("duration"), &hs(duration.as_str()))?;
+    /// Deliver toasts to the Action Center without popup and sound while a focus session
+    /// is running, see [`focus_session_state`](crate::focus_session_state).
+    ///
+    /// Windows holds back the banners of toasts shown during a focus session, and plays their
+    /// sounds when the session ends. When enabled, toasts shown during a session are
+    /// silenced and get [`Toast::suppress_popup`], so nothing piles up for the end of the
+    /// session. Toasts with a high-priority scenario, see [`Toast::respect_focus_assist`],
+    /// are left as they are, and so are all toasts when the state is unknown.
+    pub fn quiet_during_focus_sessions(mut self, enabled: bool) -> Self {
+        self.quiet_during_focus_sessions = enabled;
+        self
+    }
+
+    /// Fill in the action anchor when a toast has a text input that no action is bound to.
+    ///
+    /// Without an action bound to it with [`Action::with_input_id`](crate::Action::with_input_id),
+    /// pressing Enter in a text input does nothing. When enabled, the first button
+    /// of such a toast is bound to the input before it is shown.
+    /// See also [`ValidationIssue::UnanchoredInput`](crate::ValidationIssue::UnanchoredInput).
+    pub fn auto_anchor_inputs(mut self, enabled: bool) -> Self {
+        self.auto_anchor_inputs = enabled;
+        self
+    }
+
+    /// Show toasts without any text, image, input or action instead of rejecting them.
+    ///
+    /// See [`ValidationIssue::EmptyToast`](crate::ValidationIssue::EmptyToast).
+    /// Disabled by default.
+    pub fn allow_empty_toasts(mut self, enabled: bool) -> Self {
+        self.allow_empty_toasts = enabled;
+        self
+    }
+
+    /// Apply this manager's adjustments to a toast before it is rendered.
+    ///
+    /// The names of the builder methods whose adjustments were applied are added to `applied`.
+    fn prepare<'a>(
+        &self,
+        toast: &'a Toast,
+        applied: &mut Vec<&'static str>,
+    ) -> Result<Cow<'a, Toast>> {
+        let mut toast = self.prepare_content(toast, applied);
+
+        if let Some(quiet_hours) = &self.quiet_hours {
+            if quiet_hours.is_active_now()? {
+                silence(toast.to_mut());
+                applied.push("quiet_hours");
+            }
         }
 
-        if let Some(use_button_style) = &toast.use_button_style {
-            toast_el.SetAttribute(&hs("useButtonStyle"), &hs(use_button_style.as_str()))?;
+        if self.quiet_during_focus_sessions
+            && toast.quiets_for_focus_session(crate::focus_session_state())
+        {
+            let toast = toast.to_mut();
+            silence(toast);
+            toast.suppress_popup = true;
+            applied.push("quiet_during_focus_sessions");
         }
 
-        // <header>
-        if let Some(header) = &toast.header {
-            let el = toast_doc.CreateElement(&hs("header"))?;
-            toast_el.AppendChild(&el)?;
-            header.write_to_element(&el)?;
+        if toast.defers_to_focus_assist() {
+            toast.to_mut().suppress_popup = true;
+            applied.push("respect_focus_assist");
         }
-        // </header>
-        // <visual>
-        {
-            let visual_el = toast_doc.CreateElement(&hs("visual"))?;
-            toast_el.AppendChild(&visual_el)?;
-            // <binding>
-            {
-                let binding_el = toast_doc.CreateElement(&hs("binding"))?;
-                visual_el.AppendChild(&binding_el)?;
-                binding_el.SetAttribute(&hs("template"), &hs("ToastGeneric"))?;
-                {
-                    if let Some(text) = &toast.text.0 {
-                        let el = toast_doc.CreateElement(&hs("text"))?;
-                        binding_el.AppendChild(&el)?;
-                        text.write_to_element(1, &el)?;
-                    }
-                    if let Some(text) = &toast.text.1 {
-                        let el = toast_doc.CreateElement(&hs("text"))?;
-                        binding_el.AppendChild(&el)?;
-                        text.write_to_element(2, &el)?;
-                    }
-                    if let Some(text) = &toast.text.2 {
-                        let el = toast_doc.CreateElement(&hs("text"))?;
-                        binding_el.AppendChild(&el)?;
-                        text.write_to_element(3, &el)?;
-                    }
 
-                    for (id, image) in &toast.images {
-                        let el = toast_doc.CreateElement(&hs("image"))?;
-                        binding_el.AppendChild(&el)?;
-                        image.write_to_element(*id, &el)?;
-                    }
-                }
+        Ok(toast)
+    }
+
+    /// Apply the adjustments of [`ToastManager::prepare`] that don't depend on the moment
+    /// the toast is shown, which are also applied to scheduled toasts.
+    fn prepare_content<'a>(
+        &self,
+        toast: &'a Toast,
+        applied: &mut Vec<&'static str>,
+    ) -> Cow<'a, Toast> {
+        let mut toast = Cow::Borrowed(toast);
+
+        if with_default_group(&mut toast, self.default_group.as_deref()) {
+            applied.push("default_group");
+        }
+
+        if self.auto_anchor_inputs && anchor_input(&mut toast) {
+            applied.push("auto_anchor_inputs");
+        }
+
+        toast
+    }
+
+    /// Show `toast` again after the snooze interval the user picked.
+    ///
+    /// The interval is read from [`ActivatedAction::value`] and must be a positive whole number of minutes,
+    /// otherwise [`WinToastError::InvalidSnoozeInterval`] is returned.
+    /// The expected setup is a selection input whose selection ids are the minutes,
+    /// e.g. `Selection::new("15", "15 minutes")`, a snooze action bound to that input with
+    /// [`Action::with_input_id`](crate::Action::with_input_id), and the input's id passed to
+    /// [`ToastManager::on_activated`].
+    ///
+    /// The toast is validated like [`ToastManager::schedule`] does, and handed to the Windows
+    /// scheduler, so callbacks registered on this manager are not attached to the snoozed toast.
+    pub fn handle_snooze(&self, toast: &Toast, action: &ActivatedAction) -> Result<()> {
+        let interval = snooze_interval(action.value.as_deref().unwrap_or_default())?;
+        self.check_before_show(toast, toast.validation_issues())?;
+
+        let delivery_time = time::from_now(interval)?;
+        self.add_to_schedule(toast, delivery_time).map(|_| ())
+    }
+
+    /// Validate a toast and hand it to Windows to be shown at `delivery_time`, and return
+    /// the id of the scheduled toast.
+    ///
+    /// Windows shows the toast at that time even if the app is not running then, with
+    /// the tag and group of the toast, so it can be updated or removed like any other toast
+    /// once it is shown. Before that, [`ToastManager::unschedule`] cancels it by the returned id.
+    ///
+    /// The default group and [`ToastManager::auto_anchor_inputs`] of this manager are applied,
+    /// but the adjustments that depend on the moment the toast is shown, such as
+    /// [`ToastManager::quiet_hours`], are not, and the callbacks of this manager are not
+    /// attached to the scheduled toast.
+    ///
+    /// [`Toast::expires_in`] counts from `delivery_time`.
+    /// Windows keeps no notification data for scheduled toasts, so the sounds of
+    /// [`Action::with_feedback_sound`](crate::Action::with_feedback_sound) are not played for
+    /// them. The correlation id of [`Toast::correlation_id`] still reaches activations and
+    /// dismissals, through the arguments it is embedded in.
+    ///
+    /// Returns [`WinToastError::DeliveryTimeInPast`] if `delivery_time` is not in the future.
+    pub fn schedule(&self, toast: &Toast, delivery_time: SystemTime) -> Result<String> {
+        if delivery_time <= SystemTime::now() {
+            return Err(WinToastError::DeliveryTimeInPast(delivery_time));
+        }
+        self.check_before_show(toast, toast.validation_issues())?;
+
+        self.add_to_schedule(toast, time::to_winrt_datetime(delivery_time)?)
+    }
+
+    /// Cancel a toast scheduled with [`ToastManager::schedule`] that was not shown yet.
+    ///
+    /// Returns whether a scheduled toast with the `id` was found.
+    pub fn unschedule(&self, id: &str) -> Result<bool> {
+        let notifier = self.notifier()?;
+        for scheduled in notifier.GetScheduledToastNotifications()? {
+            if scheduled.Id()? == *id {
+                notifier.RemoveFromSchedule(&scheduled)?;
+                return Ok(true);
             }
-            // </binding>
-        }
-        // </visual>
-        // <audio>
-        if let Some(audio) = &toast.audio {
-            let audio_el = toast_doc.CreateElement(&hs("audio"))?;
-            toast_el.AppendChild(&audio_el)?;
-            audio.write_to_element(&audio_el)?;
-        }
-        // </audio>
-        // <actions>
-        if toast.input.is_some() || !toast.actions.is_empty() {
-            let actions_el = toast_doc.CreateElement(&hs("actions"))?;
-            toast_el.AppendChild(&actions_el)?;
-            // <input>
-            if let Some(input) = &toast.input {
-                let input_el = toast_doc.CreateElement(&hs("input"))?;
-                actions_el.AppendChild(&input_el)?;
-                input.write_to_element(&input_el)?;
-                // <selection>
-                {
-                    for selection in &toast.selections {
-                        let el = toast_doc.CreateElement(&hs("selection"))?;
-                        input_el.AppendChild(&el)?;
-                        selection.write_to_element(&el)?;
-                    }
-                }
-                // </selection>
+        }
+
+        Ok(false)
+    }
+
+    /// Hand `toast` to the Windows scheduler, and return the id of the scheduled toast.
+    fn add_to_schedule(&self, toast: &Toast, delivery_time: DateTime) -> Result<String> {
+        let notifier = self.notifier()?;
+        let toast = self.prepare_content(toast, &mut Vec::new());
+        let toast_doc = toast
+            .to_document()
+            .map_err(|e| e.in_stage(BuildStage::Document))?;
+
+        let scheduled = ScheduledToastNotification::CreateScheduledToastNotification(
+            &toast_doc,
+            delivery_time,
+        )?;
+        // Windows accepts ids of at most 16 characters, which a generated tag has.
+        let id = tag::generate();
+        scheduled.SetId(&hs(&id))?;
+
+        if let Some(group) = &toast.group {
+            scheduled.SetGroup(&hs(group))?;
+        }
+        if let Some(tag) = &toast.tag {
+            scheduled.SetTag(&hs(tag))?;
+        }
+        if let Some(remote_id) = &toast.remote_id {
+            scheduled.SetRemoteId(&hs(remote_id))?;
+        }
+        if toast.suppress_popup {
+            scheduled.SetSuppressPopup(true)?;
+        }
+        if let Some(exp) = toast.expires_in {
+            let ticks =
+                i64::try_from(exp.as_nanos() / 100).map_err(|_| WinToastError::TimeConversion)?;
+            let dt = DateTime {
+                UniversalTime: delivery_time
+                    .UniversalTime
+                    .checked_add(ticks)
+                    .ok_or(WinToastError::TimeConversion)?,
+            };
+            scheduled.SetExpirationTime(
+                &PropertyValue::CreateDateTime(dt)?.cast::<IReference<DateTime>>()?,
+            )?;
+        }
+
+        notifier
+            .AddToSchedule(&scheduled)
+            .map_err(|e| self.show_failed(&notifier, &toast_doc, e))?;
+
+        Ok(id)
+    }
+
+    /// Limit how many input values are read when a toast is activated, and how long each may be.
+    ///
+    /// Inputs beyond `max_values` are dropped, and values longer than `max_value_len` characters
+    /// are truncated, so a toast with unexpectedly many or large inputs can't make the activation
+    /// handler copy unbounded data. Both are counted in [`ToastManager::metrics`].
+    /// The defaults are 64 values of 16384 characters each.
+    ///
+    /// The limits apply to the `on_activated` callback no matter whether it was registered
+    /// before or after, and to the clones made from this manager afterwards.
+    pub fn input_limits(mut self, max_values: usize, max_value_len: usize) -> Self {
+        self.input_limits = InputLimits {
+            max_values,
+            max_value_len,
+        };
+        self.dispatch_activations()
+    }
+
+    /// Remove a toast from the Action Center right after its `on_activated` callback returned.
+    ///
+    /// Windows usually removes an activated toast itself, but it can linger, e.g. for
+    /// background activations. Only toasts with a tag are removed, since a toast is
+    /// addressed by its tag and group; the removal happens even if the callback panicked.
+    /// Toasts activated by a background button with
+    /// [`AfterActivation::PendingUpdate`](crate::content::action::AfterActivation::PendingUpdate) are kept,
+    /// since that button keeps its toast on screen.
+    ///
+    /// This applies to the `on_activated` callback no matter whether it was registered before
+    /// or after, and to the toasts shown by this manager and the clones made from it afterwards.
+    pub fn auto_remove_on_activation(mut self, enabled: bool) -> Self {
+        self.auto_remove_on_activation = enabled;
+        self.dispatch_activations()
+    }
+
+    /// Keep the XML of the toasts shown with [`ToastManager::show`] and its variants,
+    /// for [`ToastManager::rendered_xml`].
+    ///
+    /// Only toasts with a tag are kept, until they fail to show, are activated, or are
+    /// dismissed by the user or the app; toasts that timed out into the Action Center are kept.
+    /// At most 32 toasts are kept; beyond that, the least recently shown or read one is dropped.
+    /// This is off by default, and shared with the clones made after enabling it.
+    pub fn retain_rendered_xml(mut self, enabled: bool) -> Self {
+        self.rendered_xml = enabled.then(Arc::default);
+        self
+    }
+
+    /// The XML that was handed to Windows for the toast with `tag` and `group`, if it is
+    /// still kept, see [`ToastManager::retain_rendered_xml`].
+    ///
+    /// Pass the group the toast was shown with, including
+    /// [`ToastManager::default_group`], or `None` for a toast without a group.
+    ///
+    /// This is meant for debugging toasts that look wrong, such as dumping the XML from a
+    /// debug command of the app. See [`Toast::to_xml`] for the XML of a toast not shown yet.
+    pub fn rendered_xml(&self, tag: &str, group: Option<&str>) -> Option<String> {
+        self.rendered_xml.as_ref()?.get(tag, group)
+    }
+
+    /// The counters of this manager and its clones.
+    pub fn metrics(&self) -> Metrics {
+        self.counters.snapshot()
+    }
+
+    /// Show a toast rendered ahead of time, with `data` for its placeholders.
+    ///
+    /// Only the notification itself is created here, the XML document is reused from the template
+    /// and the notifier from earlier calls on this manager.
+    /// When this manager has to adjust the toast, e.g. because [`ToastManager::quiet_hours`] are active,
+    /// it is rendered again with the adjustments.
+    ///
+    /// While the session is locked, a manager with [`ToastManager::defer_while_locked`] queues
+    /// the template and `data`, and returns `Ok(())`.
+    pub fn show_template(&self, template: &ToastTemplate, data: &ToastData) -> Result<()> {
+        if let Some(deferral) = &self.deferral {
+            if session::is_locked() {
+                let queued = Queued::Template(template.clone(), data.clone());
+                deferral.push(self.without_deferral(), queued);
+                return Ok(());
+            }
+        }
+        let notifier = self.notifier()?;
+
+        let mut applied = Vec::new();
+        let toast = self.prepare(&template.toast, &mut applied)?;
+        let doc = template
+            .doc_for(&toast, &applied)
+            .map_err(|e| e.in_stage(BuildStage::Document))?;
+        let notification = self.create_notification(&toast, &doc, Some(data))?;
+
+        notifier
+            .Show(&notification)
+            .map_err(|e| self.show_failed(&notifier, &doc, e))?;
+
+        Ok(())
+    }
+
+    /// Show `toast` with `placeholder` as its image `id` until the real image is available,
+    /// e.g. artwork that is still being downloaded.
+    ///
+    /// Windows has no loading state for images: it waits for remote images before showing
+    /// a toast and leaves them out if they fail. With a local placeholder the toast is shown
+    /// right away, and [`PendingImage::resolve`] replaces it with the real image later.
+    /// `data` holds the values of data-bound placeholders like `{status}`, which
+    /// [`PendingImage::update`] changes in the meantime.
+    ///
+    /// `toast` is validated and shown like with [`ToastManager::show_template`]. It gets a
+    /// generated tag if it has none, since the replacement is found by its tag.
+    pub fn show_with_placeholder(
+        &self,
+        toast: &Toast,
+        id: u8,
+        placeholder: Image,
+        data: &ToastData,
+    ) -> Result<PendingImage> {
+        let mut toast = toast.clone();
+        if toast.tag.is_none() {
+            toast.tag = Some(tag::generate());
+        }
+        if toast.group.is_none() {
+            toast.group = self.default_group.clone();
+        }
+
+        let shown = placeholder::with_image(&toast, id, placeholder);
+        self.validate(&shown)?;
+        self.show_template(&ToastTemplate::new(&shown)?, data)?;
+
+        Ok(PendingImage::new(self.clone(), toast, id, data.clone()))
+    }
+
+    /// Show a toast from its XML, such as XML from the Notifications Visualizer or a server.
+    ///
+    /// The callbacks of this manager are attached as usual. The XML is shown as it is:
+    /// it is not validated, see [`Toast::validate`], and the adjustments of this manager,
+    /// such as [`ToastManager::quiet_hours`], [`ToastManager::default_group`] and
+    /// [`ToastManager::defer_while_locked`], don't apply.
+    ///
+    /// Returns [`WinToastError::InvalidXml`] if `xml` can't be parsed.
+    pub fn show_xml(&self, xml: &str, options: ShowOptions) -> Result<()> {
+        let notifier = self.notifier()?;
+
+        let doc = xml::load_document(xml)?;
+
+        let mut toast = Toast::new();
+        toast.tag = options.tag;
+        toast.group = options.group;
+        toast.expires_in = options.expires_in;
+        let notification = self.create_notification(&toast, &doc, options.data.as_ref())?;
+
+        notifier
+            .Show(&notification)
+            .map_err(|e| self.show_failed(&notifier, &doc, e))?;
+
+        Ok(())
+    }
+
+    /// Update the data-bound placeholders of the shown toast with `tag` and `group`.
+    ///
+    /// Only the values in `data` are changed, see [`ToastData`] for the placeholders.
+    /// Values with a lower sequence number than the values the toast already has are
+    /// dropped, see [`ToastData::set_sequence_number`].
+    pub fn update(
+        &self,
+        tag: &str,
+        group: Option<&str>,
+        data: &ToastData,
+    ) -> Result<NotificationUpdateResult> {
+        let notifier = self.notifier()?;
+        Self::update_with(&notifier, tag, group, data)
+    }
+
+    /// Update several shown toasts in `group`, each with the `(tag, data)` given for it.
+    ///
+    /// This is [`ToastManager::update`] for many toasts, sharing one notifier between them.
+    /// The updates are applied in order and independently of each other: a failed update
+    /// does not stop or undo the others, and its error is reported at its index.
+    pub fn update_many(
+        &self,
+        group: Option<&str>,
+        updates: &[(&str, ToastData)],
+    ) -> Vec<Result<NotificationUpdateResult>> {
+        let notifier = match self.winrt_notifier() {
+            Ok(notifier) => notifier,
+            Err(e) => {
+                return updates
+                    .iter()
+                    .map(|_| Err(self.unregistered(e.clone(), WinToastError::NotifierUnavailable)))
+                    .collect()
+            }
+        };
+
+        updates
+            .iter()
+            .map(|(tag, data)| Self::update_with(&notifier, tag, group, data))
+            .collect()
+    }
+
+    /// Update the data-bound placeholders of every toast in `group` that is still shown
+    /// or in the Action Center, each with the same `data`.
+    ///
+    /// Windows has no update for a whole group, only for a tag within a group, so this reads
+    /// the tags of the group from the history and updates each of them like
+    /// [`ToastManager::update_many`]. Toasts of the group without a tag cannot be updated and
+    /// are skipped, as are those whose tag fails [`Tag::new`], and toasts shown after the
+    /// history was read are not updated.
+    /// Each result is paired with the tag of the toast it is for.
+    pub fn update_group(
+        &self,
+        group: &str,
+        data: &ToastData,
+    ) -> Result<Vec<(Tag, Result<NotificationUpdateResult>)>> {
+        let notifier = self.notifier()?;
+        let history = ToastNotificationManager::History()?;
+        let shown = history
+            .GetHistoryWithId(&self.app_id)?
+            .into_iter()
+            .map(|notification| {
+                let tag = Tag::from_os(notification.Tag());
+                (tag, non_empty(notification.Group()))
+            });
+
+        Ok(fan_out(shown, group, |tag| {
+            Self::update_with(&notifier, tag, Some(group), data)
+        }))
+    }
+
+    fn update_with(
+        notifier: &ToastNotifier,
+        tag: &str,
+        group: Option<&str>,
+        data: &ToastData,
+    ) -> Result<NotificationUpdateResult> {
+        let data = data.to_winrt()?;
+        let result = match group {
+            Some(group) => notifier.UpdateWithTagAndGroup(&data, &hs(tag), &hs(group))?,
+            None => notifier.UpdateWithTag(&data, &hs(tag))?,
+        };
+
+        Ok(NotificationUpdateResult::from_winrt(result))
+    }
+
+    /// Check `toast` for mistakes without showing it, see [`Toast::validate`].
+    ///
+    /// [`ValidationIssue::EmptyToast`](crate::ValidationIssue::EmptyToast) is ignored if
+    /// [`ToastManager::allow_empty_toasts`] is enabled.
+    pub fn validate(&self, toast: &Toast) -> Result<()> {
+        self.check_issues(toast.validation_issues())
+    }
+
+    /// Fail with the first issue that is neither a warning nor allowed by this manager.
+    fn check_issues(&self, issues: impl IntoIterator<Item = ValidationIssue>) -> Result<()> {
+        check_issues(issues, self.allow_empty_toasts)
+    }
+
+    /// Validate a toast and send it to Windows for display.
+    ///
+    /// See [`Toast::validate`] for what is checked, and [`ToastManager::show_unchecked`]
+    /// to skip validation for toasts that are known to be valid.
+    ///
+    /// Each call is independent: showing the same toast twice validates it twice, including
+    /// the existence of its image files, and creates two notifications.
+    ///
+    /// The returned [`ToastHandle`] hides the toast or removes it from the Action Center later.
+    /// It may be dropped right away, which leaves the toast on screen.
+    pub fn show(&self, toast: &Toast) -> Result<ToastHandle> {
+        self.show_checked(toast).map(|(_, handle)| handle)
+    }
+
+    /// Show a toast with `title` and `body`, and return its tag.
+    ///
+    /// This is the shortest way to show a toast:
+    /// ```no_run
+    /// # use winrt_toast_reborn::ToastManager;
+    /// let manager = ToastManager::new(ToastManager::POWERSHELL_AUM_ID);
+    /// let tag = manager.notify("Download finished", "report.pdf")?;
+    /// # Ok::<(), winrt_toast_reborn::WinToastError>(())
+    /// ```
+    ///
+    /// The toast is shown with [`ToastManager::show`], so it is validated and gets the
+    /// callbacks and defaults of this manager like any other toast. The tag is generated,
+    /// use it to update or remove the toast later.
+    pub fn notify(&self, title: &str, body: &str) -> Result<String> {
+        self.notify_with(title, body, |_| {})
+    }
+
+    /// Like [`ToastManager::notify`], but `customize` can change the toast before it is shown.
+    ///
+    /// A tag set by `customize` is kept and returned instead of the generated one.
+    pub fn notify_with(
+        &self,
+        title: &str,
+        body: &str,
+        customize: impl FnOnce(&mut Toast),
+    ) -> Result<String> {
+        let mut toast = Toast::new();
+        toast.text1(title).text2(body).tag(tag::generate());
+        customize(&mut toast);
+        self.show(&toast)?;
+
+        Ok(toast.tag.unwrap_or_default())
+    }
+
+    /// Like [`ToastManager::show`], but also report what was done to the toast on the way.
+    pub fn show_reported(&self, toast: &Toast) -> Result<ShowReport> {
+        let replaces = self.replaces_shown(toast);
+        let (mut report, _) = self.show_checked(toast)?;
+        report.deduped = report.replaced(replaces);
+        Ok(report)
+    }
+
+    /// Whether the Action Center holds a toast with the tag and group of `toast`,
+    /// which showing `toast` replaces.
+    fn replaces_shown(&self, toast: &Toast) -> bool {
+        let Some(tag) = &toast.tag else {
+            return false;
+        };
+        let group = toast.group.as_ref().or(self.default_group.as_ref());
+        self.history().is_ok_and(|entries| {
+            entries
+                .iter()
+                .any(|entry| entry.tag.as_deref() == Some(tag) && entry.group.as_ref() == group)
+        })
+    }
+
+    fn show_checked(&self, toast: &Toast) -> Result<(ShowReport, ToastHandle)> {
+        self.show_checked_with(toast, |_| Ok(()))
+    }
+
+    /// Validate and show a toast like [`ToastManager::show_checked`], calling `attach` with the
+    /// notification right before it is shown.
+    fn show_checked_with(
+        &self,
+        toast: &Toast,
+        attach: impl FnOnce(&ToastNotification) -> Result<()>,
+    ) -> Result<(ShowReport, ToastHandle)> {
+        let issues = toast.validation_issues();
+        let mut report = ShowReport::new(toast, &issues);
+        self.check_before_show(toast, issues)?;
+
+        let handle = self.show_with(toast, &mut report, attach)?;
+        Ok((report, handle))
+    }
+
+    /// Send a toast to Windows for display without validating it first.
+    pub fn show_unchecked(&self, toast: &Toast) -> Result<ToastHandle> {
+        self.show_with_report(toast, &mut ShowReport::default())
+    }
+
+    /// Validate and show a toast on a worker thread, so slow calls into Windows don't block
+    /// the calling thread.
+    ///
+    /// The returned [`ShowTicket`] delivers the result of [`ToastManager::show`]. The toasts
+    /// shown with this method by a manager and its clones are shown one after the other in
+    /// the order of the calls, on a single worker thread that is started on first use and
+    /// exits once the manager and all its clones are dropped. See [`Metrics::last_show_ms`]
+    /// for how long Windows takes to accept a toast.
+    pub fn show_detached(&self, toast: Toast) -> ShowTicket {
+        let (sender, receiver) = mpsc::channel();
+        self.detach(toast, detached::Reply::Ticket(sender));
+        ShowTicket::new(receiver)
+    }
+
+    /// Validate and show a toast on a worker thread like [`ToastManager::show_detached`],
+    /// and report to `events` what happens to it.
+    ///
+    /// Once Windows accepted the toast, [`ToastEvent::Shown`] is sent with its handle,
+    /// followed by its activations, dismissal and failure as in
+    /// [`ToastManager::show_collecting`]. If it can't be shown, [`ToastEvent::Failed`] is
+    /// sent with the error instead. Toasts queued by [`ToastManager::defer_while_locked`]
+    /// are reported as shown, but their later events are not sent.
+    ///
+    /// One channel can collect the events of many toasts, which are reported as shown in
+    /// the order of the calls. Tell them apart by the tag of their handles and events.
+    pub fn show_detached_events(&self, toast: Toast, events: mpsc::Sender<ToastEvent>) {
+        self.detach(toast, detached::Reply::Events(events));
+    }
+
+    fn detach(&self, toast: Toast, reply: detached::Reply) {
+        let worker = self.detached.get_or_init(|| {
+            let mut manager = self.clone();
+            manager.detached = Arc::default();
+            detached::spawn(move |toast, events| match events {
+                Some(events) => manager
+                    .show_checked_with(toast, |notification| {
+                        manager.send_events(toast, notification, events.clone())
+                    })
+                    .map(|(_, handle)| handle),
+                None => manager.show(toast),
+            })
+        });
+        let _ = worker.send(detached::Job::Show(Box::new(toast), reply));
+    }
+
+    /// Wait until the toasts passed to [`ToastManager::show_detached`] so far were shown.
+    fn wait_for_detached(&self) {
+        if let Some(worker) = self.detached.get() {
+            let (done, wait) = mpsc::channel();
+            if worker.send(detached::Job::Barrier(done)).is_ok() {
+                let _ = wait.recv();
             }
-            // </input>
-            // <action>
-            for action in &toast.actions {
-                let el = toast_doc.CreateElement(&hs("action"))?;
-                actions_el.AppendChild(&el)?;
-                action.write_to_element(&el)?;
+        }
+    }
+
+    /// Show a toast and collect what happens to it, until it is resolved or `timeout` passed.
+    ///
+    /// The toast is validated like in [`ToastManager::show`]. The events are returned in the
+    /// order they arrived, and are also passed to the callbacks of this manager as usual.
+    /// The toast is resolved by
+    /// * an activation, unless the activated action keeps the toast on screen with
+    ///   [`AfterActivation::PendingUpdate`](crate::content::action::AfterActivation::PendingUpdate),
+    ///   in which case further activations are collected,
+    /// * a dismissal, including a timeout into the Action Center,
+    /// * a failure to show it.
+    ///
+    /// The [`ActivatedAction::value`] of an activation is that of the input passed to
+    /// [`ToastManager::on_activated`], or else that of the input of the toast; all values
+    /// are in [`ActivatedAction::values`].
+    ///
+    /// Reaching `timeout` is not an error, the events so far are returned. If the toast was
+    /// queued by [`ToastManager::defer_while_locked`], nothing is collected and the result is empty.
+    ///
+    /// This blocks the calling thread; Windows delivers the events on its own threads.
+    pub fn show_collecting(&self, toast: &Toast, timeout: Duration) -> Result<Vec<ToastEvent>> {
+        let deadline = Instant::now() + timeout;
+        self.check_before_show(toast, toast.validation_issues())?;
+
+        let (sender, receiver) = mpsc::channel();
+        let mut report = ShowReport::default();
+        self.show_with(toast, &mut report, |notification| {
+            self.send_events(toast, notification, sender)
+        })?;
+        if report.deferred {
+            return Ok(Vec::new());
+        }
+
+        let kept: Vec<&str> = toast
+            .actions
+            .iter()
+            .filter(|action| action.keeps_toast())
+            .map(|action| action.arguments())
+            .collect();
+
+        Ok(collect_events(&receiver, deadline, &kept))
+    }
+
+    /// Send the events of `notification`, shown for `toast`, to `sender`.
+    ///
+    /// [`ActivatedAction::value`] is read from the input passed to
+    /// [`ToastManager::on_activated`], or else from the input of the toast.
+    fn send_events(
+        &self,
+        toast: &Toast,
+        notification: &ToastNotification,
+        sender: mpsc::Sender<ToastEvent>,
+    ) -> Result<()> {
+        let input_id = match (&self.activation_callback, &toast.input) {
+            (Some((id, _)), _) if !id.is_empty() => id.clone(),
+            (_, Some(input)) => input.id.clone(),
+            _ => String::new(),
+        };
+        let (limits, counters) = (self.input_limits, self.counters.clone());
+        let (activated, dismissed, failed) = (sender.clone(), sender.clone(), sender);
+        notification.Activated(&TypedEventHandler::new(
+            move |notification: &Option<ToastNotification>, args: &Option<IInspectable>| {
+                let action =
+                    Self::get_activated_action(notification, args, &input_id, &limits, &counters);
+                let _ = activated.send(ToastEvent::Activated(action));
+                Ok(())
+            },
+        ))?;
+        notification.Dismissed(&TypedEventHandler::new(
+            move |notification: &Option<ToastNotification>,
+                  args: &Option<ToastDismissedEventArgs>| {
+                let dismissal = Self::get_dismissed(notification, args);
+                let _ = dismissed.send(ToastEvent::Dismissed(dismissal));
+                Ok(())
+            },
+        ))?;
+        notification.Failed(&TypedEventHandler::new(
+            move |_, args: &Option<ToastFailedEventArgs>| {
+                let _ = failed.send(ToastEvent::Failed(Self::get_failed_error(args)));
+                Ok(())
+            },
+        ))?;
+        Ok(())
+    }
+
+    fn show_with_report(&self, toast: &Toast, report: &mut ShowReport) -> Result<ToastHandle> {
+        self.show_with(toast, report, |_| Ok(()))
+    }
+
+    /// Show a toast, calling `attach` with the notification right before it is shown.
+    fn show_with(
+        &self,
+        toast: &Toast,
+        report: &mut ShowReport,
+        attach: impl FnOnce(&ToastNotification) -> Result<()>,
+    ) -> Result<ToastHandle> {
+        self.try_show_with(toast, report, attach)
+            .inspect_err(|error| {
+                self.undeliverable(toast, UndeliverableReason::Failed(error.to_string()));
+            })
+    }
+
+    /// A clone of this manager that shows toasts right away, for the toasts it queued.
+    fn without_deferral(&self) -> Self {
+        let mut manager = self.clone();
+        manager.deferral = None;
+        manager
+    }
+
+    fn try_show_with(
+        &self,
+        toast: &Toast,
+        report: &mut ShowReport,
+        attach: impl FnOnce(&ToastNotification) -> Result<()>,
+    ) -> Result<ToastHandle> {
+        let unshown = |toast: &Toast| self.handle(toast, None);
+        if let Some(deferral) = &self.deferral {
+            if session::is_locked() {
+                deferral.push(self.without_deferral(), Queued::Toast(toast.clone()));
+                report.deferred = true;
+                return Ok(unshown(toast));
             }
-            // </action>
         }
-        // </actions>
 
-        let toast_notifier = ToastNotification::CreateToastNotification(&toast_doc)?;
+        let notifier = self.notifier()?;
+        let disabled = || matches!(notifier.Setting(), Ok(s) if s != NotificationSetting::Enabled);
+        if self.on_undeliverable.is_some() && disabled() {
+            self.undeliverable(toast, UndeliverableReason::Disabled);
+            report.undelivered = Some(UndeliverableReason::Disabled);
+            return Ok(unshown(toast));
+        }
+        let start = Instant::now();
+        let toast = self.prepare(toast, &mut report.applied_defaults)?;
+
+        let toast_doc = toast
+            .to_document()
+            .map_err(|e| e.in_stage(BuildStage::Document))?;
+
+        let toast_notifier = self.create_notification(&toast, &toast_doc, None)?;
+        self.counters.record_render(start.elapsed());
+        let ringing = self
+            .attach_tracking(&toast, &toast_doc, &toast_notifier, attach)
+            .map_err(|e| e.in_stage(BuildStage::Notification))?;
+
+        let start = Instant::now();
+        notifier
+            .Show(&toast_notifier)
+            .map_err(|e| self.show_failed(&notifier, &toast_doc, e))?;
+        self.counters.record_show(start.elapsed());
+        let handle = self.handle(&toast, Some((notifier, toast_notifier)));
+        if let Some((ringing, after)) = ringing {
+            let (timers, toast) = (self.timers.clone(), toast.into_owned());
+            ringing.silence_after(after, timers, self.clone(), toast, handle.clone());
+        }
+
+        Ok(handle)
+    }
+
+    /// The handle of `toast`, with the notifier and notification it was `shown` with, if any.
+    ///
+    /// The default group is filled in for toasts that were not prepared, as it will be
+    /// when a deferred toast is shown.
+    fn handle(
+        &self,
+        toast: &Toast,
+        shown: Option<(ToastNotifier, ToastNotification)>,
+    ) -> ToastHandle {
+        ToastHandle::new(
+            self.app_id.clone(),
+            toast.tag.clone(),
+            toast.group.clone().or_else(|| self.default_group.clone()),
+            shown,
+            self.groups.clone(),
+        )
+    }
+
+    /// Attach the handlers that track a toast about to be shown, and `attach`, to its notification.
+    ///
+    /// Returns the ringing of the toast when its looping audio is silenced after a while.
+    fn attach_tracking(
+        &self,
+        toast: &Toast,
+        toast_doc: &XmlDocument,
+        toast_notifier: &ToastNotification,
+        attach: impl FnOnce(&ToastNotification) -> Result<()>,
+    ) -> Result<Option<(Ringing, Duration)>> {
+        if let Some(handler) = &self.on_undeliverable {
+            let (handler, toast) = (handler.clone(), toast.clone());
+            toast_notifier.Failed(&TypedEventHandler::new(
+                move |_, args: &Option<ToastFailedEventArgs>| {
+                    let reason =
+                        UndeliverableReason::Failed(Self::get_failed_error(args).to_string());
+                    catch_panic(|| handler(&toast, reason));
+                    Ok(())
+                },
+            ))?;
+        }
+        if let (Some(groups), Some(group)) = (&self.groups, &toast.group) {
+            groups.track(toast_notifier, toast, group)?;
+        }
+        if let (Some(rendered), Some(tag)) = (&self.rendered_xml, &toast.tag) {
+            let xml = toast_doc.GetXml()?.to_string();
+            rendered.track(toast_notifier, toast.group.as_deref(), tag, xml)?;
+        }
+        let ringing = match toast.audio.as_ref().and_then(Audio::silence_after) {
+            Some(after) => Some((Ringing::attach(toast_notifier)?, after)),
+            None => None,
+        };
+        attach(toast_notifier)?;
+
+        Ok(ringing)
+    }
+
+    /// Create the notification for a rendered toast, with its properties, `data` and handlers attached.
+    fn create_notification(
+        &self,
+        toast: &Toast,
+        toast_doc: &XmlDocument,
+        data: Option<&ToastData>,
+    ) -> Result<ToastNotification> {
+        self.try_create_notification(toast, toast_doc, data)
+            .map_err(|e| e.in_stage(BuildStage::Notification))
+    }
+
+    fn try_create_notification(
+        &self,
+        toast: &Toast,
+        toast_doc: &XmlDocument,
+        data: Option<&ToastData>,
+    ) -> Result<ToastNotification> {
+        let notification = ToastNotification::CreateToastNotification(toast_doc)?;
+
+        let mut data = data.map(ToastData::to_winrt).transpose()?;
+        let mut entries: Vec<_> = toast
+            .attached_title()
+            .map(|title| (hs(FULL_TITLE_KEY), title.to_string()))
+            .into_iter()
+            .collect();
+        if self.feedback_sounds {
+            entries.extend(toast.feedback_sounds().map(|(key, alias)| (hs(key), alias)));
+        }
+        entries.extend(correlation::data_entry(toast));
+        for (key, value) in entries {
+            let data = match &mut data {
+                Some(data) => data,
+                None => data.insert(NotificationData::new()?),
+            };
+            data.Values()?.Insert(&key, &hs(value))?;
+        }
+        if let Some(data) = data {
+            notification.SetData(&data)?;
+        }
 
         if let Some(group) = &toast.group {
-            toast_notifier.SetGroup(&hs(group))?;
+            notification.SetGroup(&hs(group))?;
         }
         if let Some(tag) = &toast.tag {
-            toast_notifier.SetTag(&hs(tag))?;
+            notification.SetTag(&hs(tag))?;
         }
         if let Some(remote_id) = &toast.remote_id {
-            toast_notifier.SetRemoteId(&hs(remote_id))?;
+            notification.SetRemoteId(&hs(remote_id))?;
+        }
+        if toast.suppress_popup {
+            notification.SetSuppressPopup(true)?;
+        }
+        if toast.priority == ToastPriority::High {
+            notification.SetPriority(ToastNotificationPriority::High)?;
         }
         if let Some(exp) = toast.expires_in {
-            let now = Calendar::new()?;
-            now.AddSeconds(exp.as_secs() as i32)?;
-            let dt = now.GetDateTime()?;
-            toast_notifier.SetExpirationTime(
+            let dt = time::from_now(exp)?;
+            notification.SetExpirationTime(
                 &PropertyValue::CreateDateTime(dt)?.cast::<IReference<DateTime>>()?,
             )?;
         }
 
         if let Some(handler) = &self.on_activated {
-            toast_notifier.Activated(handler)?;
+            notification.Activated(handler)?;
         }
 
         if let Some(handler) = &self.on_dismissed {
-            toast_notifier.Dismissed(handler)?;
+            notification.Dismissed(handler)?;
         }
 
         if let Some(handler) = &self.on_failed {
-            toast_notifier.Failed(handler)?;
+            notification.Failed(handler)?;
         }
 
-        notifier.Show(&toast_notifier)?;
+        Ok(notification)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{content::action::ActionPlacement, content::input::InputType, Action, Input};
+
+    #[test]
+    fn only_the_handlers_need_the_unsafe_send_and_sync() {
+        fn send_sync<T: Send + Sync>() {}
+        send_sync::<HSTRING>();
+        send_sync::<Option<(String, ActivationCallback)>>();
+        send_sync::<DismissalCallbacks>();
+        send_sync::<Option<UndeliverableHandler>>();
+        send_sync::<Option<QuietHours>>();
+        send_sync::<Option<Arc<RenderedXml>>>();
+        send_sync::<Option<Arc<GroupTracker>>>();
+        send_sync::<InputLimits>();
+        send_sync::<CacheConfig>();
+        send_sync::<Arc<Counters>>();
+        send_sync::<Arc<OnceLock<ToastNotifier>>>();
+        send_sync::<Arc<OnceLock<mpsc::Sender<detached::Job>>>>();
+        send_sync::<Arc<Timers>>();
+    }
+
+    #[test]
+    fn empty_toasts_are_rejected_unless_allowed() {
+        let issues = Toast::new().validation_issues();
+        assert!(matches!(
+            check_issues(issues.clone(), false),
+            Err(WinToastError::Validation(ValidationIssue::EmptyToast))
+        ));
+        assert!(check_issues(issues, true).is_ok());
+
+        let issues = [ValidationIssue::EmptyToast, ValidationIssue::ZeroLoopCount];
+        assert!(matches!(
+            check_issues(issues, true),
+            Err(WinToastError::Validation(ValidationIssue::ZeroLoopCount))
+        ));
+    }
+
+    fn activated() -> ActivatedAction {
+        let values = [
+            ("Count", " 42 "),
+            ("flag", "true"),
+            ("name", "Ada"),
+            ("empty", ""),
+        ];
+        let values = values
+            .into_iter()
+            .map(|(id, value)| (id.to_string(), value.to_string()))
+            .collect();
+        ActivatedAction::new("open", values)
+    }
+
+    #[test]
+    fn values_are_read_by_case_sensitive_id() {
+        let action = activated();
+        assert_eq!(action.value_str("name"), Some("Ada"));
+        assert_eq!(action.value_str("empty"), Some(""));
+        assert_eq!(action.value_str("Name"), None);
+        assert_eq!(action.value_str("missing"), None);
+    }
+
+    #[test]
+    fn values_are_parsed_as_booleans() {
+        let action = activated();
+        assert_eq!(action.value_bool("flag"), Some(Ok(true)));
+        assert!(matches!(action.value_bool("name"), Some(Err(_))));
+        assert!(matches!(action.value_bool("empty"), Some(Err(_))));
+        assert_eq!(action.value_bool("missing"), None);
+    }
 
+    #[test]
+    fn values_are_parsed_as_integers() {
+        let action = activated();
+        assert_eq!(action.value_int("Count"), Some(Ok(42)));
+        assert_eq!(action.value_int("count"), None);
+        assert!(matches!(action.value_int("flag"), Some(Err(_))));
+        assert!(matches!(action.value_int("empty"), Some(Err(_))));
+        assert_eq!(action.value_int("missing"), None);
+    }
+
+    #[test]
+    fn values_are_sorted_by_id() {
+        assert_eq!(
+            activated().values_sorted(),
+            [
+                ("Count", " 42 "),
+                ("empty", ""),
+                ("flag", "true"),
+                ("name", "Ada")
+            ]
+        );
+        assert!(ActivatedAction::new("open", HashMap::new())
+            .values_sorted()
+            .is_empty());
+    }
+
+    #[test]
+    fn boxed_values_of_each_type_become_strings() -> windows::core::Result<()> {
+        let cases = [
+            (
+                PropertyValue::CreateString(&HSTRING::from("reply"))?,
+                "reply",
+            ),
+            (PropertyValue::CreateString(&HSTRING::new())?, ""),
+            (PropertyValue::CreateBoolean(true)?, "true"),
+            (PropertyValue::CreateBoolean(false)?, "false"),
+            (PropertyValue::CreateUInt8(255)?, "255"),
+            (PropertyValue::CreateInt16(-16)?, "-16"),
+            (PropertyValue::CreateUInt16(16)?, "16"),
+            (PropertyValue::CreateInt32(-32)?, "-32"),
+            (PropertyValue::CreateUInt32(32)?, "32"),
+            (
+                PropertyValue::CreateInt64(i64::MIN)?,
+                "-9223372036854775808",
+            ),
+            (
+                PropertyValue::CreateUInt64(u64::MAX)?,
+                "18446744073709551615",
+            ),
+            (PropertyValue::CreateSingle(1.5)?, "1.5"),
+            (PropertyValue::CreateDouble(0.25)?, "0.25"),
+            (PropertyValue::CreateChar16('é' as u16)?, "é"),
+        ];
+        for (value, expected) in cases {
+            assert_eq!(property_to_string(&value).as_deref(), Some(expected));
+        }
         Ok(())
     }
+
+    #[test]
+    fn boxed_values_of_other_types_are_skipped() -> windows::core::Result<()> {
+        let guid = PropertyValue::CreateGuid(windows::core::GUID::zeroed())?;
+        assert_eq!(property_to_string(&guid), None);
+        let array = PropertyValue::CreateInt32Array(&[1, 2])?;
+        assert_eq!(property_to_string(&array), None);
+        Ok(())
+    }
+
+    fn limited(
+        entries: &[(&str, &str)],
+        limits: InputLimits,
+    ) -> (HashMap<String, String>, Metrics) {
+        let counters = Counters::default();
+        let read = |(key, value): &(&str, &str)| Some((key.to_string(), value.to_string()));
+        let values = limit_input_values(entries, read, &limits, &counters);
+        (values, counters.snapshot())
+    }
+
+    #[test]
+    fn input_values_beyond_the_limit_are_dropped_and_counted() {
+        let entries: Vec<_> = (0..100).map(|i| format!("input{}", i)).collect();
+        let entries: Vec<_> = entries.iter().map(|id| (id.as_str(), "x")).collect();
+        let limits = InputLimits {
+            max_values: 64,
+            ..InputLimits::default()
+        };
+        let (values, metrics) = limited(&entries, limits);
+        assert_eq!(values.len(), 64);
+        assert!(values.contains_key("input63") && !values.contains_key("input64"));
+        assert_eq!(metrics.dropped_input_values, 36);
+        assert_eq!(metrics.truncated_input_values, 0);
+    }
+
+    #[test]
+    fn dropped_input_values_are_not_read() {
+        let counters = Counters::default();
+        let limits = InputLimits {
+            max_values: 2,
+            ..InputLimits::default()
+        };
+        let read = |i: usize| {
+            assert!(i < 2, "value {} was read", i);
+            Some((i.to_string(), String::new()))
+        };
+        assert_eq!(limit_input_values(0..5, read, &limits, &counters).len(), 2);
+        assert_eq!(counters.snapshot().dropped_input_values, 3);
+    }
+
+    #[test]
+    fn oversized_input_values_are_truncated_at_a_char_boundary() {
+        let limits = InputLimits {
+            max_value_len: 4,
+            ..InputLimits::default()
+        };
+        let (values, metrics) =
+            limited(&[("a", "ééééé"), ("b", "abcd"), ("c", "abcdefgh")], limits);
+        assert_eq!(values["a"], "éééé");
+        assert_eq!(values["b"], "abcd");
+        assert_eq!(values["c"], "abcd");
+        assert_eq!(metrics.truncated_input_values, 2);
+        assert_eq!(metrics.dropped_input_values, 0);
+    }
+
+    #[test]
+    fn control_characters_are_removed_from_input_ids() {
+        let (values, _) = limited(&[("re\u{0}ply\n", "hi")], InputLimits::default());
+        assert_eq!(values.keys().collect::<Vec<_>>(), ["reply"]);
+    }
+
+    #[test]
+    fn empty_input_values_are_kept() {
+        let (values, _) = limited(&[("reply", ""), ("choice", "b")], InputLimits::default());
+        assert_eq!(values.get("reply").map(String::as_str), Some(""));
+        assert_eq!(values.len(), 2);
+
+        let action = ActivatedAction::new("send", values).with_value("");
+        assert_eq!(action.value.as_deref(), Some(""));
+        assert_eq!(action.value_str("reply"), Some(""));
+    }
+
+    #[test]
+    fn the_group_of_a_toast_overrides_the_default_group() {
+        let mut toast = Toast::new();
+        toast.text1("Hello");
+        let mut grouped = Cow::Borrowed(&toast);
+        assert!(with_default_group(&mut grouped, Some("default")));
+        assert_eq!(grouped.group.as_deref(), Some("default"));
+
+        toast.group("own");
+        let mut own = Cow::Borrowed(&toast);
+        assert!(!with_default_group(&mut own, Some("default")));
+        assert!(matches!(own, Cow::Borrowed(_)));
+        assert_eq!(own.group.as_deref(), Some("own"));
+
+        let mut toast = Toast::new();
+        toast.text1("Hello");
+        let mut ungrouped = Cow::Borrowed(&toast);
+        assert!(!with_default_group(&mut ungrouped, None));
+        assert_eq!(ungrouped.group, None);
+    }
+
+    #[test]
+    fn each_dismissal_reason_is_mapped() {
+        let cases = [
+            (
+                ToastDismissalReason::UserCanceled,
+                DismissalReason::UserCanceled,
+            ),
+            (
+                ToastDismissalReason::ApplicationHidden,
+                DismissalReason::ApplicationHidden,
+            ),
+            (ToastDismissalReason::TimedOut, DismissalReason::TimedOut),
+            (ToastDismissalReason(3), DismissalReason::Other(3)),
+            (ToastDismissalReason(-1), DismissalReason::Other(-1)),
+        ];
+        for (winrt, reason) in cases {
+            assert_eq!(DismissalReason::from_winrt(winrt), reason);
+        }
+    }
+
+    #[test]
+    fn expiration_times_are_read_when_present() {
+        assert_eq!(expires_at(None), None);
+        let expires = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let set = time::to_winrt_datetime(expires).unwrap();
+        assert_eq!(expires_at(Some(set)), Some(expires));
+        let shown = time::from_now(Duration::from_secs(60)).unwrap();
+        let in_a_minute = expires_at(Some(shown)).unwrap();
+        assert!(in_a_minute > SystemTime::now() + Duration::from_secs(50));
+        assert_eq!(expires_at(Some(DateTime { UniversalTime: -1 })), None);
+    }
+
+    #[test]
+    fn dismissals_have_no_expiration_time_unless_given() {
+        assert_eq!(
+            ToastDismissed::new(DismissalReason::UserCanceled).expires_at,
+            None
+        );
+        let expires = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let dismissed = ToastDismissed::new(DismissalReason::TimedOut).with_expires_at(expires);
+        assert_eq!(dismissed.expires_at, Some(expires));
+    }
+
+    #[test]
+    fn reports_start_with_the_tag_and_the_warnings() {
+        let mut toast = Toast::new();
+        toast.text1("Hello").tag("inbox");
+        let issues = [
+            ValidationIssue::ButtonStyleIgnored,
+            ValidationIssue::EmptyToast,
+        ];
+        let report = ShowReport::new(&toast, &issues);
+        assert_eq!(report.tag, Some(Tag::new("inbox").unwrap()));
+        assert_eq!(report.degradations, [ValidationIssue::ButtonStyleIgnored]);
+        assert!(report.applied_defaults.is_empty());
+        assert!(!report.deduped);
+
+        toast.tag("");
+        assert_eq!(ShowReport::new(&toast, &[]).tag, None);
+    }
+
+    #[test]
+    fn only_shown_toasts_are_reported_as_deduped() {
+        let shown = ShowReport::default();
+        assert!(shown.replaced(true));
+        assert!(!shown.replaced(false));
+        let deferred = ShowReport {
+            deferred: true,
+            ..ShowReport::default()
+        };
+        assert!(!deferred.replaced(true));
+        let undelivered = ShowReport {
+            undelivered: Some(UndeliverableReason::Disabled),
+            ..ShowReport::default()
+        };
+        assert!(!undelivered.replaced(true));
+    }
+
+    #[test]
+    fn activated_toasts_are_removed_by_tag_and_group() {
+        let removed = |tag: Option<&str>, group: Option<&str>| {
+            let mut calls = Vec::new();
+            let tag = tag.map(str::to_string);
+            remove_tagged(tag, group.map(str::to_string), |tag, group| {
+                calls.push((tag.to_string(), group.map(str::to_string)));
+                Ok(())
+            })
+            .unwrap();
+            calls
+        };
+        assert_eq!(removed(Some("a"), None), [("a".to_string(), None)]);
+        assert_eq!(
+            removed(Some("a"), Some("g")),
+            [("a".to_string(), Some("g".to_string()))]
+        );
+        assert!(removed(None, Some("g")).is_empty());
+    }
+
+    fn collected(events: Vec<ToastEvent>, kept: &[&str]) -> Vec<ToastEvent> {
+        let (sender, receiver) = mpsc::channel();
+        for event in events {
+            sender.send(event).unwrap();
+        }
+        collect_events(&receiver, Instant::now() + Duration::from_millis(50), kept)
+    }
+
+    fn activation(arg: &str) -> ToastEvent {
+        ToastEvent::Activated(Some(ActivatedAction::new(arg, HashMap::new())))
+    }
+
+    #[test]
+    fn events_are_collected_until_an_activation() {
+        let events = collected(
+            vec![
+                activation("like"),
+                activation("like"),
+                activation("open"),
+                activation("late"),
+            ],
+            &["like"],
+        );
+        let args: Vec<_> = events
+            .iter()
+            .map(|event| match event {
+                ToastEvent::Activated(Some(action)) => action.arg.as_str(),
+                event => panic!("{:?}", event),
+            })
+            .collect();
+        assert_eq!(args, ["like", "like", "open"]);
+    }
+
+    #[test]
+    fn dismissals_and_failures_resolve_the_toast() {
+        let dismissed =
+            ToastEvent::Dismissed(Ok(ToastDismissed::new(DismissalReason::UserCanceled)));
+        let events = collected(vec![dismissed, activation("late")], &[]);
+        assert!(matches!(events[..], [ToastEvent::Dismissed(Ok(_))]));
+
+        let failed = ToastEvent::Failed(WinToastError::InvalidDismissalReason);
+        let events = collected(
+            vec![activation("like"), failed, activation("late")],
+            &["like"],
+        );
+        assert!(matches!(
+            events[..],
+            [ToastEvent::Activated(_), ToastEvent::Failed(_)]
+        ));
+    }
+
+    #[test]
+    fn the_events_so_far_are_returned_on_timeout() {
+        // The sender stays alive, so the toast is never resolved.
+        let (sender, receiver) = mpsc::channel();
+        sender.send(activation("like")).unwrap();
+        let events = collect_events(
+            &receiver,
+            Instant::now() + Duration::from_millis(20),
+            &["like"],
+        );
+        assert_eq!(events.len(), 1);
+        assert!(collect_events(&receiver, Instant::now(), &[]).is_empty());
+    }
+
+    #[test]
+    fn group_updates_fan_out_to_the_tags_of_the_group() {
+        let tag = |tag: &str| Some(Tag::new(tag).unwrap());
+        let group = |group: &str| Some(group.to_string());
+        let shown = vec![
+            (tag("a"), group("mail")),
+            (tag("b"), group("chat")),
+            (None, group("mail")),
+            (tag("c"), None),
+            (tag("d"), group("mail")),
+            (tag("e"), group("Mail")),
+        ];
+        let mut updated = Vec::new();
+        let results = fan_out(shown, "mail", |tag| {
+            updated.push(tag.to_string());
+            updated.len()
+        });
+        assert_eq!(updated, ["a", "d"]);
+        assert_eq!(
+            results,
+            [(Tag::new("a").unwrap(), 1), (Tag::new("d").unwrap(), 2)]
+        );
+    }
+
+    fn reply_toast() -> Toast {
+        let mut toast = Toast::new();
+        toast
+            .text1("New message")
+            .input(Input::new("reply", InputType::Text))
+            .action(Action::new("Like", "like", "").with_placement(ActionPlacement::ContextMenu))
+            .action(Action::new("Send", "send", ""));
+        toast
+    }
+
+    #[test]
+    fn unanchored_inputs_are_a_warning() {
+        let toast = reply_toast();
+        assert_eq!(
+            toast.validation_issues(),
+            [ValidationIssue::UnanchoredInput("reply".into())]
+        );
+        assert!(toast.validate().is_ok());
+    }
+
+    #[test]
+    fn the_first_button_is_anchored_to_the_input() {
+        let toast = reply_toast();
+        let mut anchored = Cow::Borrowed(&toast);
+        assert!(anchor_input(&mut anchored));
+        assert_eq!(anchored.actions[0].input_id(), None);
+        assert_eq!(anchored.actions[1].input_id(), Some("reply"));
+        assert!(anchored.validation_issues().is_empty());
+
+        assert!(!anchor_input(&mut anchored));
+    }
+
+    #[test]
+    fn inputs_without_a_button_are_left_alone() {
+        let mut toast = reply_toast();
+        toast.actions.remove(1);
+        let mut unchanged = Cow::Borrowed(&toast);
+        assert!(!anchor_input(&mut unchanged));
+        assert!(matches!(unchanged, Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn snooze_intervals_are_positive_minutes() {
+        assert_eq!(snooze_interval("15").unwrap(), Duration::from_secs(15 * 60));
+        assert_eq!(snooze_interval(" 5 ").unwrap(), Duration::from_secs(5 * 60));
+        for value in ["", "0", "-5", "1.5", "soon", "99999999999"] {
+            assert!(
+                matches!(
+                    snooze_interval(value),
+                    Err(WinToastError::InvalidSnoozeInterval(v)) if v == value
+                ),
+                "{}",
+                value
+            );
+        }
+    }
 }
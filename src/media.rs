@@ -0,0 +1,145 @@
+use std::path::{Component, Path, PathBuf, Prefix};
+
+use url::Url;
+
+use crate::WinToastError;
+
+/// Convert a local path to the `file:` URL used as the `src` of media in a toast.
+///
+/// This is the conversion used by [`Image::new_local`](crate::Image::new_local).
+///
+/// * The path must be absolute, otherwise [`WinToastError::InvalidPath`] is returned.
+/// * Verbatim prefixes are removed: `\\?\C:\dir\a.png` becomes `file:///C:/dir/a.png`
+///   and `\\?\UNC\server\share\a.png` becomes `file://server/share/a.png`.
+///   Other verbatim and device paths cannot be expressed as URLs and are rejected.
+/// * Trailing separators are dropped, since media always refers to a file.
+/// * Spaces and other reserved characters are percent-encoded, non-ASCII characters are
+///   encoded as UTF-8 and then percent-encoded.
+/// * Paths that are not valid Unicode, i.e. that contain unpaired surrogates, cannot be
+///   percent-encoded faithfully and are rejected with [`WinToastError::NonUnicode`].
+pub fn path_to_media_url(path: &Path) -> crate::Result<Url> {
+    if !path.is_absolute() {
+        return Err(invalid(path));
+    }
+    if path.to_str().is_none() {
+        return Err(WinToastError::NonUnicode(path.as_os_str().to_owned()));
+    }
+
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Prefix(prefix) => match prefix.kind() {
+                Prefix::VerbatimDisk(disk) => normalized.push(format!("{}:\\", disk as char)),
+                Prefix::VerbatimUNC(server, share) => {
+                    let (server, share) = (server.to_str(), share.to_str());
+                    let (Some(server), Some(share)) = (server, share) else {
+                        return Err(invalid(path));
+                    };
+                    normalized.push(format!("\\\\{}\\{}\\", server, share));
+                }
+                Prefix::Verbatim(_) | Prefix::DeviceNS(_) => return Err(invalid(path)),
+                Prefix::UNC(..) | Prefix::Disk(_) => normalized.push(prefix.as_os_str()),
+            },
+            component => normalized.push(component),
+        }
+    }
+
+    Url::from_file_path(&normalized).map_err(|_| invalid(path))
+}
+
+fn invalid(path: &Path) -> WinToastError {
+    WinToastError::InvalidPath(path.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{ffi::OsString, os::windows::ffi::OsStringExt};
+
+    use super::*;
+
+    const DIRS: [&str; 3] = [r"C:\", r"D:\deep\dir\", r"\\server\share\"];
+    const NAMES: [&str; 7] = [
+        "a.png",
+        "with space.png",
+        "ünïcödé.png",
+        "日本語.png",
+        "100%.png",
+        "#hash?.png",
+        "a+b&c;d.png",
+    ];
+
+    fn verbatim(path: &str) -> String {
+        match path.strip_prefix(r"\\") {
+            Some(unc) => format!(r"\\?\UNC\{}", unc),
+            None => format!(r"\\?\{}", path),
+        }
+    }
+
+    #[test]
+    fn urls_lead_back_to_the_path() {
+        for dir in DIRS {
+            for name in NAMES {
+                let path = format!("{}{}", dir, name);
+                let url = path_to_media_url(Path::new(&path)).unwrap();
+                assert_eq!(url.scheme(), "file", "{}", path);
+                assert_eq!(url.to_file_path().unwrap(), Path::new(&path), "{}", url);
+                assert!(url.as_str().is_ascii(), "{}", url);
+            }
+        }
+    }
+
+    #[test]
+    fn verbatim_and_trailing_separators_give_the_same_url() {
+        for dir in DIRS {
+            for name in NAMES {
+                let path = format!("{}{}", dir, name);
+                let url = path_to_media_url(Path::new(&path)).unwrap();
+                let verbatim = verbatim(&path);
+                assert_eq!(path_to_media_url(Path::new(&verbatim)).unwrap(), url);
+                let trailing = format!(r"{}\", path);
+                assert_eq!(path_to_media_url(Path::new(&trailing)).unwrap(), url);
+            }
+        }
+    }
+
+    #[test]
+    fn unpaired_surrogates_are_rejected() {
+        let path = PathBuf::from(OsString::from_wide(&[
+            'C' as u16,
+            ':' as u16,
+            '\\' as u16,
+            0xD800,
+            '.' as u16,
+        ]));
+        assert!(matches!(
+            path_to_media_url(&path),
+            Err(WinToastError::NonUnicode(p)) if p == path.as_os_str()
+        ));
+    }
+
+    #[test]
+    fn unc_extended_and_relative_paths() {
+        let cases = [
+            (r"\\server\share\a.png", Some("file://server/share/a.png")),
+            (
+                r"\\?\UNC\server\share\a.png",
+                Some("file://server/share/a.png"),
+            ),
+            (r"\\?\C:\dir\a.png", Some("file:///C:/dir/a.png")),
+            (r"C:\dir\a.png", Some("file:///C:/dir/a.png")),
+            (r"\\?\GLOBALROOT\Device\a.png", None),
+            (r"\\.\pipe\a", None),
+            (r"dir\a.png", None),
+            (r"..\a.png", None),
+            (r"\dir\a.png", None),
+            (r"C:a.png", None),
+        ];
+        for (path, url) in cases {
+            match (path_to_media_url(Path::new(path)), url) {
+                (Ok(actual), Some(url)) => assert_eq!(actual.as_str(), url),
+                (Err(WinToastError::InvalidPath(p)), None) => assert_eq!(p, Path::new(path)),
+                (result, _) => panic!("{}: {:?}", path, result),
+            }
+        }
+    }
+}
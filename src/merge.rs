@@ -0,0 +1,233 @@
+use crate::{OverflowStrategy, Toast, ToastPriority};
+
+impl Toast {
+    /// Layer the properties set on `other` over this toast, e.g. per-event content over
+    /// a base toast with the app's defaults.
+    ///
+    /// The rules, by kind of property:
+    /// * Properties that are set or unset, such as the header, each text element, tag, group,
+    ///   launch string, audio, input and correlation id, are replaced if `other` sets them,
+    ///   and kept otherwise.
+    /// * Images are keyed by their id: an image of `other` replaces the image with the same id.
+    /// * Actions and selections of `other` are appended after the ones of this toast.
+    /// * Raw attributes of `other` replace the attributes with the same name and are
+    ///   appended otherwise.
+    /// * Flags such as [`Toast::suppress_popup`] are on if they are on in either toast.
+    /// * The overflow strategy and priority are replaced if `other` doesn't use the default.
+    ///
+    /// ```
+    /// # use winrt_toast_reborn::Toast;
+    /// let mut base = Toast::new();
+    /// base.group("downloads").text3("Downloader");
+    ///
+    /// let mut event = Toast::new();
+    /// event.text1("Download finished").text2("report.pdf");
+    ///
+    /// base.merge(&event);
+    ///
+    /// let xml = base.to_xml()?;
+    /// assert!(xml.contains("Download finished"));
+    /// assert!(xml.contains("report.pdf"));
+    /// assert!(xml.contains("Downloader"));
+    /// # Ok::<(), winrt_toast_reborn::WinToastError>(())
+    /// ```
+    pub fn merge(&mut self, other: &Toast) -> &mut Toast {
+        let Toast {
+            header,
+            text,
+            images,
+            progress,
+            tag,
+            group,
+            remote_id,
+            expires_in,
+            scenario,
+            launch,
+            duration,
+            audio,
+            actions,
+            input,
+            selections,
+            use_button_style,
+            template,
+            raw_attributes,
+            overflow_strategy,
+            display_timestamp,
+            suppress_popup,
+            respect_focus_assist,
+            priority,
+            schema_version,
+            correlation_id,
+        } = other;
+
+        override_with(&mut self.header, header);
+        override_with(&mut self.text.0, &text.0);
+        override_with(&mut self.text.1, &text.1);
+        override_with(&mut self.text.2, &text.2);
+        self.images
+            .extend(images.iter().map(|(id, image)| (*id, image.clone())));
+        override_with(&mut self.progress, progress);
+        override_with(&mut self.tag, tag);
+        override_with(&mut self.group, group);
+        override_with(&mut self.remote_id, remote_id);
+        override_with(&mut self.expires_in, expires_in);
+        override_with(&mut self.scenario, scenario);
+        override_with(&mut self.launch, launch);
+        override_with(&mut self.duration, duration);
+        override_with(&mut self.audio, audio);
+        self.actions.extend(actions.iter().cloned());
+        override_with(&mut self.input, input);
+        self.selections.extend(selections.iter().cloned());
+        override_with(&mut self.use_button_style, use_button_style);
+        override_with(&mut self.template, template);
+        self.raw_attributes.extend(raw_attributes);
+        if *overflow_strategy != OverflowStrategy::default() {
+            self.overflow_strategy = *overflow_strategy;
+        }
+        override_with(&mut self.display_timestamp, display_timestamp);
+        self.suppress_popup |= suppress_popup;
+        self.respect_focus_assist |= respect_focus_assist;
+        if *priority != ToastPriority::default() {
+            self.priority = *priority;
+        }
+        override_with(&mut self.schema_version, schema_version);
+        override_with(&mut self.correlation_id, correlation_id);
+        self
+    }
+}
+
+/// Replace `value` with `other` if `other` is set.
+fn override_with<T: Clone>(value: &mut Option<T>, other: &Option<T>) {
+    if let Some(other) = other {
+        *value = Some(other.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use url::Url;
+
+    use super::*;
+    use crate::{Action, Image, Selection, Text};
+
+    fn image(name: &str) -> Image {
+        Image::new(
+            Url::parse("file:///C:/images/")
+                .unwrap()
+                .join(name)
+                .unwrap(),
+        )
+    }
+
+    fn text(toast: &Toast, index: usize) -> Option<&str> {
+        let text = [&toast.text.0, &toast.text.1, &toast.text.2][index];
+        text.as_ref().map(|text| &*text.content)
+    }
+
+    #[test]
+    fn set_properties_override_and_unset_ones_are_kept() {
+        let mut base = Toast::new();
+        base.text1("Base title")
+            .text3("Base attribution")
+            .group("base")
+            .launch("base");
+        let mut other = Toast::new();
+        other.text1(Text::new("Event title")).group("event");
+
+        base.merge(&other);
+        assert_eq!(text(&base, 0), Some("Event title"));
+        assert_eq!(text(&base, 1), None);
+        assert_eq!(text(&base, 2), Some("Base attribution"));
+        assert_eq!(base.group.as_deref(), Some("event"));
+        assert_eq!(base.launch.as_deref(), Some("base"));
+        assert_eq!(base.tag, None);
+    }
+
+    #[test]
+    fn images_are_replaced_by_id() {
+        let mut base = Toast::new();
+        base.image(1, image("logo.png")).image(2, image("base.png"));
+        let mut other = Toast::new();
+        other
+            .image(2, image("event.png"))
+            .image(3, image("extra.png"));
+
+        base.merge(&other);
+        let name = |id| {
+            base.images[&id]
+                .local_path()
+                .unwrap()
+                .file_name()
+                .unwrap()
+                .to_owned()
+        };
+        assert_eq!(base.images.len(), 3);
+        assert_eq!(name(1), "logo.png");
+        assert_eq!(name(2), "event.png");
+        assert_eq!(name(3), "extra.png");
+    }
+
+    #[test]
+    fn actions_and_selections_are_appended() {
+        let mut base = Toast::new();
+        base.action(Action::new("Open", "open", ""))
+            .selection(Selection::new("a", "A"));
+        let mut other = Toast::new();
+        other
+            .action(Action::new("Snooze", "snooze", ""))
+            .selection(Selection::new("b", "B"));
+
+        base.merge(&other);
+        let arguments: Vec<_> = base.actions.iter().map(Action::arguments).collect();
+        assert_eq!(arguments, ["open", "snooze"]);
+        let selections: Vec<_> = base.selections.iter().map(Selection::id).collect();
+        assert_eq!(selections, ["a", "b"]);
+    }
+
+    #[test]
+    fn raw_attributes_are_replaced_by_name() {
+        let mut base = Toast::new();
+        base.set_raw_attribute("hint-a", "base")
+            .set_raw_attribute("hint-b", "base");
+        let mut other = Toast::new();
+        other
+            .set_raw_attribute("hint-b", "event")
+            .set_raw_attribute("hint-c", "event");
+
+        base.merge(&other);
+        assert_eq!(base.raw_attribute("hint-a"), Some("base"));
+        assert_eq!(base.raw_attribute("hint-b"), Some("event"));
+        assert_eq!(base.raw_attribute("hint-c"), Some("event"));
+    }
+
+    #[test]
+    fn flags_are_on_if_on_in_either() {
+        let mut base = Toast::new();
+        base.suppress_popup(true);
+        let mut other = Toast::new();
+        other.respect_focus_assist(true);
+
+        base.merge(&other);
+        assert!(base.suppress_popup);
+        assert!(base.respect_focus_assist);
+
+        base.merge(&Toast::new());
+        assert!(base.suppress_popup);
+    }
+
+    #[test]
+    fn defaults_do_not_override() {
+        let mut base = Toast::new();
+        base.priority(ToastPriority::High)
+            .overflow_strategy(OverflowStrategy::MoveToBody);
+
+        base.merge(&Toast::new());
+        assert_eq!(base.priority, ToastPriority::High);
+        assert_eq!(base.overflow_strategy, OverflowStrategy::MoveToBody);
+
+        let mut other = Toast::new();
+        other.overflow_strategy(OverflowStrategy::Attach);
+        base.merge(&other);
+        assert_eq!(base.overflow_strategy, OverflowStrategy::Attach);
+    }
+}
@@ -0,0 +1,239 @@
+use crate::{
+    content::input::InputType, hash::fnv1a, Action, Header, Input, Text, Toast, ToastManager,
+};
+
+/// A toast for a chat message, for apps with several accounts and conversations.
+///
+/// The group, header and tag of the toast are derived from the account, conversation and
+/// message, so all toasts of a conversation are grouped under one header in the Action Center
+/// and can be removed together with [`ToastManager::mark_conversation_read`].
+/// The ids are stable across runs of the app, and short enough for Windows even for long
+/// account and conversation ids.
+///
+/// # Example
+/// ```no_run
+/// # use winrt_toast_reborn::{ConversationToast, ToastManager};
+/// let manager = ToastManager::new(ToastManager::POWERSHELL_AUM_ID);
+///
+/// let toast = ConversationToast::new("alice@example.com", "team-chat", "Bob", "Lunch?")
+///     .with_title("Team chat")
+///     .with_reply("Type a reply");
+/// manager.show(&toast.to_toast())?;
+///
+/// // Once the conversation was read in the app:
+/// manager.mark_conversation_read("alice@example.com", "team-chat")?;
+/// # Ok::<(), winrt_toast_reborn::WinToastError>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct ConversationToast {
+    account: String,
+    conversation_id: String,
+    sender: String,
+    message: String,
+    message_id: Option<String>,
+    title: Option<String>,
+    reply: Option<String>,
+}
+
+impl ConversationToast {
+    /// The id of the reply input added by [`ConversationToast::with_reply`].
+    pub const REPLY_INPUT_ID: &'static str = "reply";
+    /// The arguments of the send action added by [`ConversationToast::with_reply`].
+    pub const REPLY_ARGUMENTS: &'static str = "reply";
+
+    /// Create a toast for `message`, sent by `sender` in a conversation of `account`.
+    pub fn new(
+        account: impl Into<String>,
+        conversation_id: impl Into<String>,
+        sender: impl Into<String>,
+        message: impl Into<String>,
+    ) -> Self {
+        Self {
+            account: account.into(),
+            conversation_id: conversation_id.into(),
+            sender: sender.into(),
+            message: message.into(),
+            message_id: None,
+            title: None,
+            reply: None,
+        }
+    }
+
+    /// The id of the message, used for the tag of the toast.
+    ///
+    /// Without it, the tag is derived from the sender and message, so a message that is
+    /// repeated word for word replaces the earlier toast.
+    pub fn with_message_id(mut self, message_id: impl Into<String>) -> Self {
+        self.message_id = Some(message_id.into());
+        self
+    }
+
+    /// The title of the conversation, shown in the header. Defaults to the conversation id.
+    pub fn with_title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// Add a reply input with `placeholder`, and a send button bound to it.
+    ///
+    /// The reply is available under [`ConversationToast::REPLY_INPUT_ID`] when the toast is
+    /// activated with [`ConversationToast::REPLY_ARGUMENTS`].
+    pub fn with_reply(mut self, placeholder: impl Into<String>) -> Self {
+        self.reply = Some(placeholder.into());
+        self
+    }
+
+    /// The group of the toast, shared by all toasts of the conversation.
+    pub fn group(&self) -> String {
+        conversation_group(&self.account, &self.conversation_id)
+    }
+
+    /// The tag of the toast, unique within the conversation.
+    pub fn tag(&self) -> String {
+        match &self.message_id {
+            Some(message_id) => format!("msg-{:016x}", hash(&[message_id])),
+            None => format!("msg-{:016x}", hash(&[&self.sender, &self.message])),
+        }
+    }
+
+    /// Build the toast.
+    pub fn to_toast(&self) -> Toast {
+        let group = self.group();
+        let title = self.title.as_deref().unwrap_or(&self.conversation_id);
+
+        let mut toast = Toast::new();
+        toast
+            .header(Header::new(&group, title, &group))
+            .group(&group)
+            .tag(self.tag())
+            .text1(Text::new(&self.sender))
+            .text2(Text::new(&self.message));
+        if let Some(placeholder) = &self.reply {
+            toast.quick_reply_enter_submits(
+                Input::new(Self::REPLY_INPUT_ID, InputType::Text).with_placeholder(placeholder),
+                Action::new("Send", Self::REPLY_ARGUMENTS, ""),
+            );
+        }
+
+        toast
+    }
+}
+
+impl ToastManager {
+    /// Remove all toasts of a conversation, see [`ConversationToast`].
+    pub fn mark_conversation_read(
+        &self,
+        account: &str,
+        conversation_id: &str,
+    ) -> crate::Result<()> {
+        self.remove_group(&conversation_group(account, conversation_id))
+    }
+}
+
+/// The group of the toasts of a conversation.
+fn conversation_group(account: &str, conversation_id: &str) -> String {
+    format!("conv-{:016x}", hash(&[account, conversation_id]))
+}
+
+/// The hash of the strings `parts`, see [`fnv1a`].
+fn hash(parts: &[&str]) -> u64 {
+    fnv1a(parts.iter().map(|part| part.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(account: &str, conversation_id: &str) -> ConversationToast {
+        ConversationToast::new(account, conversation_id, "Bob", "Lunch?")
+    }
+
+    #[test]
+    fn ids_are_deterministic() {
+        let toast = message("alice@example.com", "team-chat");
+        assert_eq!(
+            toast.group(),
+            message("alice@example.com", "team-chat").group()
+        );
+        assert_eq!(toast.tag(), message("alice@example.com", "team-chat").tag());
+        assert_eq!(
+            toast.group(),
+            conversation_group("alice@example.com", "team-chat")
+        );
+    }
+
+    #[test]
+    fn conversations_of_different_accounts_have_different_groups() {
+        let groups = [
+            message("alice", "chat").group(),
+            message("bob", "chat").group(),
+            message("alice", "other").group(),
+            message("alicec", "hat").group(),
+        ];
+        for (i, group) in groups.iter().enumerate() {
+            assert!(!groups[i + 1..].contains(group), "{}", group);
+        }
+    }
+
+    #[test]
+    fn the_tag_comes_from_the_message_id_if_given() {
+        let toast = message("alice", "chat");
+        let repeated = ConversationToast::new("alice", "chat", "Bob", "Lunch?");
+        assert_eq!(toast.tag(), repeated.tag());
+        assert_ne!(
+            toast.tag(),
+            ConversationToast::new("alice", "chat", "Bob", "Dinner?").tag()
+        );
+
+        let first = message("alice", "chat").with_message_id("1");
+        let second = message("alice", "chat").with_message_id("2");
+        assert_ne!(first.tag(), second.tag());
+        assert_ne!(first.tag(), toast.tag());
+    }
+
+    #[test]
+    fn ids_fit_windows_limits_for_long_names() {
+        let long = "x".repeat(1000);
+        let toast = ConversationToast::new(&long, &long, &long, &long);
+        assert!(toast.group().len() <= crate::limits::MAX_GROUP_LEN);
+        assert!(toast.tag().len() <= crate::limits::MAX_TAG_LEN);
+        let toast = toast.with_message_id(&long);
+        assert!(toast.tag().len() <= crate::limits::MAX_TAG_LEN);
+    }
+
+    #[test]
+    fn the_toast_is_grouped_under_the_conversation_header() {
+        let conversation = message("alice", "chat").with_title("Team chat");
+        let toast = conversation.to_toast();
+        assert_eq!(toast.group, Some(conversation.group()));
+        assert_eq!(toast.tag, Some(conversation.tag()));
+        let header = format!("{:?}", toast.header.unwrap());
+        assert!(
+            header.contains(&format!("{:?}", conversation.group())),
+            "{}",
+            header
+        );
+        assert!(header.contains("\"Team chat\""), "{}", header);
+        assert!(toast.input.is_none() && toast.actions.is_empty());
+        assert!(conversation.to_toast().validate().is_ok());
+    }
+
+    #[test]
+    fn the_reply_button_is_bound_to_the_reply_input() {
+        let toast = message("alice", "chat")
+            .with_reply("Type a reply")
+            .to_toast();
+        let input = toast.input.as_ref().unwrap();
+        assert_eq!(input.id, ConversationToast::REPLY_INPUT_ID);
+        assert_eq!(toast.actions.len(), 1);
+        assert_eq!(
+            toast.actions[0].input_id(),
+            Some(ConversationToast::REPLY_INPUT_ID)
+        );
+        assert_eq!(
+            toast.actions[0].arguments(),
+            ConversationToast::REPLY_ARGUMENTS
+        );
+        assert!(toast.validation_issues().is_empty());
+    }
+}
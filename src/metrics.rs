@@ -0,0 +1,142 @@
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::Duration,
+};
+
+/// Counters of what a [`ToastManager`](crate::ToastManager) did, see
+/// [`ToastManager::metrics`](crate::ToastManager::metrics).
+///
+/// The counters are shared by a manager and its clones, and only ever increase.
+/// The timings are `None` until a toast was shown.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Metrics {
+    /// Input values dropped on activation because a toast had more inputs than allowed
+    /// by [`ToastManager::input_limits`](crate::ToastManager::input_limits).
+    pub dropped_input_values: u64,
+    /// Input values shortened on activation because they were longer than allowed
+    /// by [`ToastManager::input_limits`](crate::ToastManager::input_limits).
+    pub truncated_input_values: u64,
+    /// How long creating the notifier for the AUM_ID took, in milliseconds.
+    /// The notifier is created once and shared with the clones of the manager.
+    pub notifier_ms: Option<u64>,
+    /// How long preparing and rendering the last shown toast took, in milliseconds.
+    pub last_render_ms: Option<u64>,
+    /// How long Windows took to accept the last shown toast, in milliseconds.
+    pub last_show_ms: Option<u64>,
+    /// The 95th percentile of how long Windows took to accept the last
+    /// [`Metrics::SHOW_SAMPLES`] shown toasts, in milliseconds.
+    pub p95_show_ms: Option<u64>,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct Counters {
+    pub(crate) dropped_input_values: AtomicU64,
+    pub(crate) truncated_input_values: AtomicU64,
+    timings: Mutex<Timings>,
+}
+
+#[derive(Debug, Default)]
+struct Timings {
+    notifier_ms: Option<u64>,
+    last_render_ms: Option<u64>,
+    show_ms: VecDeque<u64>,
+}
+
+impl Metrics {
+    /// The number of shown toasts that [`Metrics::p95_show_ms`] is taken over.
+    pub const SHOW_SAMPLES: usize = 32;
+}
+
+impl Counters {
+    pub(crate) fn snapshot(&self) -> Metrics {
+        let timings = self.timings.lock().unwrap();
+        let mut show_ms: Vec<u64> = timings.show_ms.iter().copied().collect();
+        show_ms.sort_unstable();
+        let p95_show_ms = nearest_rank(&show_ms, 95);
+
+        Metrics {
+            dropped_input_values: self.dropped_input_values.load(Ordering::Relaxed),
+            truncated_input_values: self.truncated_input_values.load(Ordering::Relaxed),
+            notifier_ms: timings.notifier_ms,
+            last_render_ms: timings.last_render_ms,
+            last_show_ms: timings.show_ms.back().copied(),
+            p95_show_ms,
+        }
+    }
+
+    pub(crate) fn record_notifier(&self, elapsed: Duration) {
+        self.timings.lock().unwrap().notifier_ms = Some(millis(elapsed));
+    }
+
+    pub(crate) fn record_render(&self, elapsed: Duration) {
+        self.timings.lock().unwrap().last_render_ms = Some(millis(elapsed));
+    }
+
+    pub(crate) fn record_show(&self, elapsed: Duration) {
+        let mut timings = self.timings.lock().unwrap();
+        if timings.show_ms.len() == Metrics::SHOW_SAMPLES {
+            timings.show_ms.pop_front();
+        }
+        timings.show_ms.push_back(millis(elapsed));
+    }
+}
+
+/// The `percent` percentile of the sorted `values` by the nearest-rank method, which is the
+/// value at the rank `ceil(percent / 100 * n)`.
+fn nearest_rank(values: &[u64], percent: usize) -> Option<u64> {
+    let rank = (values.len() * percent).div_ceil(100);
+    values.get(rank.checked_sub(1)?).copied()
+}
+
+fn millis(duration: Duration) -> u64 {
+    duration.as_millis().try_into().unwrap_or(u64::MAX)
+}
+
+/// How many input values are read on activation, and how long each may be.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct InputLimits {
+    pub(crate) max_values: usize,
+    pub(crate) max_value_len: usize,
+}
+
+impl Default for InputLimits {
+    fn default() -> Self {
+        Self {
+            max_values: 64,
+            max_value_len: 16 * 1024,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nearest_rank_p95() {
+        assert_eq!(nearest_rank(&[], 95), None);
+        assert_eq!(nearest_rank(&[7], 95), Some(7));
+        let values: Vec<u64> = (0..32).collect();
+        assert_eq!(nearest_rank(&values, 95), Some(30));
+        let values: Vec<u64> = (1..=20).collect();
+        assert_eq!(nearest_rank(&values, 95), Some(19));
+        let values: Vec<u64> = (1..=100).collect();
+        assert_eq!(nearest_rank(&values, 95), Some(95));
+    }
+
+    #[test]
+    fn snapshot_keeps_the_last_samples() {
+        let counters = Counters::default();
+        for ms in 1..=(Metrics::SHOW_SAMPLES as u64 + 8) {
+            counters.record_show(Duration::from_millis(ms));
+        }
+        let metrics = counters.snapshot();
+        assert_eq!(metrics.last_show_ms, Some(Metrics::SHOW_SAMPLES as u64 + 8));
+        assert_eq!(metrics.p95_show_ms, Some(39));
+    }
+}
@@ -0,0 +1,148 @@
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::{Text, Toast};
+
+/// What to do with a title that is too long to be shown in full.
+///
+/// Windows shows at most about two lines of the title and ellipsizes the rest.
+/// A title counts as too long when it has more than [`OverflowStrategy::TITLE_LIMIT`] characters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverflowStrategy {
+    /// Leave the title as it is, so Windows ellipsizes it.
+    #[default]
+    Truncate,
+    /// Split the title at the last word boundary before the limit and move the rest
+    /// to the start of the body.
+    ///
+    /// Words are separated by whitespace. A title without whitespace before the limit,
+    /// such as Chinese or Japanese text, is split at the limit, without splitting
+    /// a grapheme cluster such as an emoji sequence.
+    MoveToBody,
+    /// Leave the title as it is, and attach the full title to the notification,
+    /// where it is available as [`ActivatedAction::full_title`](crate::ActivatedAction::full_title)
+    /// when the toast is activated.
+    Attach,
+}
+
+impl OverflowStrategy {
+    /// The number of characters of a title that Windows shows in full, approximately.
+    pub const TITLE_LIMIT: usize = 60;
+}
+
+/// The key of the notification data entry holding the full title, see [`OverflowStrategy::Attach`].
+pub(crate) const FULL_TITLE_KEY: &str = "winrt-toast-reborn.full-title";
+
+impl Toast {
+    /// Set what to do with a title that is too long to be shown in full.
+    ///
+    /// The default is [`OverflowStrategy::Truncate`].
+    pub fn overflow_strategy(&mut self, strategy: OverflowStrategy) -> &mut Toast {
+        self.overflow_strategy = strategy;
+        self
+    }
+
+    /// The full title, if it is too long and should be attached to the notification.
+    pub(crate) fn attached_title(&self) -> Option<&str> {
+        if self.overflow_strategy != OverflowStrategy::Attach {
+            return None;
+        }
+        let title = &self.text.0.as_ref()?.content;
+        (title.chars().count() > OverflowStrategy::TITLE_LIMIT).then_some(title.as_ref())
+    }
+
+    /// A copy of this toast with an over-long title moved to the body, if it has one
+    /// and [`OverflowStrategy::MoveToBody`] is set.
+    pub(crate) fn with_title_moved_to_body(&self) -> Option<Toast> {
+        if self.overflow_strategy != OverflowStrategy::MoveToBody {
+            return None;
+        }
+        let title = self.text.0.as_ref()?;
+        let (head, tail) = split_at_word(&title.content, OverflowStrategy::TITLE_LIMIT)?;
+
+        let mut toast = self.clone();
+        toast.overflow_strategy = OverflowStrategy::Truncate;
+        if let Some(title) = &mut toast.text.0 {
+            title.content = head.to_string().into();
+        }
+        match &mut toast.text.1 {
+            Some(body) => body.content = format!("{} {}", tail, body.content).into(),
+            None => toast.text.1 = Some(Text::new(tail)),
+        }
+
+        Some(toast)
+    }
+}
+
+/// Split `s` into a head of at most `limit` characters and the rest.
+///
+/// The split is made at the last whitespace that keeps the head within the limit, and the
+/// whitespace around it is dropped. Without such whitespace the split is made at the last
+/// grapheme cluster boundary within `limit` characters, so that emoji sequences and letters
+/// with combining marks are not torn apart; a first cluster longer than the limit is kept
+/// whole. Returns `None` if `s` is within the limit.
+fn split_at_word(s: &str, limit: usize) -> Option<(&str, &str)> {
+    let (end, _) = s.char_indices().nth(limit)?;
+    let mut boundaries = s.grapheme_indices(true).map(|(i, _)| i).skip(1);
+    let end = match boundaries.clone().take_while(|&i| i <= end).last() {
+        Some(i) => i,
+        None => boundaries.next().unwrap_or(s.len()),
+    };
+
+    let boundary = s[..end]
+        .char_indices()
+        .chain(s[end..].chars().next().map(|c| (end, c)))
+        .filter(|(_, c)| c.is_whitespace())
+        .map(|(i, _)| i)
+        .rfind(|i| !s[..*i].trim_end().is_empty());
+
+    let (head, tail) = match boundary {
+        Some(i) => (s[..i].trim_end(), s[i..].trim_start()),
+        None => (&s[..end], &s[end..]),
+    };
+    (!tail.is_empty()).then_some((head, tail))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_at_the_last_word_within_the_limit() {
+        assert_eq!(
+            split_at_word("one two three", 9),
+            Some(("one two", "three"))
+        );
+        assert_eq!(
+            split_at_word("one two three", 7),
+            Some(("one two", "three"))
+        );
+        assert_eq!(split_at_word("one two", 7), None);
+    }
+
+    #[test]
+    fn splits_text_without_spaces_at_the_limit() {
+        assert_eq!(split_at_word("你好世界", 2), Some(("你好", "世界")));
+    }
+
+    #[test]
+    fn keeps_emoji_sequences_whole() {
+        let family = "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}";
+        let s = format!("ab{family}{family}");
+        // The limit ends inside the first family, which moves to the tail as a whole.
+        assert_eq!(split_at_word(&s, 4), Some(("ab", &s[2..])));
+        assert_eq!(split_at_word(&s, 7), Some((&s[..2 + family.len()], family)));
+    }
+
+    #[test]
+    fn keeps_combining_marks_with_their_letter() {
+        let s = "e\u{301}e\u{301}e\u{301}";
+        assert_eq!(split_at_word(s, 3), Some(("e\u{301}", "e\u{301}e\u{301}")));
+    }
+
+    #[test]
+    fn keeps_a_first_cluster_longer_than_the_limit() {
+        let family = "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}";
+        let s = format!("{family}x");
+        assert_eq!(split_at_word(&s, 2), Some((family, "x")));
+    }
+}
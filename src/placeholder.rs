@@ -0,0 +1,175 @@
+use crate::{
+    Image, NotificationUpdateResult, Result, Toast, ToastData, ToastManager, ToastTemplate,
+};
+
+/// A toast shown with a placeholder image, see [`ToastManager::show_with_placeholder`].
+///
+/// Windows has no loading state for images, and data-bound placeholders like `{title}` only
+/// work in texts and progress bars, not in image sources. So the real image is shown by
+/// replacing the toast: [`PendingImage::resolve`] shows it again with the same tag and group,
+/// the real image in place of the placeholder, and the latest values of its placeholders.
+#[derive(Clone)]
+pub struct PendingImage {
+    manager: ToastManager,
+    toast: Toast,
+    id: u8,
+    data: ToastData,
+}
+
+impl std::fmt::Debug for PendingImage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PendingImage")
+            .field("toast", &self.toast)
+            .field("id", &self.id)
+            .field("data", &self.data)
+            .finish_non_exhaustive()
+    }
+}
+
+impl PendingImage {
+    /// `toast`, which must have a tag, waiting for its image `id`, with `data` for its placeholders.
+    pub(crate) fn new(manager: ToastManager, toast: Toast, id: u8, data: ToastData) -> Self {
+        Self {
+            manager,
+            toast,
+            id,
+            data,
+        }
+    }
+
+    /// The tag of the toast, generated if the toast had none.
+    pub fn tag(&self) -> &str {
+        self.toast.tag.as_deref().unwrap_or_default()
+    }
+
+    /// The values the placeholders of the toast have now.
+    pub fn data(&self) -> &ToastData {
+        &self.data
+    }
+
+    /// Update the data-bound placeholders of the toast while its image loads, e.g. a progress
+    /// bar of the download, see [`ToastManager::update`].
+    ///
+    /// The values are kept for [`PendingImage::resolve`], and each update gets a higher
+    /// sequence number, so an update that arrives late can't undo a newer one.
+    pub fn update(&mut self, data: &ToastData) -> Result<NotificationUpdateResult> {
+        let update = merge(&mut self.data, data);
+        self.manager
+            .update(self.tag(), self.toast.group.as_deref(), &update)
+    }
+
+    /// Replace the placeholder with `image`, which should be a local image, e.g. from
+    /// [`ToastManager::image_from_bytes`].
+    ///
+    /// This shows the toast again with its tag and group, so it replaces the toast with the
+    /// placeholder, in the Action Center and, while it is on screen, in its popup. The
+    /// replacement is silent, so the sound of the toast isn't played twice.
+    pub fn resolve(self, image: Image) -> Result<()> {
+        let toast = resolved(&self.toast, self.id, image);
+        self.manager.validate(&toast)?;
+        self.manager
+            .show_template(&ToastTemplate::new(&toast)?, &self.data)
+    }
+}
+
+/// Merge `data` into `values`, and return it with the next sequence number of `values`.
+fn merge(values: &mut ToastData, data: &ToastData) -> ToastData {
+    for (key, value) in &data.values {
+        values.set(key.clone(), value.clone());
+    }
+    values.increment_sequence_number();
+
+    let mut update = data.clone();
+    if let Some(sequence_number) = values.sequence_number() {
+        update.set_sequence_number(sequence_number);
+    }
+    update
+}
+
+/// `toast` with `image` in place of its placeholder `id`, silenced for the replacement.
+fn resolved(toast: &Toast, id: u8, image: Image) -> Toast {
+    let mut toast = with_image(toast, id, image);
+    toast.silent();
+    toast
+}
+
+/// `toast` with its image `id` set to `image`.
+pub(crate) fn with_image(toast: &Toast, id: u8, image: Image) -> Toast {
+    let mut toast = toast.clone();
+    toast.image(id, image);
+    toast
+}
+
+#[cfg(test)]
+mod tests {
+    use url::Url;
+
+    use super::*;
+    use crate::content::audio::Sound;
+    use crate::Audio;
+
+    fn image(name: &str) -> Image {
+        Image::new(Url::parse(&format!("file:///C:/images/{}", name)).unwrap())
+    }
+
+    fn image_name(toast: &Toast, id: u8) -> String {
+        let path = toast.images[&id].local_path().unwrap();
+        path.file_name().unwrap().to_string_lossy().into_owned()
+    }
+
+    fn now_playing() -> Toast {
+        let mut toast = Toast::new();
+        toast
+            .text1("Now playing")
+            .text2("{artist}")
+            .audio(Audio::new(Sound::IM))
+            .image(1, image("artwork.png"))
+            .tag("now-playing")
+            .group("music");
+        toast
+    }
+
+    #[test]
+    fn the_placeholder_replaces_the_image() {
+        let toast = now_playing();
+        let shown = with_image(&toast, 1, image("placeholder.png"));
+        assert_eq!(image_name(&shown, 1), "placeholder.png");
+        assert_eq!(shown.tag, toast.tag);
+        assert_eq!(shown.group, toast.group);
+        assert_eq!(shown.text_elements().count(), 2);
+    }
+
+    #[test]
+    fn the_resolved_toast_replaces_the_placeholder_silently() {
+        let resolved = resolved(&now_playing(), 1, image("cover.png"));
+        assert_eq!(image_name(&resolved, 1), "cover.png");
+        assert_eq!(resolved.tag.as_deref(), Some("now-playing"));
+        assert_eq!(resolved.group.as_deref(), Some("music"));
+        assert_eq!(
+            format!("{:?}", resolved.audio),
+            format!("{:?}", Some(Audio::new(Sound::None)))
+        );
+    }
+
+    #[test]
+    fn updates_are_kept_for_the_resolved_toast() {
+        let mut values = ToastData::new();
+        values.set("artist", "Loading...");
+
+        let mut data = ToastData::new();
+        data.set("artist", "Nina Simone");
+        let first = merge(&mut values, &data);
+        assert_eq!(first.get("artist"), Some("Nina Simone"));
+        assert_eq!(first.sequence_number(), Some(1));
+
+        let mut data = ToastData::new();
+        data.set("album", "Pastel Blues");
+        let second = merge(&mut values, &data);
+        assert_eq!(second.get("artist"), None);
+        assert_eq!(second.sequence_number(), Some(2));
+
+        assert_eq!(values.get("artist"), Some("Nina Simone"));
+        assert_eq!(values.get("album"), Some("Pastel Blues"));
+        assert_eq!(values.sequence_number(), Some(2));
+    }
+}
@@ -0,0 +1,101 @@
+use windows::Data::Xml::Dom::{IXmlNode, NodeType};
+
+use crate::{xml, Toast};
+
+impl Toast {
+    /// The XML of this toast after a round trip through an `XmlDocument`, indented for reading.
+    ///
+    /// This serializes the toast like [`Toast::to_xml`] and loads the result into a new
+    /// document, so output that Windows can't parse, e.g. from raw attributes, fails here
+    /// with [`WinToastError::InvalidXml`](crate::WinToastError::InvalidXml) instead of when
+    /// the toast is shown.
+    /// Nothing is shown, and the adjustments of a [`ToastManager`](crate::ToastManager),
+    /// such as its quiet hours, are not applied.
+    pub fn preview(&self) -> crate::Result<String> {
+        let doc = xml::load_document(&self.to_xml()?)?;
+
+        let mut out = String::new();
+        for node in &doc.ChildNodes()? {
+            write_indented(&node, 0, &mut out)?;
+        }
+        out.truncate(out.trim_end().len());
+
+        Ok(out)
+    }
+}
+
+/// Write `node` to `out`, with each element that has child elements spread over several lines.
+fn write_indented(node: &IXmlNode, depth: usize, out: &mut String) -> crate::Result<()> {
+    let indent = "  ".repeat(depth);
+    let children = node.ChildNodes()?;
+    let has_elements = (&children)
+        .into_iter()
+        .any(|child| matches!(child.NodeType(), Ok(NodeType::ElementNode)));
+
+    if node.NodeType()? != NodeType::ElementNode || !has_elements {
+        let xml = node.GetXml()?.to_string();
+        if !xml.trim().is_empty() {
+            out.push_str(&indent);
+            out.push_str(xml.trim());
+            out.push('\n');
+        }
+        return Ok(());
+    }
+
+    // The element without its children serializes as `<name attributes/>`.
+    let start = node.CloneNode(false)?.GetXml()?.to_string();
+    let start = match start.strip_suffix("/>") {
+        Some(start) => format!("{}>", start.trim_end()),
+        None => start,
+    };
+    out.push_str(&indent);
+    out.push_str(&start);
+    out.push('\n');
+    for child in &children {
+        write_indented(&child, depth + 1, out)?;
+    }
+    out.push_str(&format!("{}</{}>\n", indent, node.NodeName()?));
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Text, Toast};
+
+    fn toast() -> Toast {
+        let mut toast = Toast::new();
+        toast.text1("Hello").text2("World");
+        toast
+    }
+
+    #[test]
+    fn elements_with_children_are_spread_over_lines() {
+        let preview = toast().preview().unwrap();
+        let lines: Vec<_> = preview.lines().collect();
+        assert!(lines[0].starts_with("<toast"), "{}", preview);
+        assert_eq!(lines[1], "  <visual>");
+        assert!(lines[2].starts_with("    <binding "), "{}", preview);
+        assert!(lines[3].starts_with("      <text") && lines[3].ends_with(">Hello</text>"));
+        assert!(lines[4].starts_with("      <text") && lines[4].ends_with(">World</text>"));
+        assert_eq!(lines[5..], ["    </binding>", "  </visual>", "</toast>"]);
+    }
+
+    #[test]
+    fn the_preview_has_the_xml_of_the_toast() {
+        let toast = toast();
+        let preview: String = toast.preview().unwrap().lines().map(str::trim).collect();
+        assert_eq!(preview, toast.to_xml().unwrap());
+    }
+
+    #[test]
+    fn malformed_raw_attributes_are_caught() {
+        let mut toast = toast();
+        toast.text1(Text::new("Hello").with_raw_attribute("bad name", "x"));
+        assert!(toast.preview().is_err());
+
+        let mut toast = self::toast();
+        toast.set_raw_attribute("a<b", "x");
+        assert!(toast.preview().is_err());
+    }
+}
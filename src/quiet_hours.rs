@@ -0,0 +1,123 @@
+use windows::Globalization::Calendar;
+
+use crate::{hs, WinToastError};
+
+/// A daily window of local time during which toasts are shown without sound.
+///
+/// The window is evaluated against the local wall clock each time a toast is shown,
+/// so it follows daylight saving time transitions: when clocks jump forward the skipped
+/// minutes simply never occur, and when they fall back the repeated hour is quiet both times.
+///
+/// A window whose start is later than its end crosses midnight, e.g. `22:00–07:00`.
+/// A window whose start equals its end is empty.
+///
+/// # Example
+/// ```rust
+/// # use winrt_toast_reborn::QuietHours;
+/// let quiet = QuietHours::new((22, 0), (7, 0)).unwrap();
+/// assert!(quiet.contains(23, 30));
+/// assert!(quiet.contains(6, 59));
+/// assert!(!quiet.contains(7, 0));
+/// assert!(!quiet.contains(12, 0));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuietHours {
+    start: u16,
+    end: u16,
+}
+
+impl QuietHours {
+    /// Create a new window from `start` (inclusive) to `end` (exclusive),
+    /// both given as `(hour, minute)` in 24-hour local time.
+    ///
+    /// Returns [`WinToastError::InvalidTimeOfDay`] if either time is out of range.
+    pub fn new(start: (u8, u8), end: (u8, u8)) -> crate::Result<Self> {
+        Ok(Self {
+            start: minute_of_day(start)?,
+            end: minute_of_day(end)?,
+        })
+    }
+
+    /// Whether the given local time of day falls within this window.
+    pub fn contains(&self, hour: u8, minute: u8) -> bool {
+        let t = hour as u16 * 60 + minute as u16;
+        if self.start <= self.end {
+            self.start <= t && t < self.end
+        } else {
+            t >= self.start || t < self.end
+        }
+    }
+
+    /// Whether the current local time falls within this window.
+    ///
+    /// Returns [`WinToastError::TimeConversion`] if the local time can't be read.
+    pub(crate) fn is_active_now(&self) -> crate::Result<bool> {
+        let (hour, minute) = local_time_of_day().map_err(|_| WinToastError::TimeConversion)?;
+        Ok(self.contains(hour, minute))
+    }
+}
+
+/// The current local time of day as hour and minute, read from the system calendar.
+fn local_time_of_day() -> windows::core::Result<(u8, u8)> {
+    let now = Calendar::new()?;
+    now.ChangeClock(&hs("24HourClock"))?;
+    Ok((now.Hour()? as u8, now.Minute()? as u8))
+}
+
+fn minute_of_day((hour, minute): (u8, u8)) -> crate::Result<u16> {
+    if hour > 23 || minute > 59 {
+        return Err(WinToastError::InvalidTimeOfDay(hour, minute));
+    }
+    Ok(hour as u16 * 60 + minute as u16)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn window_within_a_day() {
+        let quiet = QuietHours::new((12, 30), (14, 0)).unwrap();
+        assert!(!quiet.contains(12, 29));
+        assert!(quiet.contains(12, 30));
+        assert!(quiet.contains(13, 59));
+        assert!(!quiet.contains(14, 0));
+    }
+
+    #[test]
+    fn window_across_midnight() {
+        let quiet = QuietHours::new((22, 0), (7, 0)).unwrap();
+        assert!(!quiet.contains(21, 59));
+        assert!(quiet.contains(22, 0));
+        assert!(quiet.contains(0, 0));
+        assert!(quiet.contains(6, 59));
+        assert!(!quiet.contains(7, 0));
+    }
+
+    #[test]
+    fn window_from_midnight() {
+        let quiet = QuietHours::new((0, 0), (6, 0)).unwrap();
+        assert!(quiet.contains(0, 0));
+        assert!(!quiet.contains(23, 59));
+    }
+
+    #[test]
+    fn equal_start_and_end_is_empty() {
+        let quiet = QuietHours::new((8, 0), (8, 0)).unwrap();
+        assert!(!quiet.contains(8, 0));
+        assert!(!quiet.contains(20, 0));
+    }
+
+    #[test]
+    fn invalid_times_are_rejected() {
+        assert!(matches!(
+            QuietHours::new((24, 0), (7, 0)),
+            Err(WinToastError::InvalidTimeOfDay(24, 0))
+        ));
+        assert!(matches!(
+            QuietHours::new((22, 0), (7, 60)),
+            Err(WinToastError::InvalidTimeOfDay(7, 60))
+        ));
+        assert!(QuietHours::new((23, 59), (0, 0)).is_ok());
+    }
+}
@@ -1,31 +1,93 @@
-use std::{ffi::OsStr, os::windows::prelude::*, path::Path, ptr::null_mut};
+use std::{
+    ffi::{OsStr, OsString},
+    os::windows::prelude::*,
+    path::{Path, PathBuf},
+    ptr::null_mut,
+};
 
 use windows::{
-    core::{HSTRING, PCWSTR},
+    core::{HSTRING, PCWSTR, PWSTR},
     Win32::{
-        Foundation::CloseHandle,
-        Storage::FileSystem::{CommitTransaction, CreateTransaction},
+        Foundation::{
+            CloseHandle, ERROR_FILE_NOT_FOUND, ERROR_INSUFFICIENT_BUFFER, ERROR_NO_MORE_ITEMS,
+            ERROR_SUCCESS, WIN32_ERROR,
+        },
+        Storage::{
+            FileSystem::{CommitTransaction, CreateTransaction},
+            Packaging::Appx::GetCurrentPackageFullName,
+        },
         System::Registry::{
-            RegCreateKeyTransactedW, RegDeleteValueW, RegSetValueExW, HKEY, HKEY_CURRENT_USER,
-            KEY_ALL_ACCESS, REG_OPTION_NON_VOLATILE, REG_SZ,
+            RegCloseKey, RegCreateKeyTransactedW, RegDeleteTreeW, RegDeleteValueW, RegEnumKeyExW,
+            RegGetValueW, RegOpenKeyExW, RegSetValueExW, HKEY, HKEY_CURRENT_USER, KEY_ALL_ACCESS,
+            KEY_READ, REG_CREATED_NEW_KEY, REG_CREATE_KEY_DISPOSITION, REG_OPTION_NON_VOLATILE,
+            REG_SZ, RRF_RT_REG_SZ,
         },
     },
 };
 
 use crate::WinToastError;
 
+/// What [`register`] changed in the registry.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct RegistrationResult {
+    /// The key of the AUM_ID didn't exist and was created.
+    pub created: bool,
+    /// `IconUri` was written.
+    pub icon_set: bool,
+    /// `IconUri` existed and was removed because no icon was given.
+    pub icon_removed: bool,
+    /// Nothing was written because the app has package identity, see [`has_package_identity`].
+    pub packaged: bool,
+}
+
+/// What [`unregister`] removed from the registry.
+///
+/// A `false` field means that the key was absent.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct UnregistrationResult {
+    /// The key of the AUM_ID existed and was deleted.
+    pub app_key_deleted: bool,
+    /// The COM class of the `CustomActivator` of the AUM_ID existed and was deleted,
+    /// see [`register_activator`](crate::register_activator).
+    pub activator_key_deleted: bool,
+    /// Nothing was removed because the app has package identity, see [`has_package_identity`].
+    pub packaged: bool,
+}
+
 /// Register the application to Windows registry.
 ///
 /// `icon_path` should be an absolute path to the icon file, otherwise [`WinToastError::InvalidPath`] will be returned.
+/// The AUM_ID is checked like in [`ToastManager::try_new`](crate::ToastManager::try_new).
+///
+/// `display_name` and `icon_path` are written as UTF-16 without a detour through `str`,
+/// so names and paths that are not valid Unicode are stored exactly as given.
 ///
 /// For more information on AUM_ID and registration, see this
 /// [Windows documentation](https://docs.microsoft.com/en-us/windows/apps/design/shell/tiles-and-notifications/send-local-toast-desktop-cpp-wrl#step-5-register-with-notification-platform).
-pub fn register(aum_id: &str, display_name: &str, icon_path: Option<&Path>) -> crate::Result<()> {
-    let registry_path = HSTRING::from(format!("SOFTWARE\\Classes\\AppUserModelId\\{}", aum_id));
+///
+/// Apps with package identity get their AUM_ID, display name and icon from the package
+/// manifest, and Windows ignores the registry for them. For such apps this does nothing
+/// and returns a [`RegistrationResult`] with `packaged` set, see [`has_package_identity`].
+pub fn register(
+    aum_id: &str,
+    display_name: impl AsRef<OsStr>,
+    icon_path: Option<&Path>,
+) -> crate::Result<RegistrationResult> {
+    crate::aumid::validate_aum_id(aum_id)?;
+    if has_package_identity() {
+        return Ok(RegistrationResult {
+            packaged: true,
+            ..Default::default()
+        });
+    }
+    let mut result = RegistrationResult::default();
+    let registry_path = HSTRING::from(app_key(aum_id));
     let display_name = to_utf16(display_name);
     let icon_path = if let Some(path) = icon_path {
         if !path.is_absolute() {
-            return Err(WinToastError::InvalidPath);
+            return Err(WinToastError::InvalidPath(path.to_path_buf()));
         }
         Some(to_utf16(path))
     } else {
@@ -41,6 +103,7 @@ pub fn register(aum_id: &str, display_name: &str, icon_path: Option<&Path>) -> c
         }
 
         let mut new_hkey = HKEY::default();
+        let mut disposition = REG_CREATE_KEY_DISPOSITION::default();
         RegCreateKeyTransactedW(
             HKEY_CURRENT_USER,
             &registry_path,
@@ -50,12 +113,13 @@ pub fn register(aum_id: &str, display_name: &str, icon_path: Option<&Path>) -> c
             KEY_ALL_ACCESS,
             None,
             &mut new_hkey,
-            None,
+            Some(&mut disposition),
             transaction,
             None,
         )
         .ok()?;
         assert!(!new_hkey.is_invalid());
+        result.created = disposition == REG_CREATED_NEW_KEY;
 
         RegSetValueExW(
             new_hkey,
@@ -69,16 +133,294 @@ pub fn register(aum_id: &str, display_name: &str, icon_path: Option<&Path>) -> c
         let icon_uri_name = HSTRING::from("IconUri");
         if let Some(icon_path) = icon_path {
             RegSetValueExW(new_hkey, &icon_uri_name, 0, REG_SZ, Some(&icon_path)).ok()?;
+            result.icon_set = true;
         } else {
-            RegDeleteValueW(new_hkey, &icon_uri_name).ok()?
+            result.icon_removed = found(RegDeleteValueW(new_hkey, &icon_uri_name))?;
         }
 
         CommitTransaction(transaction).ok();
     }
 
+    Ok(result)
+}
+
+/// Remove the registration of `aum_id` from Windows registry.
+///
+/// This deletes the key written by [`register`] and, if the AUM_ID has a `CustomActivator`,
+/// the COM class written by [`register_activator`](crate::register_activator).
+/// Keys that are absent are not an error, the [`UnregistrationResult`] tells which keys
+/// were actually deleted.
+///
+/// Like [`register`], this does nothing for apps with package identity.
+pub fn unregister(aum_id: &str) -> crate::Result<UnregistrationResult> {
+    if has_package_identity() {
+        return Ok(UnregistrationResult {
+            packaged: true,
+            ..Default::default()
+        });
+    }
+    let app_path = HSTRING::from(app_key(aum_id));
+
+    let mut result = UnregistrationResult::default();
+    if let Some(clsid) = custom_activator(&app_path)? {
+        let class_path = HSTRING::from(class_key(&clsid));
+        result.activator_key_deleted =
+            found(unsafe { RegDeleteTreeW(HKEY_CURRENT_USER, &class_path) })?;
+    }
+    result.app_key_deleted = found(unsafe { RegDeleteTreeW(HKEY_CURRENT_USER, &app_path) })?;
+
+    Ok(result)
+}
+
+/// An AUM_ID registered for the current user, as listed by [`list_registered`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct RegistrationInfo {
+    /// The AUM_ID, the name of its registry key.
+    pub aum_id: String,
+    /// The `DisplayName` value, if set.
+    pub display_name: Option<OsString>,
+    /// The `IconUri` value, if set.
+    pub icon_path: Option<PathBuf>,
+    /// The CLSID in the `CustomActivator` value, if set by
+    /// [`register_activator`](crate::register_activator).
+    pub custom_activator: Option<String>,
+}
+
+/// List the AUM_IDs registered for the current user, optionally only those starting with `prefix`.
+///
+/// This enumerates the keys under `HKEY_CURRENT_USER\SOFTWARE\Classes\AppUserModelId`,
+/// which holds the registrations written by [`register`] and by other apps and installers
+/// alike. Windows doesn't record who wrote a key, so the only way to tell the
+/// registrations of an app apart is a naming convention, such as a common prefix.
+/// Keys whose names are not valid Unicode are skipped.
+pub fn list_registered(prefix: Option<&str>) -> crate::Result<Vec<RegistrationInfo>> {
+    let root_path = HSTRING::from(APPS_KEY);
+    let mut root = HKEY::default();
+    let error = unsafe { RegOpenKeyExW(HKEY_CURRENT_USER, &root_path, 0, KEY_READ, &mut root) };
+    if !found(error)? {
+        return Ok(Vec::new());
+    }
+    scopeguard::defer! {
+        let _ = unsafe { RegCloseKey(root) };
+    }
+
+    let mut registrations = Vec::new();
+    // Key names are at most 255 characters long.
+    let mut name = [0u16; 256];
+    for index in 0.. {
+        let mut len = name.len() as u32;
+        let error = unsafe {
+            RegEnumKeyExW(
+                root,
+                index,
+                PWSTR(name.as_mut_ptr()),
+                &mut len,
+                None,
+                PWSTR::null(),
+                None,
+                None,
+            )
+        };
+        if error == ERROR_NO_MORE_ITEMS {
+            break;
+        }
+        found(error)?;
+
+        let Some(aum_id) = listed_aum_id(&name[..len as usize], prefix) else {
+            continue;
+        };
+        let app_path = HSTRING::from(app_key(&aum_id));
+        registrations.push(RegistrationInfo {
+            display_name: read_string(&app_path, "DisplayName")?,
+            icon_path: read_string(&app_path, "IconUri")?.map(PathBuf::from),
+            custom_activator: custom_activator(&app_path)?,
+            aum_id,
+        });
+    }
+
+    Ok(registrations)
+}
+
+/// The AUM_ID of the key `name`, if it is valid Unicode and starts with `prefix`.
+fn listed_aum_id(name: &[u16], prefix: Option<&str>) -> Option<String> {
+    let aum_id = String::from_utf16(name).ok()?;
+    if prefix.is_some_and(|prefix| !aum_id.starts_with(prefix)) {
+        return None;
+    }
+    Some(aum_id)
+}
+
+/// Whether `aum_id` is registered with [`register`], `None` if that can't be told,
+/// see [`ShowDiagnostics::registered`](crate::ShowDiagnostics::registered).
+pub(crate) fn is_registered(aum_id: &str) -> Option<bool> {
+    if has_package_identity() {
+        return None;
+    }
+    let app_path = HSTRING::from(app_key(aum_id));
+    read_string(&app_path, "DisplayName")
+        .ok()
+        .map(|name| name.is_some())
+}
+
+/// Read the `CustomActivator` value of the AUM_ID key at `app_path`.
+fn custom_activator(app_path: &HSTRING) -> crate::Result<Option<String>> {
+    Ok(read_string(app_path, "CustomActivator")?.map(|clsid| clsid.to_string_lossy().into_owned()))
+}
+
+/// Read the string value `name` of the key at `path` under `HKEY_CURRENT_USER`.
+fn read_string(path: &HSTRING, name: &str) -> crate::Result<Option<OsString>> {
+    let name = HSTRING::from(name);
+    let read = |buffer: Option<&mut [u16]>, len: &mut u32| unsafe {
+        RegGetValueW(
+            HKEY_CURRENT_USER,
+            path,
+            &name,
+            RRF_RT_REG_SZ,
+            None,
+            buffer.map(|buffer| buffer.as_mut_ptr().cast()),
+            Some(len),
+        )
+    };
+
+    // The first call asks for the size in bytes, including the terminating null.
+    let mut len = 0;
+    if !found(read(None, &mut len))? {
+        return Ok(None);
+    }
+    let mut buffer = vec![0u16; (len as usize).div_ceil(2)];
+    if !found(read(Some(&mut buffer), &mut len))? {
+        return Ok(None);
+    }
+    buffer.truncate((len as usize / 2).saturating_sub(1));
+    Ok(Some(OsString::from_wide(&buffer)))
+}
+
+/// The key of the AUM_IDs registered for the current user, under `HKEY_CURRENT_USER`.
+const APPS_KEY: &str = "SOFTWARE\\Classes\\AppUserModelId";
+
+/// The key of the registration of `aum_id`, under `HKEY_CURRENT_USER`.
+fn app_key(aum_id: &str) -> String {
+    format!("{}\\{}", APPS_KEY, aum_id)
+}
+
+/// The key of the COM class `clsid`, in braces, under `HKEY_CURRENT_USER`.
+fn class_key(clsid: &str) -> String {
+    format!("SOFTWARE\\Classes\\CLSID\\{}", clsid)
+}
+
+/// Whether a registry call found what it was asked for, treating anything but success
+/// and `ERROR_FILE_NOT_FOUND` as an error.
+fn found(error: WIN32_ERROR) -> crate::Result<bool> {
+    match error {
+        ERROR_SUCCESS => Ok(true),
+        ERROR_FILE_NOT_FOUND => Ok(false),
+        error => Err(windows::core::Error::from(error.to_hresult()).into()),
+    }
+}
+
+/// Whether this process runs with package identity, e.g. as an MSIX or sparse package.
+///
+/// This asks Windows for the full name of the package of the current process with
+/// `GetCurrentPackageFullName`, which fails with `APPMODEL_ERROR_NO_PACKAGE` for
+/// unpackaged processes. Any other failure is taken as no package identity as well,
+/// so [`register`] doesn't skip the registration on an unexpected error.
+pub fn has_package_identity() -> bool {
+    let mut len = 0;
+    let result = unsafe { GetCurrentPackageFullName(&mut len, PWSTR::null()) };
+    matches!(result, ERROR_INSUFFICIENT_BUFFER | ERROR_SUCCESS)
+}
+
+/// Register `exe_path` as the COM server that Windows starts to activate toasts of `aum_id`.
+///
+/// This sets the `CustomActivator` of the AUM_ID to `clsid` and registers the class for the
+/// current user, so that Windows launches `exe_path` with the
+/// [`ComActivator::LAUNCH_ARG`](crate::ComActivator::LAUNCH_ARG) argument when a toast is
+/// activated after the app exited. The app then handles the activation with a
+/// [`ComActivator`](crate::ComActivator) registered under the same `clsid`.
+///
+/// Call [`register`] first, `exe_path` must be absolute, otherwise
+/// [`WinToastError::InvalidPath`] will be returned.
+///
+/// Like [`register`], this does nothing for apps with package identity, which declare
+/// their activator in the package manifest instead.
+#[cfg(feature = "activator")]
+pub fn register_activator(aum_id: &str, clsid: u128, exe_path: &Path) -> crate::Result<()> {
+    if !exe_path.is_absolute() {
+        return Err(WinToastError::InvalidPath(exe_path.to_path_buf()));
+    }
+    if has_package_identity() {
+        return Ok(());
+    }
+    let clsid = format!("{{{:?}}}", windows::core::GUID::from_u128(clsid));
+    let app_path = HSTRING::from(app_key(aum_id));
+    let server_path = HSTRING::from(format!("{}\\LocalServer32", class_key(&clsid)));
+    let command = format!(
+        "\"{}\" {}",
+        exe_path.display(),
+        crate::ComActivator::LAUNCH_ARG
+    );
+
+    unsafe {
+        let transaction = CreateTransaction(null_mut(), null_mut(), 0, 0, 0, 0, PCWSTR::null())?;
+        assert!(!transaction.is_invalid());
+
+        scopeguard::defer! {
+            let _ = CloseHandle(transaction);
+        }
+
+        let app_hkey = create_key_transacted(&app_path, transaction)?;
+        RegSetValueExW(
+            app_hkey,
+            &HSTRING::from("CustomActivator"),
+            0,
+            REG_SZ,
+            Some(&to_utf16(&clsid)),
+        )
+        .ok()?;
+
+        let server_hkey = create_key_transacted(&server_path, transaction)?;
+        RegSetValueExW(
+            server_hkey,
+            PCWSTR::null(),
+            0,
+            REG_SZ,
+            Some(&to_utf16(&command)),
+        )
+        .ok()?;
+
+        CommitTransaction(transaction).ok();
+    }
+
     Ok(())
 }
 
+/// Create or open the key at `path` under `HKEY_CURRENT_USER` as part of `transaction`.
+#[cfg(feature = "activator")]
+unsafe fn create_key_transacted(
+    path: &HSTRING,
+    transaction: windows::Win32::Foundation::HANDLE,
+) -> crate::Result<HKEY> {
+    let mut hkey = HKEY::default();
+    RegCreateKeyTransactedW(
+        HKEY_CURRENT_USER,
+        path,
+        0,
+        PCWSTR::null(),
+        REG_OPTION_NON_VOLATILE,
+        KEY_ALL_ACCESS,
+        None,
+        &mut hkey,
+        None,
+        transaction,
+        None,
+    )
+    .ok()?;
+    assert!(!hkey.is_invalid());
+
+    Ok(hkey)
+}
+
 /// Convert to null-terminated UTF-16 bytes
 fn to_utf16<P: AsRef<OsStr>>(s: P) -> Vec<u8> {
     s.as_ref()
@@ -92,3 +434,117 @@ fn to_utf16<P: AsRef<OsStr>>(s: P) -> Vec<u8> {
 // fn utf16_bytes_len(s: &[u16]) -> usize {
 //     s.len() * std::mem::size_of::<u16>()
 // }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn registry_paths() {
+        assert_eq!(
+            app_key("Contoso.App"),
+            r"SOFTWARE\Classes\AppUserModelId\Contoso.App"
+        );
+        assert_eq!(
+            class_key("{1D4B5A6C-0F3E-4C8A-9B2D-7E6F5A4B3C2D}"),
+            r"SOFTWARE\Classes\CLSID\{1D4B5A6C-0F3E-4C8A-9B2D-7E6F5A4B3C2D}"
+        );
+    }
+
+    #[test]
+    fn listed_keys_are_filtered_by_prefix() {
+        let name: Vec<u16> = "Contoso.Mail".encode_utf16().collect();
+        assert_eq!(listed_aum_id(&name, None).as_deref(), Some("Contoso.Mail"));
+        assert_eq!(
+            listed_aum_id(&name, Some("Contoso.")).as_deref(),
+            Some("Contoso.Mail")
+        );
+        assert_eq!(listed_aum_id(&name, Some("contoso.")), None);
+        assert_eq!(listed_aum_id(&name, Some("Fabrikam.")), None);
+        assert_eq!(listed_aum_id(&[], Some("")).as_deref(), Some(""));
+    }
+
+    #[test]
+    fn keys_that_are_not_unicode_are_skipped() {
+        let name = ['C' as u16, 0xD800, 'x' as u16];
+        assert_eq!(listed_aum_id(&name, None), None);
+    }
+
+    #[test]
+    #[ignore = "writes to the registry of the current user"]
+    fn registered_aum_ids_are_listed_by_prefix() -> crate::Result<()> {
+        let prefix = "WinrtToastReborn.ListCheck.";
+        let aum_ids = [format!("{}A", prefix), format!("{}B", prefix)];
+        scopeguard::defer! {
+            for aum_id in &aum_ids {
+                let _ = unregister(aum_id);
+            }
+        }
+        for aum_id in &aum_ids {
+            register(aum_id, aum_id, None)?;
+        }
+
+        let mut listed = list_registered(Some(prefix))?;
+        listed.sort_by(|a, b| a.aum_id.cmp(&b.aum_id));
+        let listed: Vec<_> = listed
+            .iter()
+            .map(|info| (info.aum_id.as_str(), info.display_name.clone()))
+            .collect();
+        assert_eq!(
+            listed,
+            aum_ids
+                .iter()
+                .map(|aum_id| (aum_id.as_str(), Some(OsString::from(aum_id))))
+                .collect::<Vec<_>>()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn absent_keys_are_not_errors() {
+        assert!(found(ERROR_SUCCESS).unwrap());
+        assert!(!found(ERROR_FILE_NOT_FOUND).unwrap());
+    }
+
+    #[test]
+    fn values_are_written_as_null_terminated_utf16() {
+        assert_eq!(to_utf16("Ab"), [b'A', 0, b'b', 0, 0, 0]);
+        assert_eq!(to_utf16(""), [0, 0]);
+    }
+
+    #[test]
+    fn wide_display_names_are_written_without_loss() {
+        let wide = ['C' as u16, 0xD800, 'x' as u16];
+        let name = OsString::from_wide(&wide);
+        assert!(name.to_str().is_none());
+        let written: Vec<u16> = to_utf16(&name)
+            .chunks(2)
+            .map(|unit| u16::from_ne_bytes([unit[0], unit[1]]))
+            .collect();
+        assert_eq!(written, ['C' as u16, 0xD800, 'x' as u16, 0]);
+    }
+
+    #[test]
+    #[ignore = "writes to the registry of the current user"]
+    fn registration_results_report_the_changes() -> crate::Result<()> {
+        let aum_id = "WinrtToastReborn.RegisterCheck";
+        let icon = std::env::temp_dir().join("icon.png");
+        let _ = unregister(aum_id)?;
+        scopeguard::defer! {
+            let _ = unregister(aum_id);
+        }
+
+        let first = register(aum_id, "Check", Some(&icon))?;
+        assert!(first.created && first.icon_set && !first.icon_removed);
+        let again = register(aum_id, "Check", None)?;
+        assert!(!again.created && !again.icon_set && again.icon_removed);
+        assert!(is_registered(aum_id) == Some(true));
+
+        let removed = unregister(aum_id)?;
+        assert!(removed.app_key_deleted && !removed.activator_key_deleted);
+        let absent = unregister(aum_id)?;
+        assert!(!absent.app_key_deleted && !absent.activator_key_deleted);
+        assert!(is_registered(aum_id) == Some(false));
+        Ok(())
+    }
+}
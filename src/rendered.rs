@@ -0,0 +1,151 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex, Weak},
+};
+
+use windows::{
+    core::IInspectable,
+    Foundation::TypedEventHandler,
+    UI::Notifications::{
+        ToastDismissalReason, ToastDismissedEventArgs, ToastFailedEventArgs, ToastNotification,
+    },
+};
+
+use crate::ToastManager;
+
+/// How many rendered toasts are retained, see [`ToastManager::retain_rendered_xml`](crate::ToastManager::retain_rendered_xml).
+pub(crate) const CAPACITY: usize = 32;
+
+/// A toast is addressed by its group, if it has one, and its tag.
+type Key = (Option<String>, String);
+
+/// The XML of the shown toasts with a tag, least recently used first.
+#[derive(Debug, Default)]
+pub(crate) struct RenderedXml {
+    entries: Mutex<VecDeque<(Key, String)>>,
+}
+
+impl RenderedXml {
+    /// Retain `xml` for `key`, evicting the least recently used entry if full.
+    pub(crate) fn insert(&self, key: Key, xml: String) {
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|(k, _)| *k != key);
+        if entries.len() >= CAPACITY {
+            entries.pop_front();
+        }
+        entries.push_back((key, xml));
+    }
+
+    /// The XML retained for `tag` in `group`, which becomes the most recently used entry.
+    pub(crate) fn get(&self, tag: &str, group: Option<&str>) -> Option<String> {
+        let mut entries = self.entries.lock().unwrap();
+        let index = entries
+            .iter()
+            .position(|((g, t), _)| t == tag && g.as_deref() == group)?;
+        let entry = entries.remove(index)?;
+        let xml = entry.1.clone();
+        entries.push_back(entry);
+        Some(xml)
+    }
+
+    pub(crate) fn remove(&self, key: &Key) {
+        self.entries.lock().unwrap().retain(|(k, _)| k != key);
+    }
+
+    /// Retain the XML of `notification` while it can still be seen.
+    ///
+    /// The XML is dropped once the toast fails, is activated by an action that doesn't keep
+    /// it on screen, or is dismissed by the user or the app. A toast that timed out is still
+    /// in the Action Center, so its XML is kept.
+    /// The handlers only hold a weak reference, so they don't keep the store alive.
+    pub(crate) fn track(
+        self: &Arc<Self>,
+        notification: &ToastNotification,
+        group: Option<&str>,
+        tag: &str,
+        xml: String,
+    ) -> windows::core::Result<()> {
+        let key = (group.map(str::to_string), tag.to_string());
+        self.insert(key.clone(), xml);
+
+        let evict = {
+            let store = Arc::downgrade(self);
+            move || {
+                if let Some(store) = Weak::upgrade(&store) {
+                    store.remove(&key);
+                }
+                Ok(())
+            }
+        };
+        let (activated, dismissed, failed) = (evict.clone(), evict.clone(), evict);
+        notification.Activated(&TypedEventHandler::new(
+            move |notification: &Option<ToastNotification>, args: &Option<IInspectable>| {
+                match notification {
+                    Some(notification) if ToastManager::keeps_toast(notification, args) => Ok(()),
+                    _ => activated(),
+                }
+            },
+        ))?;
+        notification.Dismissed(&TypedEventHandler::new(
+            move |_, args: &Option<ToastDismissedEventArgs>| match args
+                .as_ref()
+                .and_then(|args| args.Reason().ok())
+            {
+                Some(ToastDismissalReason::TimedOut) => Ok(()),
+                _ => dismissed(),
+            },
+        ))?;
+        notification.Failed(&TypedEventHandler::new(
+            move |_, _: &Option<ToastFailedEventArgs>| failed(),
+        ))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(group: Option<&str>, tag: &str) -> Key {
+        (group.map(str::to_string), tag.to_string())
+    }
+
+    #[test]
+    fn same_tag_in_different_groups() {
+        let store = RenderedXml::default();
+        store.insert(key(Some("a"), "tag"), "<a/>".into());
+        store.insert(key(Some("b"), "tag"), "<b/>".into());
+        store.insert(key(None, "tag"), "<none/>".into());
+
+        assert_eq!(store.get("tag", Some("a")).as_deref(), Some("<a/>"));
+        assert_eq!(store.get("tag", Some("b")).as_deref(), Some("<b/>"));
+        assert_eq!(store.get("tag", None).as_deref(), Some("<none/>"));
+
+        store.remove(&key(Some("a"), "tag"));
+        assert_eq!(store.get("tag", Some("a")), None);
+        assert_eq!(store.get("tag", Some("b")).as_deref(), Some("<b/>"));
+    }
+
+    #[test]
+    fn showing_again_replaces_the_xml() {
+        let store = RenderedXml::default();
+        store.insert(key(None, "tag"), "<old/>".into());
+        store.insert(key(None, "tag"), "<new/>".into());
+        assert_eq!(store.get("tag", None).as_deref(), Some("<new/>"));
+        assert_eq!(store.entries.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn evicts_the_least_recently_used() {
+        let store = RenderedXml::default();
+        for i in 0..CAPACITY {
+            store.insert(key(None, &i.to_string()), String::new());
+        }
+        assert!(store.get("0", None).is_some());
+        store.insert(key(None, "new"), String::new());
+
+        assert!(store.get("0", None).is_some());
+        assert!(store.get("1", None).is_none());
+        assert!(store.get("new", None).is_some());
+    }
+}
@@ -0,0 +1,137 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Condvar, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use windows::{
+    core::IInspectable,
+    Foundation::TypedEventHandler,
+    UI::Notifications::{ToastDismissedEventArgs, ToastNotification},
+};
+
+use crate::{Toast, ToastHandle, ToastManager};
+
+/// Wakes the timers of a manager and its clones early when the manager is shut down.
+#[derive(Debug, Default)]
+pub(crate) struct Timers {
+    cancelled: Mutex<bool>,
+    wake: Condvar,
+}
+
+impl Timers {
+    /// Wait for `duration`, and return whether it passed without [`Timers::cancel`] being called.
+    pub(crate) fn sleep(&self, duration: Duration) -> bool {
+        let deadline = Instant::now() + duration;
+        let mut cancelled = self.cancelled.lock().unwrap();
+        while !*cancelled {
+            let Some(remaining) = deadline.checked_duration_since(Instant::now()) else {
+                return true;
+            };
+            cancelled = self.wake.wait_timeout(cancelled, remaining).unwrap().0;
+        }
+        false
+    }
+
+    /// End all current and future [`Timers::sleep`]s early.
+    pub(crate) fn cancel(&self) {
+        *self.cancelled.lock().unwrap() = true;
+        self.wake.notify_all();
+    }
+}
+
+/// The loop count of a shown toast, see [`Audio::with_loop_count`](crate::Audio::with_loop_count).
+///
+/// Tracks whether the toast was activated or dismissed, after which it is left alone.
+pub(crate) struct Ringing {
+    resolved: Arc<AtomicBool>,
+}
+
+impl Ringing {
+    /// Start tracking `notification`, before it is shown.
+    pub(crate) fn attach(notification: &ToastNotification) -> crate::Result<Self> {
+        let resolved = Arc::new(AtomicBool::new(false));
+
+        let flag = resolved.clone();
+        notification.Activated(&TypedEventHandler::new(
+            move |_, _: &Option<IInspectable>| {
+                flag.store(true, Ordering::Relaxed);
+                Ok(())
+            },
+        ))?;
+        let flag = resolved.clone();
+        notification.Dismissed(&TypedEventHandler::new(
+            move |_, _: &Option<ToastDismissedEventArgs>| {
+                flag.store(true, Ordering::Relaxed);
+                Ok(())
+            },
+        ))?;
+
+        Ok(Self { resolved })
+    }
+
+    /// After `after`, replace the toast of `handle` with a silent copy of `toast`,
+    /// unless it was resolved or `timers` were cancelled by then.
+    ///
+    /// The handle then refers to the silent copy.
+    pub(crate) fn silence_after(
+        self,
+        after: Duration,
+        timers: Arc<Timers>,
+        manager: ToastManager,
+        toast: Toast,
+        handle: ToastHandle,
+    ) {
+        thread::spawn(move || {
+            if !timers.sleep(after) || self.resolved.load(Ordering::Relaxed) {
+                return;
+            }
+            let mut silent = toast;
+            silent.audio = silent.audio.as_ref().map(|audio| audio.silenced());
+            let _ = handle.replace(|| manager.show_unchecked(&silent));
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sleep_runs_out() {
+        let timers = Timers::default();
+        let start = Instant::now();
+        assert!(timers.sleep(Duration::from_millis(20)));
+        assert!(start.elapsed() >= Duration::from_millis(20));
+    }
+
+    #[test]
+    fn cancel_wakes_sleeping_timers() {
+        let timers = Arc::new(Timers::default());
+        let sleepers: Vec<_> = (0..3)
+            .map(|_| {
+                let timers = timers.clone();
+                thread::spawn(move || timers.sleep(Duration::from_secs(60)))
+            })
+            .collect();
+        thread::sleep(Duration::from_millis(20));
+        let start = Instant::now();
+        timers.cancel();
+        for sleeper in sleepers {
+            assert!(!sleeper.join().unwrap());
+        }
+        assert!(start.elapsed() < Duration::from_secs(10));
+    }
+
+    #[test]
+    fn sleep_after_cancel_returns_at_once() {
+        let timers = Timers::default();
+        timers.cancel();
+        let start = Instant::now();
+        assert!(!timers.sleep(Duration::from_secs(60)));
+        assert!(start.elapsed() < Duration::from_secs(10));
+    }
+}
@@ -0,0 +1,567 @@
+use windows::{
+    core::Interface,
+    Data::Xml::Dom::{IXmlNode, NodeType},
+};
+
+use crate::{xml, Toast, WinToastError};
+
+/// The attributes and child elements an element of the toast schema may have.
+struct ElementRule {
+    name: &'static str,
+    attributes: &'static [&'static str],
+    children: &'static [&'static str],
+}
+
+/// The attributes shared by `<text>` elements, in and out of subgroups.
+const TEXT_ATTRIBUTES: &[&str] = &[
+    "id",
+    "lang",
+    "placement",
+    "hint-style",
+    "hint-align",
+    "hint-wrap",
+    "hint-maxLines",
+    "hint-minLines",
+    "hint-callScenarioCenterAlign",
+];
+
+/// The attributes shared by `<image>` elements, in and out of subgroups.
+const IMAGE_ATTRIBUTES: &[&str] = &[
+    "id",
+    "src",
+    "alt",
+    "addImageQuery",
+    "placement",
+    "hint-crop",
+    "hint-removeMargin",
+    "hint-align",
+];
+
+/// The toast content schema, from
+/// <https://learn.microsoft.com/en-us/windows/apps/design/shell/tiles-and-notifications/toast-schema>,
+/// including the legacy templates and the elements of the alarm and call scenarios.
+const RULES: &[ElementRule] = &[
+    ElementRule {
+        name: "toast",
+        attributes: &[
+            "launch",
+            "duration",
+            "displayTimestamp",
+            "scenario",
+            "useButtonStyle",
+            "activationType",
+            "protocolActivationTargetApplicationPfn",
+            "afterActivationBehavior",
+            "hint-toastId",
+            "hint-people",
+        ],
+        children: &["visual", "audio", "actions", "header", "commands"],
+    },
+    ElementRule {
+        name: "visual",
+        attributes: &["version", "lang", "baseUri", "branding", "addImageQuery"],
+        children: &["binding"],
+    },
+    ElementRule {
+        name: "binding",
+        attributes: &[
+            "template",
+            "fallback",
+            "lang",
+            "baseUri",
+            "branding",
+            "addImageQuery",
+        ],
+        children: &["text", "image", "group", "progress"],
+    },
+    ElementRule {
+        name: "text",
+        attributes: TEXT_ATTRIBUTES,
+        children: &[],
+    },
+    ElementRule {
+        name: "image",
+        attributes: IMAGE_ATTRIBUTES,
+        children: &[],
+    },
+    ElementRule {
+        name: "group",
+        attributes: &[],
+        children: &["subgroup"],
+    },
+    ElementRule {
+        name: "subgroup",
+        attributes: &["hint-weight", "hint-textStacking"],
+        children: &["text", "image"],
+    },
+    ElementRule {
+        name: "progress",
+        attributes: &["title", "value", "valueStringOverride", "status"],
+        children: &[],
+    },
+    ElementRule {
+        name: "audio",
+        attributes: &["src", "loop", "silent"],
+        children: &[],
+    },
+    ElementRule {
+        name: "actions",
+        attributes: &["hint-systemCommands"],
+        children: &["input", "action"],
+    },
+    ElementRule {
+        name: "input",
+        attributes: &["id", "type", "title", "placeHolderContent", "defaultInput"],
+        children: &["selection"],
+    },
+    ElementRule {
+        name: "selection",
+        attributes: &["id", "content"],
+        children: &[],
+    },
+    ElementRule {
+        name: "action",
+        attributes: &[
+            "content",
+            "arguments",
+            "type",
+            "activationType",
+            "afterActivationBehavior",
+            "protocolActivationTargetApplicationPfn",
+            "placement",
+            "imageUri",
+            "hint-inputId",
+            "hint-buttonStyle",
+            "hint-toolTip",
+        ],
+        children: &[],
+    },
+    ElementRule {
+        name: "header",
+        attributes: &["id", "title", "arguments", "activationType"],
+        children: &[],
+    },
+    ElementRule {
+        name: "commands",
+        attributes: &["scenario"],
+        children: &["command"],
+    },
+    ElementRule {
+        name: "command",
+        attributes: &["id", "arguments"],
+        children: &[],
+    },
+];
+
+impl Toast {
+    /// Check the XML of this toast against the toast content schema.
+    ///
+    /// Windows ignores elements and attributes it doesn't know, so a misspelled name, such as
+    /// `hint-inputid` for `hint-inputId`, silently has no effect. This reports the first
+    /// element or attribute that the schema doesn't allow where it is as
+    /// [`WinToastError::SchemaViolation`], naming the intended one if only the case differs.
+    ///
+    /// The schema is a table built into this crate, so raw attributes that Windows added
+    /// after this version of the crate are reported too. Only names are checked, not values.
+    /// See [`check_toast_xml`](crate::check_toast_xml) for XML from elsewhere.
+    pub fn check_schema(&self) -> crate::Result<()> {
+        check_element(
+            &self.to_document()?.DocumentElement()?.cast::<IXmlNode>()?,
+            None,
+        )
+    }
+}
+
+/// Check toast XML against the toast content schema, like [`Toast::check_schema`].
+///
+/// Returns [`WinToastError::InvalidXml`] if `xml` can't be parsed.
+pub fn check_toast_xml(xml: &str) -> crate::Result<()> {
+    let doc = xml::load_document(xml)?;
+    check_element(&doc.DocumentElement()?.cast::<IXmlNode>()?, None)
+}
+
+/// An element of toast XML, as seen by the schema check.
+trait SchemaElement: Sized {
+    fn name(&self) -> crate::Result<String>;
+    fn attribute_names(&self) -> crate::Result<Vec<String>>;
+    fn child_elements(&self) -> crate::Result<Vec<Self>>;
+}
+
+impl SchemaElement for IXmlNode {
+    fn name(&self) -> crate::Result<String> {
+        Ok(self.NodeName()?.to_string())
+    }
+
+    fn attribute_names(&self) -> crate::Result<Vec<String>> {
+        let mut names = Vec::new();
+        for attribute in &self.Attributes()? {
+            names.push(attribute.NodeName()?.to_string());
+        }
+        Ok(names)
+    }
+
+    fn child_elements(&self) -> crate::Result<Vec<Self>> {
+        let mut children = Vec::new();
+        for child in &self.ChildNodes()? {
+            if child.NodeType()? == NodeType::ElementNode {
+                children.push(child);
+            }
+        }
+        Ok(children)
+    }
+}
+
+fn check_element<E: SchemaElement>(el: &E, parent: Option<&ElementRule>) -> crate::Result<()> {
+    let name = el.name()?;
+    let allowed = match parent {
+        Some(parent) => parent.children,
+        None => &["toast"],
+    };
+    if !allowed.contains(&name.as_str()) {
+        let place = match parent {
+            Some(parent) => format!("in <{}>", parent.name),
+            None => "as the root".to_string(),
+        };
+        return Err(violation(
+            format!("<{}> is not allowed {}", name, place),
+            &name,
+            allowed,
+        ));
+    }
+    let rule = RULES
+        .iter()
+        .find(|rule| rule.name == name)
+        .expect("every allowed child has a rule");
+
+    for attribute in el.attribute_names()? {
+        if attribute.starts_with("xmlns") || attribute.starts_with("xml:") {
+            continue;
+        }
+        if !rule.attributes.contains(&attribute.as_str()) {
+            return Err(violation(
+                format!("<{}> has the unknown attribute {:?}", name, attribute),
+                &attribute,
+                rule.attributes,
+            ));
+        }
+    }
+
+    for child in el.child_elements()? {
+        check_element(&child, Some(rule))?;
+    }
+
+    Ok(())
+}
+
+/// A schema violation with `problem`, suggesting a name from `known` that differs from
+/// `name` only in case.
+fn violation(problem: String, name: &str, known: &[&str]) -> WinToastError {
+    match known.iter().find(|known| known.eq_ignore_ascii_case(name)) {
+        Some(known) => {
+            WinToastError::SchemaViolation(format!("{}, did you mean {:?}?", problem, known))
+        }
+        None => WinToastError::SchemaViolation(problem),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use url::Url;
+
+    use super::*;
+    use crate::{
+        content::{
+            action::{ActionPlacement, ActivationType, AfterActivation, HintButtonStyle},
+            audio::Sound,
+            header,
+            image::{ImageHintCrop, ImagePlacement},
+            input::InputType,
+            text::{TextAlign, TextPlacement, TextStyle},
+        },
+        Action, Audio, Header, Image, Input, Progress, ProgressValue, Scenario, Selection,
+        SelectionGroup, Text, ToastDuration,
+    };
+
+    impl SchemaElement for roxmltree::Node<'_, '_> {
+        fn name(&self) -> crate::Result<String> {
+            Ok(self.tag_name().name().to_string())
+        }
+
+        fn attribute_names(&self) -> crate::Result<Vec<String>> {
+            let qualified = |a: roxmltree::Attribute| match a.namespace() {
+                Some(ns) => format!(
+                    "{}:{}",
+                    self.lookup_prefix(ns).unwrap_or_default(),
+                    a.name()
+                ),
+                None => a.name().to_string(),
+            };
+            Ok(self.attributes().map(qualified).collect())
+        }
+
+        fn child_elements(&self) -> crate::Result<Vec<Self>> {
+            Ok(self.children().filter(|n| n.is_element()).collect())
+        }
+    }
+
+    /// XML in tests/snapshots, with the toast that generates it.
+    struct Snapshot {
+        name: &'static str,
+        xml: &'static str,
+        toast: fn() -> Toast,
+    }
+
+    const SNAPSHOTS: &[Snapshot] = &[
+        Snapshot {
+            name: "plain",
+            xml: include_str!("../tests/snapshots/plain.xml"),
+            toast: plain,
+        },
+        Snapshot {
+            name: "quick_reply",
+            xml: include_str!("../tests/snapshots/quick_reply.xml"),
+            toast: quick_reply,
+        },
+        Snapshot {
+            name: "progress",
+            xml: include_str!("../tests/snapshots/progress.xml"),
+            toast: progress,
+        },
+        Snapshot {
+            name: "selection",
+            xml: include_str!("../tests/snapshots/selection.xml"),
+            toast: selection,
+        },
+        Snapshot {
+            name: "step_through",
+            xml: include_str!("../tests/snapshots/step_through.xml"),
+            toast: step_through,
+        },
+    ];
+
+    fn url(url: &str) -> Url {
+        Url::parse(url).unwrap()
+    }
+
+    fn plain() -> Toast {
+        let mut toast = Toast::new();
+        toast
+            .launch("open")
+            .text1("Build finished")
+            .text2("All 92 tests passed");
+        toast
+    }
+
+    fn quick_reply() -> Toast {
+        let mut toast = Toast::new();
+        toast
+            .scenario(Scenario::Reminder)
+            .launch("open-chat")
+            .duration(ToastDuration::Long)
+            .header(Header::new("chat", "Chat", "open-chat"))
+            .text1("Ada Lovelace")
+            .text2(Text::new("Are we still on for today?").with_max_lines(2))
+            .text3(Text::new("via Chat").with_placement(TextPlacement::Attribution))
+            .image(
+                1,
+                Image::new(url("https://example.com/ada.png"))
+                    .with_placement(ImagePlacement::AppLogoOverride)
+                    .with_hint_crop(ImageHintCrop::Circle)
+                    .with_alt("Ada"),
+            )
+            .audio(Audio::new(Sound::IM))
+            .action(Action::new("Mute", "mute", "").with_placement(ActionPlacement::ContextMenu))
+            .action(
+                Action::new("Later", "later", "").with_activation_type(ActivationType::Background),
+            )
+            .with_default_dismiss()
+            .quick_reply_enter_submits(
+                Input::new("reply", InputType::Text).with_placeholder("Reply"),
+                Action::new("Send", "send", "").with_button_style(HintButtonStyle::Success),
+            );
+        toast
+    }
+
+    fn progress() -> Toast {
+        let mut toast = Toast::new();
+        toast
+            .text1("Uploading photos")
+            .image(
+                1,
+                Image::new(url("https://example.com/hero.png"))
+                    .with_placement(ImagePlacement::Hero),
+            )
+            .progress(
+                Progress::new(ProgressValue::Fixed(0.6), "Uploading...")
+                    .with_title("Holiday")
+                    .with_value_string_override("3 of 5 photos"),
+            )
+            .silent();
+        toast
+    }
+
+    fn selection() -> Toast {
+        let snooze = SelectionGroup::new()
+            .with_selection(Selection::new("5", "5 minutes"))
+            .with_selection(Selection::new("15", "15 minutes"))
+            .with_default("15");
+        let mut toast = Toast::new();
+        toast
+            .scenario(Scenario::Alarm)
+            .text1("Wake up")
+            .selection_input(
+                Input::new("snooze", InputType::Selection).with_title("Snooze for"),
+                &snooze,
+            )
+            .action(
+                Action::new("", "snooze", "")
+                    .with_activation_type(ActivationType::System)
+                    .with_input_id("snooze"),
+            )
+            .with_default_dismiss();
+        toast
+    }
+
+    fn step_through() -> Toast {
+        let mut toast = Toast::new();
+        toast
+            .use_button_style()
+            .header(
+                Header::new("inbox", "Inbox", "https://example.com/inbox")
+                    .with_activation_type(header::ActivationType::Protocol),
+            )
+            .text1(
+                Text::new("New message")
+                    .with_lang("en-GB")
+                    .with_style(TextStyle::Base)
+                    .with_align(TextAlign::Center)
+                    .with_wrap(true)
+                    .with_min_lines(1),
+            )
+            .text2("{status}")
+            .progress(Progress::new(
+                ProgressValue::Bound("progressValue".to_string()),
+                "Step {step} of 3",
+            ))
+            .action(
+                Action::new("Mark read", "read", "")
+                    .with_activation_type(ActivationType::Background)
+                    .with_after_activation(AfterActivation::PendingUpdate),
+            )
+            .action(
+                Action::new("Delete", "delete", "")
+                    .with_button_style(HintButtonStyle::Critical)
+                    .with_raw_attribute("hint-toolTip", "Delete the message"),
+            )
+            .root_attribute("hint-people", "remoteid:1234")
+            .unwrap();
+        toast
+    }
+
+    fn check(xml: &str) -> crate::Result<()> {
+        let doc = roxmltree::Document::parse(xml).unwrap();
+        check_element(&doc.root_element(), None)
+    }
+
+    fn violation_of(xml: &str) -> String {
+        match check(xml) {
+            Err(WinToastError::SchemaViolation(problem)) => problem,
+            other => panic!("expected a schema violation, got {:?}", other),
+        }
+    }
+
+    /// An element with its attributes in name order, without the whitespace between elements.
+    #[derive(Debug, PartialEq)]
+    struct Normalized {
+        name: String,
+        attributes: BTreeMap<String, String>,
+        text: String,
+        children: Vec<Normalized>,
+    }
+
+    fn normalize(node: roxmltree::Node) -> Normalized {
+        Normalized {
+            name: node.tag_name().name().to_string(),
+            attributes: node
+                .attributes()
+                .map(|a| (a.name().to_string(), a.value().to_string()))
+                .collect(),
+            text: node
+                .children()
+                .filter(|n| n.is_text())
+                .filter_map(|n| n.text())
+                .collect::<String>()
+                .trim()
+                .to_string(),
+            children: node
+                .children()
+                .filter(|n| n.is_element())
+                .map(normalize)
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn snapshots_conform_to_the_schema() {
+        for snapshot in SNAPSHOTS {
+            if let Err(e) = check(snapshot.xml) {
+                panic!("snapshot {} doesn't conform: {}", snapshot.name, e);
+            }
+        }
+    }
+
+    #[test]
+    fn generated_xml_matches_the_snapshots() {
+        for snapshot in SNAPSHOTS {
+            let xml = (snapshot.toast)().to_xml().unwrap();
+            let generated = roxmltree::Document::parse(&xml).unwrap();
+            let expected = roxmltree::Document::parse(snapshot.xml).unwrap();
+            assert_eq!(
+                normalize(generated.root_element()),
+                normalize(expected.root_element()),
+                "snapshot {}, generated {}",
+                snapshot.name,
+                xml
+            );
+        }
+    }
+
+    #[test]
+    fn misspelled_attributes_are_reported_with_the_intended_name() {
+        let problem = violation_of(
+            r#"<toast><actions><input id="r" type="text"/><action content="Send" arguments="s" hint-inputid="r"/></actions></toast>"#,
+        );
+        assert_eq!(
+            problem,
+            r#"<action> has the unknown attribute "hint-inputid", did you mean "hint-inputId"?"#
+        );
+    }
+
+    #[test]
+    fn unknown_attributes_are_reported() {
+        let problem = violation_of(
+            r#"<toast><audio src="ms-winsoundevent:Notification.IM" volume="1"/></toast>"#,
+        );
+        assert_eq!(problem, r#"<audio> has the unknown attribute "volume""#);
+    }
+
+    #[test]
+    fn misplaced_elements_are_reported() {
+        let problem = violation_of(r#"<toast><visual><text id="1">Hi</text></visual></toast>"#);
+        assert_eq!(problem, "<text> is not allowed in <visual>");
+        let problem = violation_of(r#"<tile><visual/></tile>"#);
+        assert_eq!(problem, "<tile> is not allowed as the root");
+        let problem = violation_of(r#"<toast><Visual/></toast>"#);
+        assert_eq!(
+            problem,
+            r#"<Visual> is not allowed in <toast>, did you mean "visual"?"#
+        );
+    }
+
+    #[test]
+    fn namespace_declarations_are_allowed() {
+        assert!(check(r#"<toast xmlns:x="urn:x" xml:lang="en"><visual/></toast>"#).is_ok());
+    }
+}
@@ -0,0 +1,382 @@
+use std::{
+    collections::VecDeque,
+    sync::{mpsc, Arc, Mutex, OnceLock, Weak},
+    thread,
+    time::{Duration, Instant},
+};
+
+use windows::{
+    core::{w, PWSTR},
+    Win32::{
+        Foundation::{HWND, LPARAM, LRESULT, WPARAM},
+        System::{
+            LibraryLoader::GetModuleHandleW,
+            RemoteDesktop::{
+                WTSFreeMemory, WTSQuerySessionInformationW, WTSRegisterSessionNotification,
+                WTSSessionInfoEx, WTSUnRegisterSessionNotification, NOTIFY_FOR_THIS_SESSION,
+                WTSINFOEXW, WTS_CURRENT_SERVER_HANDLE, WTS_CURRENT_SESSION, WTS_SESSIONSTATE_LOCK,
+            },
+            Threading::GetCurrentThreadId,
+        },
+        UI::WindowsAndMessaging::{
+            CreateWindowExW, DefWindowProcW, DestroyWindow, DispatchMessageW, GetMessageW,
+            PostMessageW, PostThreadMessageW, RegisterClassW, HWND_MESSAGE, MSG, WINDOW_EX_STYLE,
+            WINDOW_STYLE, WM_APP, WM_QUIT, WM_WTSSESSION_CHANGE, WNDCLASSW, WTS_SESSION_UNLOCK,
+        },
+    },
+};
+
+use crate::{Toast, ToastData, ToastManager, ToastTemplate, UndeliverableReason};
+
+/// How often the listener checks whether the session was unlocked, if Windows can't notify it.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Posted by the window procedure when the session is unlocked, so that the queue is
+/// flushed by the message loop rather than while Windows waits for the window procedure.
+const WM_UNLOCKED: u32 = WM_APP;
+
+/// Whether the workstation of the current session is locked.
+///
+/// Returns `false` if the lock state cannot be determined.
+pub(crate) fn is_locked() -> bool {
+    unsafe {
+        let mut buffer = PWSTR::null();
+        let mut len = 0;
+        if WTSQuerySessionInformationW(
+            WTS_CURRENT_SERVER_HANDLE,
+            WTS_CURRENT_SESSION,
+            WTSSessionInfoEx,
+            &mut buffer,
+            &mut len,
+        )
+        .is_err()
+        {
+            return false;
+        }
+
+        scopeguard::defer! {
+            WTSFreeMemory(buffer.0 as _);
+        }
+
+        if (len as usize) < std::mem::size_of::<WTSINFOEXW>() {
+            return false;
+        }
+        let info = &*(buffer.0 as *const WTSINFOEXW);
+        info.Level == 1 && info.Data.WTSInfoExLevel1.SessionFlags == WTS_SESSIONSTATE_LOCK as i32
+    }
+}
+
+/// A message-only window registered for the session notifications of the current session.
+struct SessionWindow(HWND);
+
+impl SessionWindow {
+    fn create() -> windows::core::Result<Self> {
+        unsafe {
+            let instance = GetModuleHandleW(None)?;
+            let class = w!("winrt-toast-reborn-session");
+            let window_class = WNDCLASSW {
+                lpfnWndProc: Some(Self::window_proc),
+                hInstance: instance.into(),
+                lpszClassName: class,
+                ..Default::default()
+            };
+            // This fails once the class is registered by an earlier listener, which is fine.
+            RegisterClassW(&window_class);
+
+            let hwnd = CreateWindowExW(
+                WINDOW_EX_STYLE::default(),
+                class,
+                None,
+                WINDOW_STYLE::default(),
+                0,
+                0,
+                0,
+                0,
+                HWND_MESSAGE,
+                None,
+                instance,
+                None,
+            );
+            if hwnd.0 == 0 {
+                return Err(windows::core::Error::from_win32());
+            }
+            let window = Self(hwnd);
+            WTSRegisterSessionNotification(hwnd, NOTIFY_FOR_THIS_SESSION)?;
+            Ok(window)
+        }
+    }
+
+    extern "system" fn window_proc(
+        hwnd: HWND,
+        message: u32,
+        wparam: WPARAM,
+        lparam: LPARAM,
+    ) -> LRESULT {
+        unsafe {
+            if message == WM_WTSSESSION_CHANGE && wparam.0 as u32 == WTS_SESSION_UNLOCK {
+                let _ = PostMessageW(hwnd, WM_UNLOCKED, WPARAM(0), LPARAM(0));
+                return LRESULT(0);
+            }
+            DefWindowProcW(hwnd, message, wparam, lparam)
+        }
+    }
+
+    /// Run the message loop of this thread until it receives `WM_QUIT`,
+    /// calling `on_unlock` whenever the session is unlocked.
+    fn run(&self, mut on_unlock: impl FnMut()) {
+        let mut message = MSG::default();
+        // `GetMessageW` returns 0 for `WM_QUIT` and -1 on errors.
+        while unsafe { GetMessageW(&mut message, HWND::default(), 0, 0) }.0 > 0 {
+            if message.message == WM_UNLOCKED {
+                on_unlock();
+            } else {
+                unsafe { DispatchMessageW(&message) };
+            }
+        }
+    }
+}
+
+impl Drop for SessionWindow {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = WTSUnRegisterSessionNotification(self.0);
+            let _ = DestroyWindow(self.0);
+        }
+    }
+}
+
+/// A show held back while the session is locked.
+pub(crate) enum Queued {
+    /// A toast passed to [`ToastManager::show`] or one of its variants.
+    Toast(Toast),
+    /// A template and its data passed to [`ToastManager::show_template`].
+    Template(ToastTemplate, ToastData),
+}
+
+impl Queued {
+    /// The toast to be shown.
+    pub(crate) fn toast(&self) -> &Toast {
+        match self {
+            Queued::Toast(toast) => toast,
+            Queued::Template(template, _) => &template.toast,
+        }
+    }
+
+    fn toast_mut(&mut self) -> &mut Toast {
+        match self {
+            Queued::Toast(toast) => toast,
+            Queued::Template(template, _) => &mut template.toast,
+        }
+    }
+
+    /// Show it with `manager`, the way it was first passed to the manager.
+    pub(crate) fn show(&self, manager: &ToastManager) -> crate::Result<()> {
+        match self {
+            Queued::Toast(toast) => manager.show(toast).map(drop),
+            Queued::Template(template, data) => manager.show_template(template, data),
+        }
+    }
+}
+
+struct Deferred<M> {
+    manager: M,
+    queued: Queued,
+    expires_at: Option<Instant>,
+}
+
+/// Queued shows, each with the manager that queued it.
+type Shows<M> = Vec<(M, Queued)>;
+
+/// The shows held back while the session is locked, each with the manager `M` that queued it.
+struct LockQueue<M>(VecDeque<Deferred<M>>);
+
+impl<M> LockQueue<M> {
+    fn push(&mut self, manager: M, queued: Queued, now: Instant) {
+        let expires_at = queued.toast().expires_in.map(|expires_in| now + expires_in);
+        self.0.push_back(Deferred {
+            manager,
+            queued,
+            expires_at,
+        });
+    }
+
+    /// Remove all queued shows, and return the ones that are still due, in order and with only
+    /// their remaining expiration time, and the ones that expired while queued.
+    fn take(&mut self, now: Instant) -> (Shows<M>, Shows<M>) {
+        let mut due = Vec::with_capacity(self.0.len());
+        let mut expired = Vec::new();
+        for Deferred {
+            manager,
+            mut queued,
+            expires_at,
+        } in self.0.drain(..)
+        {
+            if let Some(expires_at) = expires_at {
+                match expires_at.checked_duration_since(now) {
+                    Some(remaining) if !remaining.is_zero() => {
+                        queued.toast_mut().expires_in = Some(remaining)
+                    }
+                    _ => {
+                        expired.push((manager, queued));
+                        continue;
+                    }
+                }
+            }
+            due.push((manager, queued));
+        }
+        (due, expired)
+    }
+}
+
+/// Toasts held back while the session is locked.
+///
+/// A listener thread is notified by Windows when the session is unlocked, and shows the queued
+/// toasts in order then. It only holds a weak reference to the queue, and its message loop is
+/// stopped when the last manager sharing the queue is dropped. Where session notifications
+/// are unavailable, the listener checks the lock state every second instead.
+pub(crate) struct LockDeferral {
+    queue: Mutex<LockQueue<ToastManager>>,
+    /// The thread ID of the listener, if it runs a message loop.
+    listener: OnceLock<u32>,
+}
+
+impl LockDeferral {
+    pub(crate) fn start() -> Arc<Self> {
+        let deferral = Arc::new(Self {
+            queue: Mutex::new(LockQueue(VecDeque::new())),
+            listener: OnceLock::new(),
+        });
+
+        let weak = Arc::downgrade(&deferral);
+        let (ready, listening) = mpsc::channel();
+        thread::spawn(move || Self::listen(weak, ready));
+        // Wait for the registration, so that no unlock after this returns is missed.
+        if let Ok(Some(thread)) = listening.recv() {
+            let _ = deferral.listener.set(thread);
+        }
+
+        deferral
+    }
+
+    fn listen(deferral: Weak<Self>, ready: mpsc::Sender<Option<u32>>) {
+        let window = match SessionWindow::create() {
+            Ok(window) => window,
+            Err(e) => {
+                log::debug!("Session notifications are unavailable, polling the lock state: {e}");
+                let _ = ready.send(None);
+                return Self::poll(deferral);
+            }
+        };
+        let _ = ready.send(Some(unsafe { GetCurrentThreadId() }));
+        window.run(|| {
+            if let Some(deferral) = deferral.upgrade() {
+                deferral.flush(Instant::now());
+            }
+        });
+    }
+
+    fn poll(deferral: Weak<Self>) {
+        loop {
+            thread::sleep(POLL_INTERVAL);
+            let Some(deferral) = deferral.upgrade() else {
+                return;
+            };
+            if !is_locked() {
+                deferral.flush(Instant::now());
+            }
+        }
+    }
+
+    /// Queue a show by `manager` until the session is unlocked.
+    pub(crate) fn push(&self, manager: ToastManager, queued: Queued) {
+        self.queue.lock().unwrap().push(manager, queued, Instant::now());
+    }
+
+    /// Show all queued toasts in order, dropping the ones that expired while queued.
+    pub(crate) fn flush(&self, now: Instant) {
+        for (manager, queued) in self.take(now) {
+            let _ = queued.show(&manager);
+        }
+    }
+
+    /// Remove all queued toasts, dropping the ones that expired while queued.
+    ///
+    /// Expired toasts are reported to their manager as undeliverable. The others are returned in order, with the manager that queued them
+    /// and only their remaining expiration time.
+    pub(crate) fn take(&self, now: Instant) -> Vec<(ToastManager, Queued)> {
+        let (due, expired) = self.queue.lock().unwrap().take(now);
+        for (manager, queued) in expired {
+            manager.undeliverable(queued.toast(), UndeliverableReason::ExpiredWhileLocked);
+        }
+        due
+    }
+}
+
+impl Drop for LockDeferral {
+    fn drop(&mut self) {
+        if let Some(&thread) = self.listener.get() {
+            // This fails if the listener already stopped, which is fine.
+            let _ = unsafe { PostThreadMessageW(thread, WM_QUIT, WPARAM(0), LPARAM(0)) };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn queued(text: &str, expires_in: Option<Duration>) -> Queued {
+        let mut toast = Toast::new();
+        toast.text1(text);
+        toast.expires_in = expires_in;
+        Queued::Toast(toast)
+    }
+
+    fn texts(shows: &[(u8, Queued)]) -> Vec<(u8, &str)> {
+        shows
+            .iter()
+            .map(|(manager, queued)| {
+                let text = queued.toast().text_elements().next().unwrap();
+                (*manager, &*text.content)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn toasts_queued_while_locked_are_due_in_order_on_unlock() {
+        let now = Instant::now();
+        let mut queue = LockQueue(VecDeque::new());
+        queue.push(1, queued("first", None), now);
+        queue.push(2, queued("second", None), now);
+        queue.push(1, queued("third", None), now);
+
+        let (due, expired) = queue.take(now + Duration::from_secs(3600));
+        assert_eq!(texts(&due), [(1, "first"), (2, "second"), (1, "third")]);
+        assert!(expired.is_empty());
+        assert!(queue.0.is_empty());
+    }
+
+    #[test]
+    fn toasts_that_expired_while_locked_are_dropped() {
+        let now = Instant::now();
+        let mut queue = LockQueue(VecDeque::new());
+        queue.push(1, queued("short", Some(Duration::from_secs(60))), now);
+        queue.push(2, queued("long", Some(Duration::from_secs(600))), now);
+        queue.push(3, queued("exact", Some(Duration::from_secs(120))), now);
+
+        let (due, expired) = queue.take(now + Duration::from_secs(120));
+        assert_eq!(texts(&due), [(2, "long")]);
+        assert_eq!(due[0].1.toast().expires_in, Some(Duration::from_secs(480)));
+        assert_eq!(texts(&expired), [(1, "short"), (3, "exact")]);
+    }
+
+    #[test]
+    fn a_taken_queue_starts_over() {
+        let now = Instant::now();
+        let mut queue = LockQueue(VecDeque::new());
+        queue.push(1, queued("before", None), now);
+        queue.take(now);
+        queue.push(1, queued("after", None), now);
+        assert_eq!(texts(&queue.take(now).0), [(1, "after")]);
+    }
+}
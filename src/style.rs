@@ -0,0 +1,142 @@
+use crate::{
+    content::audio::{LoopingSound, Sound},
+    Audio, Scenario, Toast, ToastDuration, ToastPriority,
+};
+
+/// A bundle of the presentation properties of a toast, applied with [`Toast::styled`].
+///
+/// A style describes how a kind of notification behaves, independent of its content:
+/// its scenario, duration, audio, button style and priority. The presets cover common
+/// archetypes, and custom styles are built from [`ToastStyle::new`] with the `with_*` methods.
+///
+/// With the `serde` feature, styles can be serialized, e.g. to ship them as data.
+/// Missing fields deserialize to the values of [`ToastStyle::new`].
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct ToastStyle {
+    scenario: Option<Scenario>,
+    duration: Option<ToastDuration>,
+    audio: Option<Audio>,
+    use_button_style: bool,
+    priority: Option<ToastPriority>,
+}
+
+impl ToastStyle {
+    /// Create a style that leaves every property of the toast as it is.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A style for informational toasts: a short toast with the default sound.
+    pub fn info() -> Self {
+        Self::new().with_duration(ToastDuration::Short)
+    }
+
+    /// A style for toasts that ask the user to act: a long toast with button styles
+    /// and the reminder sound.
+    pub fn actionable() -> Self {
+        Self::new()
+            .with_duration(ToastDuration::Long)
+            .with_audio(Audio::new(Sound::Reminder))
+            .with_button_style()
+    }
+
+    /// A style for critical toasts: an urgent, high-priority toast with button styles and
+    /// a looping alarm sound, which breaks through Focus Assist if the user allows it.
+    pub fn critical() -> Self {
+        Self::new()
+            .with_scenario(Scenario::Urgent)
+            .with_duration(ToastDuration::Long)
+            .with_audio(Audio::new(Sound::Looping(LoopingSound::Alarm)).with_looping())
+            .with_button_style()
+            .with_priority(ToastPriority::High)
+    }
+
+    /// Set the scenario, see [`Toast::scenario`].
+    pub fn with_scenario(mut self, scenario: Scenario) -> Self {
+        self.scenario = Some(scenario);
+        self
+    }
+
+    /// Set the duration, see [`Toast::duration`].
+    pub fn with_duration(mut self, duration: ToastDuration) -> Self {
+        self.duration = Some(duration);
+        self
+    }
+
+    /// Set the audio, see [`Toast::audio`].
+    pub fn with_audio(mut self, audio: Audio) -> Self {
+        self.audio = Some(audio);
+        self
+    }
+
+    /// Enable the button style, see [`Toast::use_button_style`].
+    pub fn with_button_style(mut self) -> Self {
+        self.use_button_style = true;
+        self
+    }
+
+    /// Set the priority, see [`Toast::priority`].
+    pub fn with_priority(mut self, priority: ToastPriority) -> Self {
+        self.priority = Some(priority);
+        self
+    }
+}
+
+impl Toast {
+    /// Apply the properties of `style` to this toast.
+    ///
+    /// Properties the style doesn't set are left as they are. Call this before setting
+    /// the content of the toast: later calls to [`Toast::scenario`], [`Toast::duration`],
+    /// [`Toast::audio`] and [`Toast::priority`] override the style.
+    pub fn styled(&mut self, style: ToastStyle) -> &mut Toast {
+        if let Some(scenario) = style.scenario {
+            self.scenario = Some(scenario);
+        }
+        if let Some(duration) = style.duration {
+            self.duration = Some(duration);
+        }
+        if let Some(audio) = style.audio {
+            self.audio = Some(audio);
+        }
+        if style.use_button_style {
+            self.use_button_style();
+        }
+        if let Some(priority) = style.priority {
+            self.priority = priority;
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn leaves_unset_properties_alone() {
+        let mut toast = Toast::new();
+        toast
+            .priority(ToastPriority::High)
+            .scenario(Scenario::Reminder)
+            .styled(ToastStyle::info());
+
+        assert_eq!(toast.priority, ToastPriority::High);
+        assert_eq!(toast.scenario, Some(Scenario::Reminder));
+        assert!(matches!(toast.duration, Some(ToastDuration::Short)));
+    }
+
+    #[test]
+    fn sets_the_priority_of_the_style() {
+        let mut toast = Toast::new();
+        toast.styled(ToastStyle::critical());
+        assert_eq!(toast.priority, ToastPriority::High);
+
+        toast.styled(ToastStyle::new().with_priority(ToastPriority::Default));
+        assert_eq!(toast.priority, ToastPriority::Default);
+    }
+}
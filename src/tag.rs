@@ -0,0 +1,222 @@
+use std::{
+    fmt,
+    ops::Deref,
+    sync::atomic::{AtomicU64, Ordering},
+    time::SystemTime,
+};
+
+use crate::{hash, limits::MAX_TAG_LEN, WinToastError};
+
+/// The longest file name [`Tag::file_name`] returns, well below the 255 UTF-16 units
+/// Windows allows for a path component.
+const MAX_FILE_NAME_LEN: usize = 96;
+
+/// A validated toast tag, as reported by Windows in [`ToastDismissed`](crate::ToastDismissed)
+/// and [`HistoryEntry`](crate::HistoryEntry).
+///
+/// Tags often come from outside the app, e.g. from the id of a remote conversation,
+/// so treat them as untrusted: compare them with [`Tag::as_str`], and derive file names
+/// with [`Tag::file_name`] rather than joining the tag to a path.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Tag(String);
+
+impl Tag {
+    /// Check that `tag` is a tag Windows accepts: not empty, at most
+    /// [`MAX_TAG_LEN`](crate::limits::MAX_TAG_LEN) UTF-16 units long, and without
+    /// control characters.
+    pub fn new(tag: impl Into<String>) -> crate::Result<Self> {
+        let tag = tag.into();
+        let problem = if tag.is_empty() {
+            "it is empty".to_string()
+        } else if tag.encode_utf16().count() > MAX_TAG_LEN {
+            format!("it is longer than {} characters", MAX_TAG_LEN)
+        } else if let Some(c) = tag.chars().find(|c| c.is_control()) {
+            format!("it contains the control character {:?}", c)
+        } else {
+            return Ok(Self(tag));
+        };
+
+        Err(WinToastError::InvalidTag(format!("{:?}: {}", tag, problem)))
+    }
+
+    /// The tag as a string.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// The tag as a string, consuming the tag.
+    pub fn into_string(self) -> String {
+        self.0
+    }
+
+    /// A file name derived from the tag, safe to join to a directory.
+    ///
+    /// The name starts with `tag-`, so it is never a reserved device name like `CON`,
+    /// and contains only ASCII letters, digits, `-`, `_` and `~`: every other character,
+    /// including path separators and `.`, is escaped as `~` followed by its code point in hex
+    /// and `~`. Different tags give different names, except that names which would be longer
+    /// than 96 characters are shortened and end with a hash of the whole tag instead.
+    pub fn file_name(&self) -> String {
+        let mut name = String::from("tag-");
+        for c in self.0.chars() {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                name.push(c);
+            } else {
+                name.push_str(&format!("~{:x}~", c as u32));
+            }
+        }
+        if name.len() > MAX_FILE_NAME_LEN {
+            let hash = hash::fnv1a([self.0.as_bytes()]);
+            // Only ASCII was pushed, so any index is a char boundary.
+            name.truncate(MAX_FILE_NAME_LEN - 17);
+            name.push_str(&format!("-{:016x}", hash));
+        }
+        name
+    }
+
+    /// Parse a tag reported by Windows, `None` if there is no tag or it fails [`Tag::new`].
+    pub(crate) fn from_os(tag: windows::core::Result<windows::core::HSTRING>) -> Option<Self> {
+        let tag = tag.ok()?;
+        Self::new(String::from_utf16(tag.as_wide()).ok()?).ok()
+    }
+}
+
+impl Deref for Tag {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for Tag {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl PartialEq<str> for Tag {
+    fn eq(&self, other: &str) -> bool {
+        self.0 == other
+    }
+}
+
+impl PartialEq<&str> for Tag {
+    fn eq(&self, other: &&str) -> bool {
+        self.0 == *other
+    }
+}
+
+impl fmt::Display for Tag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<Tag> for String {
+    fn from(tag: Tag) -> String {
+        tag.0
+    }
+}
+
+impl TryFrom<String> for Tag {
+    type Error = WinToastError;
+
+    fn try_from(tag: String) -> crate::Result<Self> {
+        Self::new(tag)
+    }
+}
+
+impl TryFrom<&str> for Tag {
+    type Error = WinToastError;
+
+    fn try_from(tag: &str) -> crate::Result<Self> {
+        Self::new(tag)
+    }
+}
+
+/// A tag that no other toast of this app is likely to have, for toasts shown without one.
+///
+/// The tag hashes the process id, the current time and a counter, so it differs between
+/// calls and between processes of the same app.
+pub(crate) fn generate() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+    let nanos = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_nanos());
+    let hash = hash::fnv1a([
+        &std::process::id().to_le_bytes()[..],
+        &nanos.to_le_bytes(),
+        &count.to_le_bytes(),
+    ]);
+    format!("{:016x}", hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_safe_file_name(name: &str) -> bool {
+        name.starts_with("tag-")
+            && name.len() <= MAX_FILE_NAME_LEN
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '~'))
+    }
+
+    #[test]
+    fn rejects_invalid_tags() {
+        assert!(Tag::new("").is_err());
+        assert!(Tag::new("a\u{0}b").is_err());
+        assert!(Tag::new("line\nbreak").is_err());
+        assert!(Tag::new("x".repeat(MAX_TAG_LEN + 1)).is_err());
+        assert!(Tag::new("x".repeat(MAX_TAG_LEN)).is_ok());
+        // Tags are measured in UTF-16 units, and this character takes two.
+        assert!(Tag::new("😀".repeat(MAX_TAG_LEN / 2 + 1)).is_err());
+    }
+
+    #[test]
+    fn file_name_escapes_path_traversal() {
+        let name = Tag::new(r"..\..\x").unwrap().file_name();
+        assert!(is_safe_file_name(&name), "{name}");
+        assert!(!name.contains(".."));
+        assert_ne!(name, Tag::new("../../x").unwrap().file_name());
+    }
+
+    #[test]
+    fn file_name_is_never_a_device_name() {
+        for device in ["CON", "nul", "COM1", "LPT9", "AUX.txt"] {
+            let name = Tag::new(device).unwrap().file_name();
+            assert!(is_safe_file_name(&name), "{name}");
+        }
+        assert_eq!(Tag::new("CON").unwrap().file_name(), "tag-CON");
+    }
+
+    #[test]
+    fn file_name_shortens_long_unicode_tags() {
+        let long = Tag::new("ß".repeat(MAX_TAG_LEN)).unwrap();
+        let longer = Tag::new(format!("{}x", "ß".repeat(MAX_TAG_LEN - 1))).unwrap();
+        let (name, other) = (long.file_name(), longer.file_name());
+
+        assert!(is_safe_file_name(&name), "{name}");
+        assert!(is_safe_file_name(&other), "{other}");
+        assert_ne!(name, other);
+    }
+
+    #[test]
+    fn file_names_differ_for_different_tags() {
+        let names = ["a", "A", "a-b", "a_b", "a b", "a~62~", "ab"]
+            .map(|tag| Tag::new(tag).unwrap().file_name());
+        for (i, name) in names.iter().enumerate() {
+            assert!(!names[i + 1..].contains(name), "{name}");
+        }
+    }
+
+    #[test]
+    fn generated_tags_differ_between_calls() {
+        let tags: std::collections::HashSet<_> = (0..100).map(|_| generate()).collect();
+        assert_eq!(tags.len(), 100);
+        assert!(tags.iter().all(|tag| Tag::new(tag.as_str()).is_ok()));
+    }
+}
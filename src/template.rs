@@ -0,0 +1,317 @@
+use std::sync::OnceLock;
+
+use windows::{
+    core::Interface,
+    Data::Xml::Dom::{XmlDocument, XmlElement},
+    UI::Notifications::{ToastNotificationManager, ToastTemplateType},
+};
+
+use crate::{hs, Toast, WinToastError};
+
+/// One of the legacy toast templates built into Windows.
+///
+/// These predate the adaptive `ToastGeneric` template and only support a fixed number
+/// of text lines and at most one image.
+///
+/// See <https://docs.microsoft.com/en-us/uwp/api/windows.ui.notifications.toasttemplatetype>
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LegacyTemplate {
+    /// An image and a single string wrapped across a maximum of three lines of text.
+    ToastImageAndText01,
+    /// An image, one string of bold text on the first line,
+    /// and one string of regular text wrapped across the second and third lines.
+    ToastImageAndText02,
+    /// An image, one string of bold text wrapped across the first two lines,
+    /// and one string of regular text on the third line.
+    ToastImageAndText03,
+    /// An image, one string of bold text on the first line,
+    /// and one string of regular text on each of the second and third lines.
+    ToastImageAndText04,
+    /// A single string wrapped across a maximum of three lines of text.
+    ToastText01,
+    /// One string of bold text on the first line,
+    /// and one string of regular text wrapped across the second and third lines.
+    ToastText02,
+    /// One string of bold text wrapped across the first two lines,
+    /// and one string of regular text on the third line.
+    ToastText03,
+    /// One string of bold text on the first line,
+    /// and one string of regular text on each of the second and third lines.
+    ToastText04,
+}
+
+impl LegacyTemplate {
+    /// The number of text elements in this template.
+    pub fn text_slots(&self) -> usize {
+        match self {
+            LegacyTemplate::ToastImageAndText01 | LegacyTemplate::ToastText01 => 1,
+            LegacyTemplate::ToastImageAndText02
+            | LegacyTemplate::ToastImageAndText03
+            | LegacyTemplate::ToastText02
+            | LegacyTemplate::ToastText03 => 2,
+            LegacyTemplate::ToastImageAndText04 | LegacyTemplate::ToastText04 => 3,
+        }
+    }
+
+    /// Whether this template contains an image element.
+    pub fn has_image(&self) -> bool {
+        matches!(
+            self,
+            LegacyTemplate::ToastImageAndText01
+                | LegacyTemplate::ToastImageAndText02
+                | LegacyTemplate::ToastImageAndText03
+                | LegacyTemplate::ToastImageAndText04
+        )
+    }
+
+    fn as_winrt(&self) -> ToastTemplateType {
+        match self {
+            LegacyTemplate::ToastImageAndText01 => ToastTemplateType::ToastImageAndText01,
+            LegacyTemplate::ToastImageAndText02 => ToastTemplateType::ToastImageAndText02,
+            LegacyTemplate::ToastImageAndText03 => ToastTemplateType::ToastImageAndText03,
+            LegacyTemplate::ToastImageAndText04 => ToastTemplateType::ToastImageAndText04,
+            LegacyTemplate::ToastText01 => ToastTemplateType::ToastText01,
+            LegacyTemplate::ToastText02 => ToastTemplateType::ToastText02,
+            LegacyTemplate::ToastText03 => ToastTemplateType::ToastText03,
+            LegacyTemplate::ToastText04 => ToastTemplateType::ToastText04,
+        }
+    }
+
+    /// Whether the text and images of `toast` fit this template, which has no elements for more
+    /// text lines or for images other than the one with ID 1.
+    fn check_fits(&self, toast: &Toast) -> crate::Result<()> {
+        let texts = [&toast.text.0, &toast.text.1, &toast.text.2];
+        let text_fits = texts
+            .iter()
+            .enumerate()
+            .all(|(index, text)| text.is_none() || index < self.text_slots());
+        let images_fit = toast.images.keys().all(|&id| id == 1 && self.has_image());
+        if text_fits && images_fit {
+            Ok(())
+        } else {
+            Err(WinToastError::TemplateMismatch(*self))
+        }
+    }
+
+    /// Fetch the template from Windows and fill in the text and image elements of `toast`.
+    ///
+    /// Returns [`WinToastError::TemplateMismatch`] instead of leaving out text or images that
+    /// were added after [`Toast::from_template`] and have no element in the template.
+    pub(crate) fn load(&self, toast: &Toast) -> crate::Result<XmlDocument> {
+        self.check_fits(toast)?;
+        let toast_doc = ToastNotificationManager::GetTemplateContent(self.as_winrt())?;
+
+        let text_els = toast_doc.GetElementsByTagName(&hs("text"))?;
+        let texts = [&toast.text.0, &toast.text.1, &toast.text.2];
+        for (index, text) in texts.into_iter().enumerate() {
+            if let Some(text) = text {
+                let el = text_els.Item(index as u32)?.cast::<XmlElement>()?;
+                text.write_to_element(index as u8 + 1, &el)?;
+            }
+        }
+
+        if let Some(image) = toast.images.get(&1) {
+            let el = toast_doc
+                .GetElementsByTagName(&hs("image"))?
+                .Item(0)?
+                .cast::<XmlElement>()?;
+            image.write_to_element(1, &el)?;
+        }
+
+        Ok(toast_doc)
+    }
+}
+
+/// A toast rendered once, to be shown many times with different data.
+///
+/// Building the XML document is the bulk of the work done by [`ToastManager::show`](crate::ToastManager::show).
+/// A template does it once up front, so [`ToastManager::show_template`](crate::ToastManager::show_template)
+/// only has to attach the [`ToastData`](crate::ToastData) for the placeholders in the toast.
+/// The silenced document used during [`ToastManager::quiet_hours`](crate::ToastManager::quiet_hours)
+/// and focus sessions is rendered the first time it is needed, and reused as well.
+#[derive(Debug, Clone)]
+pub struct ToastTemplate {
+    pub(crate) toast: Toast,
+    pub(crate) doc: XmlDocument,
+    silenced_doc: OnceLock<XmlDocument>,
+}
+
+impl ToastTemplate {
+    /// Validate and render `toast` into a template.
+    pub fn new(toast: &Toast) -> crate::Result<Self> {
+        toast.validate()?;
+        Ok(Self {
+            toast: toast.clone(),
+            doc: toast.to_document()?,
+            silenced_doc: OnceLock::new(),
+        })
+    }
+
+    /// The document of `toast`, which is the toast of this template with the `applied`
+    /// adjustments of the manager, see [`TemplateDoc::for_adjustments`].
+    pub(crate) fn doc_for(&self, toast: &Toast, applied: &[&str]) -> crate::Result<XmlDocument> {
+        match TemplateDoc::for_adjustments(applied) {
+            TemplateDoc::Rendered => Ok(self.doc.clone()),
+            TemplateDoc::Silenced => {
+                if let Some(doc) = self.silenced_doc.get() {
+                    return Ok(doc.clone());
+                }
+                let doc = toast.to_document()?;
+                Ok(self.silenced_doc.get_or_init(|| doc).clone())
+            }
+            TemplateDoc::Render => toast.to_document(),
+        }
+    }
+
+    /// The toast this template was rendered from.
+    pub fn toast(&self) -> &Toast {
+        &self.toast
+    }
+}
+
+/// Which document of a [`ToastTemplate`] fits its toast after the adjustments of the manager.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TemplateDoc {
+    /// The rendered document: the adjustments only set properties of the notification,
+    /// such as its group or popup suppression.
+    Rendered,
+    /// The silenced document, rendered once: the adjustments silence the toast.
+    Silenced,
+    /// Neither: the adjustments change the XML in other ways, so the toast is rendered again.
+    Render,
+}
+
+impl TemplateDoc {
+    /// The document for the adjustments `applied`, named as in
+    /// [`ShowReport::applied_defaults`](crate::ShowReport::applied_defaults).
+    pub(crate) fn for_adjustments(applied: &[&str]) -> Self {
+        let mut xml_changes = applied
+            .iter()
+            .filter(|name| !matches!(**name, "default_group" | "respect_focus_assist"))
+            .peekable();
+        if xml_changes.peek().is_none() {
+            TemplateDoc::Rendered
+        } else if xml_changes
+            .all(|name| matches!(*name, "quiet_hours" | "quiet_during_focus_sessions"))
+        {
+            TemplateDoc::Silenced
+        } else {
+            TemplateDoc::Render
+        }
+    }
+}
+
+#[cfg(feature = "winrt-interop")]
+impl From<LegacyTemplate> for ToastTemplateType {
+    fn from(template: LegacyTemplate) -> Self {
+        template.as_winrt()
+    }
+}
+
+#[cfg(feature = "winrt-interop")]
+impl TryFrom<ToastTemplateType> for LegacyTemplate {
+    type Error = WinToastError;
+
+    fn try_from(template: ToastTemplateType) -> crate::Result<Self> {
+        match template {
+            ToastTemplateType::ToastImageAndText01 => Ok(LegacyTemplate::ToastImageAndText01),
+            ToastTemplateType::ToastImageAndText02 => Ok(LegacyTemplate::ToastImageAndText02),
+            ToastTemplateType::ToastImageAndText03 => Ok(LegacyTemplate::ToastImageAndText03),
+            ToastTemplateType::ToastImageAndText04 => Ok(LegacyTemplate::ToastImageAndText04),
+            ToastTemplateType::ToastText01 => Ok(LegacyTemplate::ToastText01),
+            ToastTemplateType::ToastText02 => Ok(LegacyTemplate::ToastText02),
+            ToastTemplateType::ToastText03 => Ok(LegacyTemplate::ToastText03),
+            ToastTemplateType::ToastText04 => Ok(LegacyTemplate::ToastText04),
+            _ => Err(WinToastError::UnknownTemplateType(template.0)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Image, Text};
+
+    fn image() -> Image {
+        Image::new_local(std::env::temp_dir().join("winrt-toast-reborn-missing.png")).unwrap()
+    }
+
+    #[test]
+    fn content_from_the_template_fits() {
+        let template = LegacyTemplate::ToastImageAndText02;
+        let toast = Toast::from_template(template, &["Title", "Body"], Some(&image())).unwrap();
+        assert!(template.check_fits(&toast).is_ok());
+    }
+
+    #[test]
+    fn images_other_than_the_first_do_not_fit() {
+        let template = LegacyTemplate::ToastImageAndText01;
+        let mut toast = Toast::from_template(template, &["Body"], Some(&image())).unwrap();
+        toast.image(2, image());
+        assert!(matches!(
+            template.check_fits(&toast),
+            Err(WinToastError::TemplateMismatch(
+                LegacyTemplate::ToastImageAndText01
+            ))
+        ));
+    }
+
+    #[test]
+    fn images_do_not_fit_text_only_templates() {
+        let template = LegacyTemplate::ToastText01;
+        let mut toast = Toast::from_template(template, &["Body"], None).unwrap();
+        toast.image(1, image());
+        assert!(template.check_fits(&toast).is_err());
+    }
+
+    #[test]
+    fn text_beyond_the_slots_does_not_fit() {
+        let template = LegacyTemplate::ToastText02;
+        let mut toast = Toast::from_template(template, &["Title", "Body"], None).unwrap();
+        toast.text3(Text::new("More"));
+        assert!(template.check_fits(&toast).is_err());
+    }
+
+    #[cfg(feature = "winrt-interop")]
+    #[test]
+    fn unknown_template_types_are_reported() {
+        for template in [
+            LegacyTemplate::ToastImageAndText01,
+            LegacyTemplate::ToastText04,
+        ] {
+            assert_eq!(
+                LegacyTemplate::try_from(template.as_winrt()).unwrap(),
+                template
+            );
+        }
+        assert!(matches!(
+            LegacyTemplate::try_from(ToastTemplateType(99)),
+            Err(WinToastError::UnknownTemplateType(99))
+        ));
+    }
+
+    #[test]
+    fn template_docs_are_reused_unless_the_xml_changes() {
+        assert_eq!(TemplateDoc::for_adjustments(&[]), TemplateDoc::Rendered);
+        assert_eq!(
+            TemplateDoc::for_adjustments(&["default_group", "respect_focus_assist"]),
+            TemplateDoc::Rendered
+        );
+        assert_eq!(
+            TemplateDoc::for_adjustments(&["default_group", "quiet_hours"]),
+            TemplateDoc::Silenced
+        );
+        assert_eq!(
+            TemplateDoc::for_adjustments(&["quiet_hours", "quiet_during_focus_sessions"]),
+            TemplateDoc::Silenced
+        );
+        assert_eq!(
+            TemplateDoc::for_adjustments(&["auto_anchor_inputs"]),
+            TemplateDoc::Render
+        );
+        assert_eq!(
+            TemplateDoc::for_adjustments(&["quiet_hours", "auto_anchor_inputs"]),
+            TemplateDoc::Render
+        );
+    }
+}
@@ -0,0 +1,175 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use windows::Foundation::DateTime;
+
+use crate::WinToastError;
+
+/// The number of 100-nanosecond ticks between 1601-01-01 and 1970-01-01, both UTC.
+const UNIX_EPOCH_TICKS: i64 = 116_444_736_000_000_000;
+
+/// Convert a [`SystemTime`] to a WinRT [`DateTime`], which counts 100-nanosecond ticks since 1601-01-01 UTC.
+///
+/// Sub-tick precision is truncated towards 1601.
+/// Returns [`WinToastError::TimeConversion`] for times before 1601 or too far in the future to fit.
+pub fn to_winrt_datetime(time: SystemTime) -> crate::Result<DateTime> {
+    let ticks = match time.duration_since(UNIX_EPOCH) {
+        Ok(since) => i64::try_from(since.as_nanos() / 100)
+            .ok()
+            .and_then(|ticks| ticks.checked_add(UNIX_EPOCH_TICKS)),
+        Err(before) => i64::try_from(before.duration().as_nanos().div_ceil(100))
+            .ok()
+            .map(|ticks| UNIX_EPOCH_TICKS - ticks)
+            .filter(|ticks| *ticks >= 0),
+    };
+
+    match ticks {
+        Some(ticks) => Ok(DateTime {
+            UniversalTime: ticks,
+        }),
+        None => Err(WinToastError::TimeConversion),
+    }
+}
+
+/// Convert a WinRT [`DateTime`] to a [`SystemTime`].
+///
+/// Returns [`WinToastError::TimeConversion`] for times before 1601 or outside the range of [`SystemTime`].
+pub fn from_winrt_datetime(time: DateTime) -> crate::Result<SystemTime> {
+    if time.UniversalTime < 0 {
+        return Err(WinToastError::TimeConversion);
+    }
+
+    let offset = time.UniversalTime - UNIX_EPOCH_TICKS;
+    let ticks = offset.unsigned_abs();
+    let since = Duration::new(ticks / 10_000_000, (ticks % 10_000_000 * 100) as u32);
+    let time = if offset >= 0 {
+        UNIX_EPOCH.checked_add(since)
+    } else {
+        UNIX_EPOCH.checked_sub(since)
+    };
+    time.ok_or(WinToastError::TimeConversion)
+}
+
+/// The WinRT [`DateTime`] `duration` after `start`.
+///
+/// This is how the expiration time of a toast is computed from [`Toast::expires_in`](crate::Toast::expires_in),
+/// with `start` being the moment the toast is shown.
+/// Returns [`WinToastError::TimeConversion`] if the result is out of range.
+pub fn after(start: SystemTime, duration: Duration) -> crate::Result<DateTime> {
+    let time = start
+        .checked_add(duration)
+        .ok_or(WinToastError::TimeConversion)?;
+    to_winrt_datetime(time)
+}
+
+/// Format `time` as an ISO 8601 UTC timestamp such as `2017-04-15T19:45:00Z`, as used by toast XML.
+///
+/// Sub-second precision is truncated towards 1970.
+/// Returns [`WinToastError::TimeConversion`] for times outside the years 0 to 9999.
+pub fn to_iso8601(time: SystemTime) -> crate::Result<String> {
+    let secs = match time.duration_since(UNIX_EPOCH) {
+        Ok(since) => i64::try_from(since.as_secs()).ok(),
+        Err(before) => i64::try_from(before.duration().as_nanos().div_ceil(1_000_000_000))
+            .ok()
+            .map(|secs| -secs),
+    }
+    .ok_or(WinToastError::TimeConversion)?;
+
+    let (days, secs_of_day) = (secs.div_euclid(86_400), secs.rem_euclid(86_400));
+    let (year, month, day) = civil_from_days(days);
+    if !(0..=9999).contains(&year) {
+        return Err(WinToastError::TimeConversion);
+    }
+
+    Ok(format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        secs_of_day / 3600,
+        secs_of_day / 60 % 60,
+        secs_of_day % 60
+    ))
+}
+
+/// The proleptic Gregorian date `days` after 1970-01-01, after Howard Hinnant's `civil_from_days`.
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// The WinRT [`DateTime`] `duration` from now.
+pub(crate) fn from_now(duration: Duration) -> crate::Result<DateTime> {
+    after(SystemTime::now(), duration)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn winrt_datetime_round_trips() {
+        for time in [
+            UNIX_EPOCH,
+            UNIX_EPOCH + Duration::new(1_492_285_500, 123_456_700),
+            UNIX_EPOCH - Duration::from_secs(86_400 * 365),
+        ] {
+            assert_eq!(
+                from_winrt_datetime(to_winrt_datetime(time).unwrap()).unwrap(),
+                time
+            );
+        }
+    }
+
+    #[test]
+    fn winrt_datetime_is_counted_from_1601() {
+        let epoch = to_winrt_datetime(UNIX_EPOCH).unwrap();
+        assert_eq!(epoch.UniversalTime, UNIX_EPOCH_TICKS);
+        let start = DateTime { UniversalTime: 0 };
+        let expected = UNIX_EPOCH - Duration::from_secs(11_644_473_600);
+        assert_eq!(from_winrt_datetime(start).unwrap(), expected);
+    }
+
+    #[test]
+    fn far_future_winrt_datetime_does_not_overflow() {
+        let max = DateTime {
+            UniversalTime: i64::MAX,
+        };
+        let since = Duration::from_secs(i64::MAX as u64 / 10_000_000 - 11_644_473_600);
+        let time = from_winrt_datetime(max).unwrap();
+        assert!(time.duration_since(UNIX_EPOCH).unwrap() >= since);
+    }
+
+    #[test]
+    fn winrt_datetime_before_1601_is_rejected() {
+        let before = DateTime { UniversalTime: -1 };
+        assert!(matches!(
+            from_winrt_datetime(before),
+            Err(WinToastError::TimeConversion)
+        ));
+        let time = UNIX_EPOCH - Duration::from_secs(11_644_473_601);
+        assert!(matches!(
+            to_winrt_datetime(time),
+            Err(WinToastError::TimeConversion)
+        ));
+    }
+
+    #[test]
+    fn iso8601_formats_utc() {
+        let time = UNIX_EPOCH + Duration::new(1_492_285_500, 999_999_999);
+        assert_eq!(to_iso8601(time).unwrap(), "2017-04-15T19:45:00Z");
+        assert_eq!(to_iso8601(UNIX_EPOCH).unwrap(), "1970-01-01T00:00:00Z");
+        let leap_day = UNIX_EPOCH + Duration::from_secs(951_782_400);
+        assert_eq!(to_iso8601(leap_day).unwrap(), "2000-02-29T00:00:00Z");
+    }
+
+    #[test]
+    fn iso8601_before_1970() {
+        let time = UNIX_EPOCH - Duration::from_millis(500);
+        assert_eq!(to_iso8601(time).unwrap(), "1969-12-31T23:59:59Z");
+    }
+}
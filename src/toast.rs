@@ -1,17 +1,43 @@
 use std::fmt::Debug;
-use std::{collections::HashMap, time::Duration};
+use std::{
+    collections::HashMap,
+    time::{Duration, SystemTime},
+};
 
-use crate::{Action, Audio, Header, Image, Input, Selection, Text};
+use windows::{
+    core::Interface,
+    Data::Xml::Dom::{XmlDocument, XmlElement},
+};
+
+use crate::{
+    content::{
+        self,
+        action::ActivationType,
+        audio::{LoopingSound, Sound},
+        image::{ImageHintCrop, ImagePlacement},
+        is_xml_name,
+        progress::Progress,
+        ElementExt, RawAttributes,
+    },
+    hs, xml, Action, Audio, Header, Image, Input, LegacyTemplate, OverflowStrategy, Selection,
+    SelectionGroup, Text, ValidationIssue, WinToastError,
+};
 
 /// Represents a Windows toast.
 ///
 /// See <https://docs.microsoft.com/en-us/uwp/api/windows.ui.notifications.toastnotification>
 ///
+/// A `Toast` is a value describing a notification, not a handle to a shown one.
+/// Every call to [`ToastManager::show`](crate::ToastManager::show) renders and validates it anew
+/// and creates a separate notification, so the same `Toast` can be shown any number of times.
+/// Whether those notifications coexist is decided by Windows: a notification with the same
+/// tag and group as one still in the Action Center replaces it.
 #[derive(Debug, Clone, Default)]
 pub struct Toast {
     pub(crate) header: Option<Header>,
     pub(crate) text: (Option<Text>, Option<Text>, Option<Text>),
     pub(crate) images: HashMap<u8, Image>,
+    pub(crate) progress: Option<Progress>,
     pub(crate) tag: Option<String>,
     pub(crate) group: Option<String>,
     pub(crate) remote_id: Option<String>,
@@ -24,14 +50,81 @@ pub struct Toast {
     pub(crate) input: Option<Input>,
     pub(crate) selections: Vec<Selection>,
     pub(crate) use_button_style: Option<UseButtonStyle>,
+    pub(crate) template: Option<LegacyTemplate>,
+    pub(crate) raw_attributes: RawAttributes,
+    pub(crate) overflow_strategy: OverflowStrategy,
+    pub(crate) display_timestamp: Option<SystemTime>,
+    pub(crate) suppress_popup: bool,
+    pub(crate) respect_focus_assist: bool,
+    pub(crate) priority: ToastPriority,
+    pub(crate) schema_version: Option<u32>,
+    pub(crate) correlation_id: Option<String>,
 }
 
 impl Toast {
+    /// The attributes of the `<toast>` element that this crate writes itself.
+    pub const RESERVED_ROOT_ATTRIBUTES: &'static [&'static str] = &[
+        "scenario",
+        "launch",
+        "duration",
+        "useButtonStyle",
+        "displayTimestamp",
+    ];
+
     /// Creates an empty toast.
     pub fn new() -> Self {
         Self::default()
     }
 
+    /// Creates a toast from one of the legacy templates built into Windows.
+    ///
+    /// `texts` fill the template's text elements in order, and `image` fills its image element.
+    /// Everything that is not part of the visual content, such as the tag, group, expiration,
+    /// audio, actions and callbacks, still applies as usual.
+    ///
+    /// Returns [`WinToastError::TemplateMismatch`] if the template has fewer text elements
+    /// than `texts`, or no image element while `image` is given.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use winrt_toast_reborn::{LegacyTemplate, Toast, ToastManager};
+    /// let manager = ToastManager::new(ToastManager::POWERSHELL_AUM_ID);
+    /// let toast = Toast::from_template(LegacyTemplate::ToastText02, &["Title", "Body"], None)?;
+    /// manager.show(&toast)?;
+    /// # Ok::<(), winrt_toast_reborn::WinToastError>(())
+    /// ```
+    pub fn from_template(
+        template: LegacyTemplate,
+        texts: &[&str],
+        image: Option<&Image>,
+    ) -> crate::Result<Self> {
+        if texts.len() > template.text_slots() || (image.is_some() && !template.has_image()) {
+            return Err(WinToastError::TemplateMismatch(template));
+        }
+
+        let mut toast = Self {
+            template: Some(template),
+            ..Self::default()
+        };
+        let mut texts = texts.iter().map(|text| Text::new(*text));
+        toast.text = (texts.next(), texts.next(), texts.next());
+        if let Some(image) = image {
+            toast.images.insert(1, image.clone());
+        }
+
+        Ok(toast)
+    }
+
+    /// Set the third text element to `source` as attribution, and the display timestamp to `at`.
+    ///
+    /// This is meant for toasts about events that happened earlier, such as replayed messages:
+    /// the Action Center shows `at` instead of the time the toast was shown, and `source`
+    /// below the body. It replaces any text set with [`Toast::text3`].
+    pub fn attributed(&mut self, source: impl Into<String>, at: SystemTime) -> &mut Toast {
+        self.text3(Text::new(source).info_attribution())
+            .display_timestamp(at)
+    }
+
     /// Add a [`Header`] to this toast.
     pub fn header(&mut self, header: Header) -> &mut Toast {
         self.header = header.into();
@@ -70,37 +163,190 @@ impl Toast {
         self
     }
 
+    /// Set all three text elements from `lines`, in order.
+    ///
+    /// Text elements without a line are cleared.
+    /// Returns [`ValidationIssue::TooManyTexts`] if there
+    /// are more than three lines, in which case the toast is left unchanged.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use winrt_toast_reborn::Toast;
+    /// let lines = vec!["Title".to_string(), "Body".to_string()];
+    /// let mut toast = Toast::new();
+    /// toast.texts(lines)?;
+    /// # Ok::<(), winrt_toast_reborn::WinToastError>(())
+    /// ```
+    pub fn texts<T: Into<Text>>(
+        &mut self,
+        lines: impl IntoIterator<Item = T>,
+    ) -> crate::Result<&mut Toast> {
+        let lines: Vec<Text> = lines.into_iter().map(Into::into).collect();
+        if lines.len() > crate::limits::MAX_TEXTS {
+            return Err(ValidationIssue::TooManyTexts(lines.len()).into());
+        }
+
+        let mut lines = lines.into_iter();
+        self.text = (lines.next(), lines.next(), lines.next());
+        Ok(self)
+    }
+
     /// Add an image with the corresponding ID to the toast.
     ///
     /// # ID
     /// The image element in the toast template that this image is intended for.
     /// If a template has only one image, then this value is 1.
     /// The number of available image positions is based on the template definition.
+    ///
+    /// An image that was added before with the same ID is replaced,
+    /// see [`Toast::try_image`] to catch that instead.
     pub fn image(&mut self, id: u8, image: Image) -> &mut Toast {
         self.images.insert(id, image);
         self
     }
 
+    /// Show a progress bar below the texts and images of the toast.
+    pub fn progress(&mut self, progress: Progress) -> &mut Toast {
+        self.progress = Some(progress);
+        self
+    }
+
+    /// Add an image with the corresponding ID to the toast, unless the ID is already taken.
+    ///
+    /// Returns [`ValidationIssue::DuplicateImageId`] if an image with the same ID was added
+    /// before, in which case the toast is left unchanged. See [`Toast::image`] for the ID.
+    pub fn try_image(&mut self, id: u8, image: Image) -> crate::Result<&mut Toast> {
+        if self.images.contains_key(&id) {
+            return Err(ValidationIssue::DuplicateImageId(id).into());
+        }
+        self.images.insert(id, image);
+        Ok(self)
+    }
+
+    /// Add an image to the toast with a free ID, and return that ID.
+    ///
+    /// Inline images get the lowest free ID, starting at 1. A toast shows at most one hero
+    /// image and one app logo, so an image with [`ImagePlacement::Hero`] or
+    /// [`ImagePlacement::AppLogoOverride`] replaces the one with the same placement added
+    /// before and keeps its ID; otherwise it gets the highest free ID, so that inline images
+    /// still get consecutive IDs.
+    /// Use [`Toast::image`] instead when the ID matters, e.g. for legacy templates.
+    ///
+    /// Returns [`ValidationIssue::NoFreeImageId`] if all 255 IDs are taken.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use winrt_toast_reborn::{content::image::ImagePlacement, Image, Toast};
+    /// # use winrt_toast_reborn::url::Url;
+    /// # let url = Url::parse("https://example.com/a.png").unwrap();
+    /// let mut toast = Toast::new();
+    /// assert_eq!(toast.add_image(Image::new(url.clone()))?, 1);
+    /// let hero = Image::new(url.clone()).with_placement(ImagePlacement::Hero);
+    /// assert_eq!(toast.add_image(hero)?, 255);
+    /// assert_eq!(toast.add_image(Image::new(url))?, 2);
+    /// # Ok::<(), winrt_toast_reborn::WinToastError>(())
+    /// ```
+    pub fn add_image(&mut self, image: Image) -> crate::Result<u8> {
+        let is_free = |id: &u8| !self.images.contains_key(id);
+        let id = match image.placement() {
+            Some(placement) => self
+                .images
+                .iter()
+                .find(|(_, image)| image.placement() == Some(placement))
+                .map(|(id, _)| *id)
+                .or_else(|| (1..=u8::MAX).rev().find(is_free)),
+            None => (1..=u8::MAX).find(is_free),
+        }
+        .ok_or(ValidationIssue::NoFreeImageId)?;
+        self.images.insert(id, image);
+        Ok(id)
+    }
+
+    /// Add `hero` as the hero image and `logo` as a circular app logo, with the next free IDs.
+    ///
+    /// This sets [`ImagePlacement::Hero`] on `hero`, and [`ImagePlacement::AppLogoOverride`]
+    /// with [`ImageHintCrop::Circle`] on `logo`, replacing their placements and crops.
+    ///
+    /// A hero image and app logo added before are replaced, see [`Toast::add_image`], which
+    /// also describes the error returned if no image ID is free.
+    pub fn with_hero_and_logo(&mut self, hero: Image, logo: Image) -> crate::Result<&mut Toast> {
+        self.add_image(hero.with_placement(ImagePlacement::Hero))?;
+        self.add_image(
+            logo.with_placement(ImagePlacement::AppLogoOverride)
+                .with_hint_crop(ImageHintCrop::Circle),
+        )?;
+        Ok(self)
+    }
+
     /// Add an input field to the toast.
     pub fn input(&mut self, input: Input) -> &mut Toast {
         self.input = Some(input);
         self
     }
 
+    /// Add a text input in which pressing Enter presses `send`, as in a quick reply.
+    ///
+    /// Windows submits a text input on Enter only through a button bound to it with
+    /// [`Action::with_input_id`], which becomes the `hint-inputId` attribute. It then shows
+    /// that button next to the input, and Enter activates it exactly like a click: the
+    /// activation carries the arguments of `send` and the text of the input. Without such a
+    /// button, Enter does nothing, and there is no attribute to change this.
+    ///
+    /// This sets `input` as the input of the toast and adds `send` bound to it as the first
+    /// action. `input` should be a text input, and `send` a button, not a context menu item.
+    pub fn quick_reply_enter_submits(&mut self, input: Input, send: Action) -> &mut Toast {
+        self.actions.insert(0, send.with_input_id(&input.id));
+        self.input = Some(input);
+        self
+    }
+
     /// Add a selection field to the toast.
     pub fn selection(&mut self, selection: Selection) -> &mut Toast {
         self.selections.push(selection);
         self
     }
 
+    /// Set `input` as the input of the toast, with the selections of `group`.
+    ///
+    /// This replaces the selections added so far. The default of the group becomes the
+    /// default input, unless `input` already has one. `input` should be a selection input.
+    pub fn selection_input(&mut self, input: Input, group: &SelectionGroup) -> &mut Toast {
+        let input = match (&input.default_input, group.default_id()) {
+            (None, Some(default)) => input.with_default_input(default),
+            _ => input,
+        };
+        self.input = Some(input);
+        self.selections = group.selections().to_vec();
+        self
+    }
+
     /// Add a new action to the toast.
+    ///
+    /// Actions are emitted with all buttons first, followed by all context menu items
+    /// (see [`ActionPlacement::ContextMenu`](crate::content::action::ActionPlacement::ContextMenu)).
+    /// Within each group the insertion order is kept.
     pub fn action(&mut self, action: Action) -> &mut Toast {
         self.actions.push(action);
         self
     }
 
+    /// Add a dismiss button handled by Windows, labeled by Windows in the user's language.
+    ///
+    /// Microsoft recommends an explicit dismiss button for reminders and alarms,
+    /// see [`ValidationIssue::ScenarioWithoutActions`].
+    pub fn with_default_dismiss(&mut self) -> &mut Toast {
+        self.actions
+            .push(Action::new("", "dismiss", "").with_activation_type(ActivationType::System));
+        self
+    }
+
     /// Set the tag of this toast.
     ///
+    /// The tag must pass [`Tag::new`](crate::Tag::new), so that the toast can be found again
+    /// by its tag; [`Toast::validate`] reports other tags as [`ValidationIssue::InvalidTag`]
+    /// or [`ValidationIssue::TagTooLong`], and [`ToastManager::show`](crate::ToastManager::show)
+    /// refuses to show them.
+    ///
     /// See <https://docs.microsoft.com/en-us/windows/apps/design/shell/tiles-and-notifications/send-local-toast-cpp-uwp?tabs=xml#provide-a-primary-key-for-your-toast>
     pub fn tag(&mut self, tag: impl Into<String>) -> &mut Toast {
         self.tag = Some(tag.into());
@@ -115,6 +361,24 @@ impl Toast {
         self
     }
 
+    /// An id that follows the toast from showing it to its activation and dismissal,
+    /// e.g. the id of the request in the server logs that led to the toast.
+    ///
+    /// The id is reported in [`ActivatedAction::correlation_id`](crate::ActivatedAction::correlation_id)
+    /// for activations of the body, the header and the buttons, also through a
+    /// [`ComActivator`](crate::ComActivator) after a restart, and in
+    /// [`ToastDismissed::correlation_id`](crate::ToastDismissed::correlation_id).
+    ///
+    /// For activations, the id is encoded and appended to the launch string and to the
+    /// arguments of the header and the buttons, behind a marker that starts with the
+    /// private use character U+F8FF. It is removed again before the arguments reach
+    /// [`ActivatedAction::arg`](crate::ActivatedAction::arg), but shows in [`Toast::to_xml`].
+    /// Arguments of protocol and system activations are left as they are.
+    pub fn correlation_id(&mut self, id: impl Into<String>) -> &mut Toast {
+        self.correlation_id = Some(id.into());
+        self
+    }
+
     /// Set a remote id for the notification that enables the system to correlate
     /// this notification with another one generated on another device.
     pub fn remote_id(&mut self, remote_id: impl Into<String>) -> &mut Toast {
@@ -136,6 +400,11 @@ impl Toast {
     /// When the user taps or clicks the toast to launch its associated app,
     ///  the launch string provides the context to the app that allows it to show the user a view relevant to the toast content,
     /// rather than launching in its default way.
+    ///
+    /// The string is escaped when the XML is written, so any payload, including `<`, `&` and
+    /// quotes, arrives unchanged in [`ActivatedAction::arg`](crate::ActivatedAction::arg).
+    /// Only control characters other than tab and line breaks can't be represented in XML,
+    /// and are reported by [`Toast::validate`].
     pub fn launch(&mut self, launch: impl Into<String>) -> &mut Toast {
         self.launch = Some(launch.into());
         self
@@ -153,6 +422,24 @@ impl Toast {
         self
     }
 
+    /// Set the priority of this toast.
+    ///
+    /// Windows shows [`ToastPriority::High`] toasts above the others in the Action Center.
+    pub fn priority(&mut self, priority: ToastPriority) -> &mut Toast {
+        self.priority = priority;
+        self
+    }
+
+    /// Pin the version of the toast schema, written as the `version` attribute of `<visual>`.
+    ///
+    /// By default no version is written, and Windows renders the toast with the latest schema
+    /// it supports. Versions outside [`limits::SCHEMA_VERSIONS`](crate::limits::SCHEMA_VERSIONS)
+    /// are reported by [`Toast::validate`].
+    pub fn schema_version(&mut self, version: u32) -> &mut Toast {
+        self.schema_version = Some(version);
+        self
+    }
+
     /// Set the expiration time of this toast, starting from the moment it is shown.
     ///
     /// After expiration, the toast will be removed from the Notification Center.
@@ -161,19 +448,250 @@ impl Toast {
         self
     }
 
+    /// The time shown for this toast in the Action Center, instead of the time it was shown.
+    ///
+    /// Written with second precision. Showing the toast fails with
+    /// [`WinToastError::TimeConversion`] for times outside the years 0 to 9999.
+    pub fn display_timestamp(&mut self, at: SystemTime) -> &mut Toast {
+        self.display_timestamp = Some(at);
+        self
+    }
+
     /// Set the audio for this toast.
     ///
     /// The audio will play when the toast is shown.
+    /// To show the toast without any sound, use [`Toast::silent`].
+    ///
+    /// Without audio, the toast plays the default sound of its scenario,
+    /// see [`Scenario::default_sound`].
     pub fn audio(&mut self, audio: Audio) -> &mut Toast {
         self.audio = Some(audio);
         self
     }
+
+    /// Show this toast without any sound, whatever its scenario.
+    ///
+    /// This writes `<audio silent="true"/>`, the only way to silence the looping default
+    /// sound of alarms and incoming calls.
+    pub fn silent(&mut self) -> &mut Toast {
+        self.audio = Some(Audio::new(Sound::None));
+        self
+    }
+
+    /// Remove the audio set with [`Toast::audio`] or [`Toast::silent`], so the toast plays
+    /// the default sound of its scenario, see [`Scenario::default_sound`].
+    ///
+    /// Note that this is not the same as silence, which is [`Toast::silent`].
+    pub fn clear_audio(&mut self) -> &mut Toast {
+        self.audio = None;
+        self
+    }
+
+    /// Set an arbitrary attribute on the `<toast>` element, emitted verbatim after the attributes this crate supports.
+    ///
+    /// This is an escape hatch for attributes without typed support yet, such as new `hint-*` attributes.
+    /// Names that are not valid XML names, and the attributes written by this crate
+    /// (see [`Toast::RESERVED_ROOT_ATTRIBUTES`]), are reported by [`Toast::validate`].
+    pub fn set_raw_attribute(
+        &mut self,
+        name: impl Into<String>,
+        value: impl Into<String>,
+    ) -> &mut Toast {
+        self.raw_attributes.set(name.into(), value.into());
+        self
+    }
+
+    /// Set a custom attribute on the `<toast>` element, such as a namespaced attribute read
+    /// by a system that post-processes the XML.
+    ///
+    /// Unlike [`Toast::set_raw_attribute`], this rejects names that are not valid XML names
+    /// with [`ValidationIssue::InvalidAttributeName`], and the attributes written by this crate
+    /// (see [`Toast::RESERVED_ROOT_ATTRIBUTES`]) with [`ValidationIssue::ReservedAttributeName`].
+    pub fn root_attribute(
+        &mut self,
+        name: impl Into<String>,
+        value: impl Into<String>,
+    ) -> crate::Result<&mut Toast> {
+        let name = name.into();
+        if !is_xml_name(&name) {
+            return Err(ValidationIssue::InvalidAttributeName(name).into());
+        }
+        if Self::RESERVED_ROOT_ATTRIBUTES.contains(&name.as_str()) {
+            return Err(ValidationIssue::ReservedAttributeName(name).into());
+        }
+        Ok(self.set_raw_attribute(name, value))
+    }
+
+    /// The value of an attribute set with [`Toast::set_raw_attribute`].
+    pub fn raw_attribute(&self, name: &str) -> Option<&str> {
+        self.raw_attributes.get(name)
+    }
+
+    /// The text elements that are set, in order.
+    pub(crate) fn text_elements(&self) -> impl Iterator<Item = &Text> {
+        [&self.text.0, &self.text.1, &self.text.2]
+            .into_iter()
+            .flatten()
+    }
+
+    /// The XML that is handed to Windows when this toast is shown.
+    pub fn to_xml(&self) -> crate::Result<String> {
+        Ok(self.to_document()?.GetXml()?.to_string())
+    }
+
+    /// Build the XML document that is handed to Windows.
+    pub(crate) fn to_document(&self) -> crate::Result<XmlDocument> {
+        if let Some(toast) = self.with_title_moved_to_body() {
+            return toast.to_document();
+        }
+        if let Some(toast) = self.with_correlation_embedded() {
+            return toast.to_document();
+        }
+
+        let (toast_doc, toast_el) = match &self.template {
+            Some(template) => {
+                let toast_doc = template.load(self)?;
+                let toast_el = toast_doc.DocumentElement()?;
+                (toast_doc, toast_el)
+            }
+            None => {
+                let toast_doc = xml::new_document()?;
+                let toast_el = content::append_root(&toast_doc, "toast")?;
+                (toast_doc, toast_el)
+            }
+        };
+
+        if let Some(scenario) = &self.scenario {
+            toast_el.set("scenario", scenario.as_str())?;
+        }
+
+        if let Some(launch) = &self.launch {
+            toast_el.set("launch", launch)?;
+        }
+
+        if let Some(duration) = &self.duration {
+            toast_el.set("duration", duration.as_str())?;
+        }
+
+        if let Some(use_button_style) = &self.use_button_style {
+            toast_el.set("useButtonStyle", use_button_style.as_str())?;
+        }
+
+        if let Some(at) = self.display_timestamp {
+            toast_el.set("displayTimestamp", crate::time::to_iso8601(at)?)?;
+        }
+
+        self.raw_attributes.write_to_element(&toast_el)?;
+
+        // <header>
+        if let Some(header) = &self.header {
+            // A legacy template already has its <visual>, and the header has to come first.
+            let el = toast_el.prepend_element("header")?;
+            header.write_to_element(&el)?;
+        }
+        // </header>
+        // <visual>
+        if self.template.is_some() {
+            if let Some(version) = self.schema_version {
+                let visual_el: XmlElement = toast_doc
+                    .GetElementsByTagName(&hs("visual"))?
+                    .Item(0)?
+                    .cast()?;
+                visual_el.set("version", version.to_string())?;
+            }
+        } else {
+            let visual_el = toast_el.append_element("visual")?;
+            if let Some(version) = self.schema_version {
+                visual_el.set("version", version.to_string())?;
+            }
+            // <binding>
+            {
+                let binding_el = visual_el.append_element("binding")?;
+                binding_el.set("template", "ToastGeneric")?;
+                {
+                    if let Some(text) = &self.text.0 {
+                        let el = binding_el.append_element("text")?;
+                        text.write_to_element(1, &el)?;
+                    }
+                    if let Some(text) = &self.text.1 {
+                        let el = binding_el.append_element("text")?;
+                        text.write_to_element(2, &el)?;
+                    }
+                    if let Some(text) = &self.text.2 {
+                        let el = binding_el.append_element("text")?;
+                        text.write_to_element(3, &el)?;
+                    }
+
+                    for (id, image) in &self.images {
+                        let el = binding_el.append_element("image")?;
+                        image.write_to_element(*id, &el)?;
+                    }
+
+                    if let Some(progress) = &self.progress {
+                        let el = binding_el.append_element("progress")?;
+                        progress.write_to_element(&el)?;
+                    }
+                }
+            }
+            // </binding>
+        }
+        // </visual>
+        // <audio>
+        if let Some(audio) = &self.audio {
+            let audio_el = toast_el.append_element("audio")?;
+            audio.write_to_element(&audio_el)?;
+        }
+        // </audio>
+        // <actions>
+        self.write_actions(&toast_el)?;
+        // </actions>
+
+        Ok(toast_doc)
+    }
+
+    /// Write the `<actions>` element, which holds the input followed by the buttons and context menu items.
+    ///
+    /// The element is only written if there is at least one input or action of any kind,
+    /// since an empty `<actions>` element is invalid.
+    fn write_actions(&self, toast_el: &XmlElement) -> crate::Result<()> {
+        // Buttons come first, then context menu items, each in insertion order.
+        let (buttons, context_menu): (Vec<_>, Vec<_>) =
+            self.actions.iter().partition(|action| !action.is_context_menu());
+
+        if self.input.is_none() && buttons.is_empty() && context_menu.is_empty() {
+            return Ok(());
+        }
+
+        let actions_el = toast_el.append_element("actions")?;
+
+        // <input>
+        if let Some(input) = &self.input {
+            let input_el = actions_el.append_element("input")?;
+            input.write_to_element(&input_el)?;
+            // <selection>
+            for selection in &self.selections {
+                let el = input_el.append_element("selection")?;
+                selection.write_to_element(&el)?;
+            }
+            // </selection>
+        }
+        // </input>
+        // <action>
+        for action in buttons.into_iter().chain(context_menu) {
+            let el = actions_el.append_element("action")?;
+            action.write_to_element(&el)?;
+        }
+        // </action>
+
+        Ok(())
+    }
 }
 
 /// The scenario your toast is used for, like an alarm or reminder.
 ///
 /// See [Microsoft documentation](https://docs.microsoft.com/en-us/windows/apps/design/shell/tiles-and-notifications/adaptive-interactive-toasts?tabs=xml#scenarios).
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Scenario {
     /// A reminder notification. This will be displayed pre-expanded and stay on the user's screen till dismissed.
     Reminder,
@@ -183,6 +701,7 @@ pub enum Scenario {
     /// An incoming call notification.
     /// This will be displayed pre-expanded in a special call format and stay on the user's screen till dismissed.
     /// Audio will loop by default and will use ringtone audio.
+    /// See [`Action::call_accept`] and [`Action::call_decline`] for the buttons.
     IncomingCall,
     /// An important notification.
     /// This allows users to have more control over what apps can send them high-priority toast notifications that can break through Focus Assist (Do not Disturb).
@@ -191,6 +710,23 @@ pub enum Scenario {
 }
 
 impl Scenario {
+    /// The sound Windows plays for a toast with this scenario and no audio.
+    ///
+    /// * Alarms loop [`LoopingSound::Alarm`] until they are dismissed.
+    /// * Incoming calls loop [`LoopingSound::Call`] until they are dismissed.
+    /// * Reminders and urgent toasts have no sound of their own, and play the default
+    ///   notification sound once, like toasts without a scenario. This is `None`.
+    ///
+    /// A toast with audio plays that instead, see [`Toast::audio`]. Looping sounds only
+    /// loop in alarms, incoming calls and toasts with [`ToastDuration::Long`].
+    pub fn default_sound(&self) -> Option<Sound> {
+        match self {
+            Scenario::Alarm => Some(Sound::Looping(LoopingSound::Alarm)),
+            Scenario::IncomingCall => Some(Sound::Looping(LoopingSound::Call)),
+            Scenario::Reminder | Scenario::Urgent => None,
+        }
+    }
+
     pub(crate) fn as_str(&self) -> &'static str {
         match self {
             Scenario::Reminder => "reminder",
@@ -203,10 +739,11 @@ impl Scenario {
 
 /// The amount of time the toast should display
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ToastDuration {
-    /// The toast will display for 25 seconds.
+    /// The toast will display for 25 seconds, see [`limits::LONG_DURATION`](crate::limits::LONG_DURATION).
     Long,
-    /// The toast will display for 7 seconds.
+    /// The toast will display for 7 seconds, see [`limits::SHORT_DURATION`](crate::limits::SHORT_DURATION).
     Short,
 }
 
@@ -219,6 +756,19 @@ impl ToastDuration {
     }
 }
 
+/// The priority of a toast in the Action Center.
+///
+/// See [Microsoft documentation](https://learn.microsoft.com/en-us/uwp/api/windows.ui.notifications.toastnotificationpriority).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ToastPriority {
+    /// The toast is sorted by the time it was shown.
+    #[default]
+    Default,
+    /// The toast is shown above toasts with the default priority.
+    High,
+}
+
 #[derive(Debug, Clone)]
 pub(crate) struct UseButtonStyle(());
 
@@ -227,3 +777,288 @@ impl UseButtonStyle {
         "true"
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use url::Url;
+
+    use super::*;
+
+    fn image() -> Image {
+        Image::new(Url::parse("https://example.com/a.png").unwrap())
+    }
+
+    #[test]
+    fn add_image_assigns_distinct_ids() {
+        let mut toast = Toast::new();
+        let ids: Vec<_> = (0..5).map(|_| toast.add_image(image()).unwrap()).collect();
+        assert_eq!(ids, [1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn add_image_skips_taken_ids() {
+        let mut toast = Toast::new();
+        toast.image(1, image()).image(3, image());
+        assert_eq!(toast.add_image(image()).unwrap(), 2);
+        assert_eq!(toast.add_image(image()).unwrap(), 4);
+    }
+
+    #[test]
+    fn add_image_replaces_hero_and_logo() {
+        let mut toast = Toast::new();
+        toast
+            .with_hero_and_logo(image(), image())
+            .unwrap()
+            .with_hero_and_logo(image(), image())
+            .unwrap();
+        assert_eq!(toast.images.len(), 2);
+        assert_eq!(toast.images[&255].placement(), Some(ImagePlacement::Hero));
+        assert_eq!(
+            toast.images[&254].placement(),
+            Some(ImagePlacement::AppLogoOverride)
+        );
+        assert_eq!(toast.add_image(image()).unwrap(), 1);
+    }
+
+    #[test]
+    fn try_image_keeps_the_image_with_a_taken_id() {
+        let mut toast = Toast::new();
+        let hero = image().with_placement(ImagePlacement::Hero);
+        toast.try_image(1, hero).unwrap();
+        assert!(matches!(
+            toast.try_image(1, image()),
+            Err(WinToastError::Validation(ValidationIssue::DuplicateImageId(1)))
+        ));
+        assert_eq!(toast.images.len(), 1);
+        assert_eq!(toast.images[&1].placement(), Some(ImagePlacement::Hero));
+        toast.try_image(2, image()).unwrap();
+        assert_eq!(toast.images.len(), 2);
+    }
+
+    #[test]
+    fn add_image_fails_once_all_ids_are_taken() {
+        let mut toast = Toast::new();
+        for _ in 1..=u8::MAX {
+            toast.add_image(image()).unwrap();
+        }
+        assert!(matches!(
+            toast.add_image(image()),
+            Err(WinToastError::Validation(ValidationIssue::NoFreeImageId))
+        ));
+        let hero = image().with_placement(ImagePlacement::Hero);
+        assert!(toast.add_image(hero).is_err());
+    }
+
+    #[test]
+    fn validate_rejects_tags_that_cant_be_found_again() {
+        let mut toast = Toast::new();
+        toast.text1("Hello").tag("bad\u{7}tag");
+        assert!(matches!(
+            toast.validate(),
+            Err(WinToastError::Validation(ValidationIssue::InvalidTag(_)))
+        ));
+        toast.tag("");
+        assert!(toast.validate().is_err());
+        toast.tag(r"..\..\x");
+        assert!(toast.validate().is_ok());
+    }
+
+    #[test]
+    fn validate_rejects_reserved_raw_attributes_on_every_element() {
+        let mut toast = Toast::new();
+        toast
+            .text1(Text::new("Hello").with_raw_attribute("id", "7"))
+            .text2(Text::new("World").with_raw_attribute("hint-callScenarioCenterAlign", "true"))
+            .image(
+                1,
+                image().with_raw_attribute("src", "https://example.com/b.png"),
+            )
+            .action(Action::new("Open", "open", "").with_raw_attribute("arguments", "other"))
+            .set_raw_attribute("launch", "other");
+        let mut reserved: Vec<_> = toast
+            .validation_issues()
+            .into_iter()
+            .filter_map(|issue| match issue {
+                ValidationIssue::ReservedAttributeName(name) => Some(name),
+                _ => None,
+            })
+            .collect();
+        reserved.sort();
+        assert_eq!(
+            reserved,
+            [
+                "arguments",
+                "hint-callScenarioCenterAlign",
+                "id",
+                "launch",
+                "src"
+            ]
+        );
+    }
+
+    #[test]
+    fn validate_accepts_unreserved_raw_attributes() {
+        let mut toast = Toast::new();
+        toast
+            .text1(Text::new("Hello").with_raw_attribute("hint-experimental", "true"))
+            .image(1, image().with_raw_attribute("hint-removeMargin", "true"))
+            .action(Action::new("Open", "open", "").with_raw_attribute("imageUri", "a.png"));
+        assert!(toast.validate().is_ok());
+    }
+
+    #[test]
+    fn clear_audio_goes_back_to_the_default_sound() {
+        let mut toast = Toast::new();
+        toast.audio(Audio::new(Sound::Default)).clear_audio();
+        assert!(toast.audio.is_none());
+        toast.silent().clear_audio();
+        assert!(toast.audio.is_none());
+    }
+
+    #[test]
+    fn xml_has_no_audio_after_clear_audio() {
+        let mut toast = Toast::new();
+        toast.text1("Hello").silent();
+        assert!(toast.to_xml().unwrap().contains("<audio"));
+        toast.clear_audio();
+        assert!(!toast.to_xml().unwrap().contains("<audio"));
+    }
+
+    #[test]
+    fn xml_errors_name_the_element_and_attribute() {
+        let mut toast = Toast::new();
+        toast
+            .text1("Hello")
+            .image(2, image().with_raw_attribute("bad name", "x"));
+        let error = toast.to_xml().unwrap_err();
+        assert!(
+            matches!(
+                &error,
+                WinToastError::Render { element, attribute: Some(attribute), .. }
+                    if element == "<image id=2>" && attribute == "bad name"
+            ),
+            "{:?}",
+            error
+        );
+        assert!(error
+            .to_string()
+            .starts_with("Failed to write <image id=2> attribute bad name: "));
+    }
+
+    #[test]
+    fn xml_has_the_schema_version_on_the_visual() {
+        let mut toast = Toast::new();
+        toast.text1("Hello");
+        assert!(toast.to_xml().unwrap().contains("<visual>"));
+        toast.schema_version(2);
+        assert!(toast.to_xml().unwrap().contains(r#"<visual version="2">"#));
+    }
+
+    #[test]
+    fn only_alarms_and_calls_have_a_default_sound() {
+        assert!(matches!(
+            Scenario::Alarm.default_sound(),
+            Some(Sound::Looping(LoopingSound::Alarm))
+        ));
+        assert!(matches!(
+            Scenario::IncomingCall.default_sound(),
+            Some(Sound::Looping(LoopingSound::Call))
+        ));
+        assert!(Scenario::Reminder.default_sound().is_none());
+        assert!(Scenario::Urgent.default_sound().is_none());
+    }
+
+    #[test]
+    fn xml_leaves_the_scenario_sound_to_windows_unless_silent() {
+        let mut toast = Toast::new();
+        toast.text1("Wake up").scenario(Scenario::Alarm);
+        let xml = toast.to_xml().unwrap();
+        assert!(xml.contains(r#"scenario="alarm""#), "{}", xml);
+        assert!(!xml.contains("<audio"), "{}", xml);
+
+        toast.silent();
+        assert!(toast.to_xml().unwrap().contains(r#"<audio silent="true"/>"#));
+    }
+
+    #[test]
+    fn static_content_is_not_copied_into_the_toast() {
+        // Statics have a single address, so a pointer into them proves nothing was copied.
+        static TITLE: &str = "Backup finished";
+        static LABEL: &str = "Open";
+        static ARGUMENTS: &str = "open";
+        const _: Text = Text::from_static("Backup finished");
+        const _: Action = Action::from_static("Open", "open", "");
+
+        let mut toast = Toast::new();
+        toast
+            .text1(Text::from_static(TITLE))
+            .action(Action::from_static(LABEL, ARGUMENTS, ""));
+        let toast = toast.clone();
+        let text = toast.text.0.as_ref().unwrap();
+        assert_eq!(text.content.as_ptr(), TITLE.as_ptr());
+        let action = &toast.actions[0];
+        assert_eq!(action.content().as_ptr(), LABEL.as_ptr());
+        assert_eq!(action.arguments().as_ptr(), ARGUMENTS.as_ptr());
+    }
+
+    #[test]
+    fn xml_escapes_launch_and_arguments() {
+        let payload = r#"<a href="x">&amp; 'quoted'</a>"#;
+        let mut toast = Toast::new();
+        toast
+            .text1("Hello")
+            .launch(payload)
+            .action(Action::new("Open", payload, ""));
+
+        let doc = XmlDocument::new().unwrap();
+        doc.LoadXml(&hs(toast.to_xml().unwrap())).unwrap();
+        for (element, attribute) in [("toast", "launch"), ("action", "arguments")] {
+            let el: XmlElement = doc
+                .GetElementsByTagName(&hs(element))
+                .unwrap()
+                .Item(0)
+                .unwrap()
+                .cast()
+                .unwrap();
+            assert_eq!(el.GetAttribute(&hs(attribute)).unwrap(), payload);
+        }
+    }
+
+    #[test]
+    fn root_attribute_rejects_reserved_and_invalid_names() {
+        let mut toast = Toast::new();
+        for name in Toast::RESERVED_ROOT_ATTRIBUTES {
+            assert!(matches!(
+                toast.root_attribute(*name, "x"),
+                Err(WinToastError::Validation(ValidationIssue::ReservedAttributeName(n)))
+                    if n == *name
+            ));
+        }
+        assert!(matches!(
+            toast.root_attribute("has space", "x"),
+            Err(WinToastError::Validation(ValidationIssue::InvalidAttributeName(_)))
+        ));
+        for name in Toast::RESERVED_ROOT_ATTRIBUTES {
+            assert_eq!(toast.raw_attribute(name), None);
+        }
+
+        toast.root_attribute("acme:route", "billing").unwrap();
+        assert_eq!(toast.raw_attribute("acme:route"), Some("billing"));
+    }
+
+    #[test]
+    fn xml_has_custom_root_attributes() {
+        let mut toast = Toast::new();
+        toast
+            .text1("Hello")
+            .launch("open")
+            .root_attribute("xmlns:acme", "urn:acme")
+            .unwrap()
+            .root_attribute("acme:route", "billing")
+            .unwrap();
+        let xml = toast.to_xml().unwrap();
+        let root = &xml[..xml.find('>').unwrap()];
+        assert!(root.contains(r#"xmlns:acme="urn:acme""#), "{}", xml);
+        assert!(root.contains(r#"acme:route="billing""#), "{}", xml);
+    }
+}
@@ -0,0 +1,548 @@
+use std::path::PathBuf;
+
+use thiserror::Error;
+
+use crate::{
+    content::{image::ImagePlacement, input::InputType, is_xml_char},
+    limits, Action, Image, Input, Scenario, Tag, Text, Toast, WinToastError,
+};
+
+/// A problem found while validating a [`Toast`].
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[non_exhaustive]
+pub enum ValidationIssue {
+    /// The toast has no text, image, progress bar, input or action, so Windows would show a blank toast.
+    ///
+    /// Texts that are empty or only whitespace count as missing. Data-bound texts such as
+    /// `{title}` are not empty, so toasts filled from [`ToastData`](crate::ToastData) pass.
+    /// See [`ToastManager::allow_empty_toasts`](crate::ToastManager::allow_empty_toasts)
+    /// to show such toasts anyway.
+    #[error("The toast has no text, image, input or action")]
+    EmptyToast,
+    /// The toast has more actions than Windows displays.
+    #[error(
+        "The toast has {0} actions, but at most {} are supported",
+        limits::MAX_ACTIONS
+    )]
+    TooManyActions(usize),
+    /// More lines were given than a toast has text elements.
+    #[error(
+        "{0} lines were given, but a toast has at most {} text elements",
+        limits::MAX_TEXTS
+    )]
+    TooManyTexts(usize),
+    /// An image with the same ID was already added to the toast.
+    #[error("An image with the ID {0} was already added")]
+    DuplicateImageId(u8),
+    /// All image IDs from 1 to 255 are taken, so [`Toast::add_image`](crate::Toast::add_image)
+    /// has none left to assign.
+    #[error("All image IDs are taken")]
+    NoFreeImageId,
+    /// The toast has more selections than Windows displays.
+    #[error(
+        "The toast has {0} selections, but at most {} are supported",
+        limits::MAX_SELECTIONS_PER_INPUT
+    )]
+    TooManySelections(usize),
+    /// The toast has selections but no input to show them in.
+    #[error("The toast has selections but no input")]
+    SelectionsWithoutInput,
+    /// The tag is longer than Windows accepts.
+    #[error(
+        "The tag is {0} characters long, but at most {} are supported",
+        limits::MAX_TAG_LEN
+    )]
+    TagTooLong(usize),
+    /// The tag is empty or contains a control character, so [`Tag::new`](crate::Tag::new)
+    /// rejects it and the toast can't be found by its tag once it was shown.
+    #[error("The tag {0:?} is empty or contains a control character")]
+    InvalidTag(String),
+    /// The group is longer than Windows accepts.
+    #[error(
+        "The group is {0} characters long, but at most {} are supported",
+        limits::MAX_GROUP_LEN
+    )]
+    GroupTooLong(usize),
+    /// A local image file does not exist.
+    #[error("The image file {0:?} does not exist")]
+    MissingImageFile(PathBuf),
+    /// A local image file exists, but can't be read, e.g. because access to it is denied.
+    #[error("The image file {0:?} can't be read: {1}")]
+    UnreadableImageFile(PathBuf, std::io::ErrorKind),
+    /// A local image file is larger than [`limits::MAX_IMAGE_FILE_SIZE`],
+    /// so Windows would leave it out.
+    #[error(
+        "The image file {0:?} is {1} bytes large, but at most {} bytes are supported",
+        limits::MAX_IMAGE_FILE_SIZE
+    )]
+    ImageTooLarge(PathBuf, u64),
+    /// A local image is far from the aspect ratio recommended for its placement,
+    /// so Windows crops or letterboxes it. Its width and height are given.
+    ///
+    /// This is a warning, see [`ValidationIssue::is_warning`]. It is only reported with
+    /// the `image-validation` feature.
+    #[error("The image file {0:?} is {2}x{3} pixels, far from the aspect ratio recommended for {1:?} images")]
+    AspectRatioMismatch(PathBuf, ImagePlacement, u32, u32),
+    /// A raw attribute name is not a valid XML name.
+    #[error("{0:?} is not a valid attribute name")]
+    InvalidAttributeName(String),
+    /// An action is bound with [`Action::with_input_id`](crate::Action::with_input_id)
+    /// to an input that the toast does not have.
+    #[error("An action is bound to the input {0:?}, but the toast has no input with that id")]
+    UnknownInputId(String),
+    /// The audio has a loop count of 0, see [`Audio::with_loop_count`](crate::Audio::with_loop_count).
+    #[error("The audio has a loop count of 0, but it must be at least 1")]
+    ZeroLoopCount,
+    /// The default input of a selection input is not the id of any of its selections,
+    /// so Windows shows the input blank. The default and the ids of the selections are given.
+    #[error("The default selection {0:?} is not one of the selections {1:?}")]
+    UnknownDefaultSelection(String, Vec<String>),
+    /// The launch string or the arguments of an action contain a character that XML
+    /// can't hold, such as a NUL or another control character. The name of the value is given.
+    #[error("The {0} contain the character {1:?}, which XML can't hold")]
+    IllegalXmlCharacter(&'static str, char),
+    /// An action with [`ActivationType::Protocol`](crate::content::action::ActivationType::Protocol)
+    /// has arguments that are not an absolute URL, so Windows can't launch anything for it.
+    /// See [`Action::link`](crate::Action::link) to build such actions from a parsed URL.
+    #[error("The protocol action arguments {0:?} are not an absolute URL")]
+    InvalidProtocolUri(String),
+    /// A raw attribute would override one written by this crate itself, see
+    /// [`Toast::RESERVED_ROOT_ATTRIBUTES`] and the `RESERVED_ATTRIBUTES` of the content types.
+    #[error("The attribute {0:?} is managed by this crate")]
+    ReservedAttributeName(String),
+    /// The schema version is outside [`limits::SCHEMA_VERSIONS`], see [`Toast::schema_version`].
+    #[error(
+        "The schema version {0} is unknown, the known versions are {} to {}",
+        limits::SCHEMA_VERSIONS.start(),
+        limits::SCHEMA_VERSIONS.end()
+    )]
+    UnknownSchemaVersion(u32),
+    /// A text input has no action bound to it, so pressing Enter in it does nothing.
+    ///
+    /// This is a warning, see [`ValidationIssue::is_warning`].
+    #[error("No action is bound to the text input {0:?}; consider `Action::with_input_id`")]
+    UnanchoredInput(String),
+    /// A reminder or alarm has no actions, so it can only be dismissed with the close button.
+    ///
+    /// This is a warning, see [`ValidationIssue::is_warning`].
+    #[error("The {0:?} toast has no actions; consider `Toast::with_default_dismiss`")]
+    ScenarioWithoutActions(Scenario),
+    /// An action of an [`Scenario::IncomingCall`] toast has an icon, set with the raw
+    /// `imageUri` attribute, so Windows shows small round buttons instead of wide ones.
+    /// The content of the action is given.
+    ///
+    /// This is a warning, see [`ValidationIssue::is_warning`].
+    #[error("The call button {0:?} has an icon, which makes Windows show it small and round")]
+    CallButtonIcon(String),
+    /// An action has a button style, but the toast doesn't enable
+    /// [`Toast::use_button_style`], so Windows ignores the style.
+    ///
+    /// This is a warning, see [`ValidationIssue::is_warning`].
+    #[error("An action has a button style, but the toast doesn't enable `use_button_style`")]
+    ButtonStyleIgnored,
+}
+
+impl ValidationIssue {
+    /// Whether this issue is only a warning.
+    ///
+    /// Warnings point out likely mistakes in a toast that Windows still shows correctly,
+    /// so they are reported by [`Toast::validation_issues`] but do not fail [`Toast::validate`].
+    pub fn is_warning(&self) -> bool {
+        matches!(
+            self,
+            ValidationIssue::UnanchoredInput(_)
+                | ValidationIssue::ScenarioWithoutActions(_)
+                | ValidationIssue::CallButtonIcon(_)
+                | ValidationIssue::ButtonStyleIgnored
+                | ValidationIssue::AspectRatioMismatch(..)
+        )
+    }
+}
+
+impl Toast {
+    /// Check this toast for mistakes that would make Windows drop or misrender it.
+    ///
+    /// Returns the first issue that is not a warning as [`WinToastError::Validation`].
+    ///
+    /// Most checks only look at the toast itself. The exception is that every local image
+    /// is checked for existence and size, which costs one file system lookup per image.
+    pub fn validate(&self) -> crate::Result<()> {
+        let mut issues = self.validation_issues().into_iter();
+        match issues.find(|issue| !issue.is_warning()) {
+            Some(issue) => Err(WinToastError::Validation(issue)),
+            None => Ok(()),
+        }
+    }
+
+    /// All issues found in this toast, see [`Toast::validate`].
+    pub fn validation_issues(&self) -> Vec<ValidationIssue> {
+        let mut issues = Vec::new();
+
+        let has_text = self
+            .text_elements()
+            .any(|text| !text.content.trim().is_empty());
+        if !has_text
+            && self.images.is_empty()
+            && self.progress.is_none()
+            && self.input.is_none()
+            && self.actions.is_empty()
+        {
+            issues.push(ValidationIssue::EmptyToast);
+        }
+        if self.actions.len() > limits::MAX_ACTIONS {
+            issues.push(ValidationIssue::TooManyActions(self.actions.len()));
+        }
+        if self.selections.len() > limits::MAX_SELECTIONS_PER_INPUT {
+            issues.push(ValidationIssue::TooManySelections(self.selections.len()));
+        }
+        if !self.selections.is_empty() && self.input.is_none() {
+            issues.push(ValidationIssue::SelectionsWithoutInput);
+        }
+        let tag_len = self.tag.as_deref().map(utf16_len);
+        if let Some(len) = tag_len.filter(|len| *len > limits::MAX_TAG_LEN) {
+            issues.push(ValidationIssue::TagTooLong(len));
+        } else if let Some(tag) = self.tag.as_ref().filter(|tag| Tag::new(*tag).is_err()) {
+            issues.push(ValidationIssue::InvalidTag(tag.clone()));
+        }
+        let group_len = self.group.as_deref().map(utf16_len);
+        if let Some(len) = group_len.filter(|len| *len > limits::MAX_GROUP_LEN) {
+            issues.push(ValidationIssue::GroupTooLong(len));
+        }
+        if let Some(version) = self.schema_version {
+            if !limits::SCHEMA_VERSIONS.contains(&version) {
+                issues.push(ValidationIssue::UnknownSchemaVersion(version));
+            }
+        }
+        let payloads = self
+            .launch
+            .iter()
+            .map(|launch| ("launch string", launch.as_str()))
+            .chain(
+                self.actions
+                    .iter()
+                    .map(|a| ("action arguments", a.arguments())),
+            );
+        for (name, payload) in payloads {
+            if let Some(c) = payload.chars().find(|c| !is_xml_char(*c)) {
+                issues.push(ValidationIssue::IllegalXmlCharacter(name, c));
+            }
+        }
+        for uri in self
+            .actions
+            .iter()
+            .filter_map(|action| action.protocol_uri())
+        {
+            if url::Url::parse(uri).is_err() {
+                issues.push(ValidationIssue::InvalidProtocolUri(uri.to_string()));
+            }
+        }
+        if self.audio.as_ref().and_then(|audio| audio.loop_count()) == Some(0) {
+            issues.push(ValidationIssue::ZeroLoopCount);
+        }
+        let input_id = self.input.as_ref().map(|input| input.id.as_str());
+        for id in self.actions.iter().filter_map(|action| action.input_id()) {
+            if Some(id) != input_id {
+                issues.push(ValidationIssue::UnknownInputId(id.to_string()));
+            }
+        }
+        if let Some(input) = &self.input {
+            let default = input.default_input.as_deref();
+            if let (InputType::Selection, Some(default)) = (input.type_, default) {
+                if !self.selections.iter().any(|s| s.id() == default) {
+                    let ids = self.selections.iter().map(|s| s.id().to_string()).collect();
+                    issues.push(ValidationIssue::UnknownDefaultSelection(
+                        default.to_string(),
+                        ids,
+                    ));
+                }
+            }
+        }
+        if let Some(input) = self.unanchored_text_input() {
+            issues.push(ValidationIssue::UnanchoredInput(input.id.clone()));
+        }
+        if let Some(scenario) = self.scenario {
+            if matches!(scenario, Scenario::Reminder | Scenario::Alarm) && self.actions.is_empty() {
+                issues.push(ValidationIssue::ScenarioWithoutActions(scenario));
+            }
+        }
+        if self.scenario == Some(Scenario::IncomingCall) {
+            let with_icon = self
+                .actions
+                .iter()
+                .filter(|action| action.raw_attribute("imageUri").is_some());
+            for action in with_icon {
+                issues.push(ValidationIssue::CallButtonIcon(
+                    action.content().to_string(),
+                ));
+            }
+        }
+        if self.use_button_style.is_none() && self.actions.iter().any(Action::has_button_style) {
+            issues.push(ValidationIssue::ButtonStyleIgnored);
+        }
+        let raw_attributes =
+            std::iter::once((&self.raw_attributes, Toast::RESERVED_ROOT_ATTRIBUTES))
+                .chain(
+                    self.text_elements()
+                        .map(|text| (&text.raw_attributes, Text::RESERVED_ATTRIBUTES)),
+                )
+                .chain(
+                    self.images
+                        .values()
+                        .map(|image| (&image.raw_attributes, Image::RESERVED_ATTRIBUTES)),
+                )
+                .chain(
+                    self.actions
+                        .iter()
+                        .map(|action| (&action.raw_attributes, Action::RESERVED_ATTRIBUTES)),
+                );
+        for (attributes, reserved) in raw_attributes {
+            for name in attributes.invalid_names() {
+                issues.push(ValidationIssue::InvalidAttributeName(name.to_string()));
+            }
+            for name in attributes.reserved_names(reserved) {
+                issues.push(ValidationIssue::ReservedAttributeName(name.to_string()));
+            }
+        }
+        for image in self.images.values() {
+            let Some(path) = image.local_path() else {
+                continue;
+            };
+            match path.metadata() {
+                Ok(metadata) if metadata.len() > limits::MAX_IMAGE_FILE_SIZE => {
+                    issues.push(ValidationIssue::ImageTooLarge(path, metadata.len()))
+                }
+                #[cfg(feature = "image-validation")]
+                Ok(_) => {
+                    let placement = image.placement();
+                    let size = placement.and_then(|_| image.dimensions().ok().flatten());
+                    if let (Some(placement), Some((width, height))) = (placement, size) {
+                        if !crate::dimensions::fits(placement, (width, height)) {
+                            issues.push(ValidationIssue::AspectRatioMismatch(
+                                path, placement, width, height,
+                            ));
+                        }
+                    }
+                }
+                #[cfg(not(feature = "image-validation"))]
+                Ok(_) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                    issues.push(ValidationIssue::MissingImageFile(path))
+                }
+                Err(e) => issues.push(ValidationIssue::UnreadableImageFile(path, e.kind())),
+            }
+        }
+
+        issues
+    }
+
+    /// The text input of this toast, if it has actions but none of them is bound to the input.
+    pub(crate) fn unanchored_text_input(&self) -> Option<&Input> {
+        let input = self.input.as_ref().filter(|i| i.type_ == InputType::Text)?;
+        let anchored = self
+            .actions
+            .iter()
+            .any(|action| action.input_id() == Some(input.id.as_str()));
+        (!self.actions.is_empty() && !anchored).then_some(input)
+    }
+
+    /// Panic if this toast is invalid, in debug builds only.
+    ///
+    /// In debug builds this panics with the issue returned by [`Toast::validate`],
+    /// so mistakes surface loudly during development.
+    /// In release builds this does nothing and costs nothing.
+    #[track_caller]
+    pub fn assert_valid(&self) {
+        if cfg!(debug_assertions) {
+            if let Err(e) = self.validate() {
+                panic!("{}", e);
+            }
+        }
+    }
+}
+
+fn utf16_len(s: &str) -> usize {
+    s.encode_utf16().count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Progress, ProgressValue, Selection};
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "The toast has no text, image, input or action")]
+    fn assert_valid_panics_on_invalid_toasts_in_debug_builds() {
+        Toast::new().assert_valid();
+    }
+
+    #[test]
+    #[cfg(not(debug_assertions))]
+    fn assert_valid_does_nothing_in_release_builds() {
+        Toast::new().assert_valid();
+    }
+
+    #[test]
+    fn assert_valid_accepts_valid_toasts() {
+        let mut toast = Toast::new();
+        toast.text1("Hello");
+        toast.assert_valid();
+    }
+
+    #[test]
+    fn toasts_without_content_are_empty() {
+        assert_eq!(
+            Toast::new().validation_issues(),
+            [ValidationIssue::EmptyToast]
+        );
+
+        let mut toast = Toast::new();
+        toast.text1("").text2(" \t\n");
+        assert!(toast
+            .validation_issues()
+            .contains(&ValidationIssue::EmptyToast));
+    }
+
+    #[test]
+    fn data_bound_toasts_are_not_empty() {
+        let mut toast = Toast::new();
+        toast.text1("{title}");
+        assert!(toast.validate().is_ok());
+
+        let mut toast = Toast::new();
+        toast.progress(Progress::new(
+            ProgressValue::Bound("progress".into()),
+            "{status}",
+        ));
+        assert!(toast.validate().is_ok());
+    }
+
+    #[test]
+    fn actions_must_be_bound_to_the_input_of_the_toast() {
+        let mut toast = Toast::new();
+        toast
+            .text1("New message")
+            .input(Input::new("box", InputType::Text))
+            .action(Action::new("Send", "send", "").with_input_id("bx"));
+        assert!(matches!(
+            toast.validate(),
+            Err(WinToastError::Validation(ValidationIssue::UnknownInputId(id))) if id == "bx"
+        ));
+
+        toast.actions.clear();
+        toast.action(Action::new("Send", "send", "").with_input_id("box"));
+        assert!(toast.validation_issues().is_empty());
+    }
+
+    #[test]
+    fn actions_bound_without_an_input_are_rejected() {
+        let mut toast = Toast::new();
+        toast
+            .text1("New message")
+            .action(Action::new("Send", "send", "").with_input_id("box"));
+        assert_eq!(
+            toast.validation_issues(),
+            [ValidationIssue::UnknownInputId("box".into())]
+        );
+    }
+
+    fn issues_with(actions: usize, selections: usize, tag_len: usize) -> Vec<ValidationIssue> {
+        let mut toast = Toast::new();
+        toast
+            .text1("Hello")
+            .input(Input::new("choice", InputType::Selection))
+            .tag("t".repeat(tag_len));
+        for i in 0..actions {
+            toast.action(Action::new(i.to_string(), i.to_string(), ""));
+        }
+        for i in 0..selections {
+            toast.selection(Selection::new(i.to_string(), i.to_string()));
+        }
+        toast.validation_issues()
+    }
+
+    #[test]
+    fn the_limits_are_inclusive() {
+        let issues = issues_with(
+            limits::MAX_ACTIONS,
+            limits::MAX_SELECTIONS_PER_INPUT,
+            limits::MAX_TAG_LEN,
+        );
+        assert!(issues.is_empty(), "{:?}", issues);
+    }
+
+    #[test]
+    fn exceeding_a_limit_is_reported_with_the_count() {
+        let actions = limits::MAX_ACTIONS + 1;
+        let selections = limits::MAX_SELECTIONS_PER_INPUT + 1;
+        let tag_len = limits::MAX_TAG_LEN + 1;
+        assert_eq!(
+            issues_with(actions, selections, tag_len),
+            [
+                ValidationIssue::TooManyActions(actions),
+                ValidationIssue::TooManySelections(selections),
+                ValidationIssue::TagTooLong(tag_len),
+            ]
+        );
+    }
+
+    #[test]
+    fn icons_on_call_buttons_are_reported() {
+        let mut toast = Toast::new();
+        toast
+            .text1("Incoming call")
+            .scenario(Scenario::IncomingCall)
+            .use_button_style()
+            .action(Action::call_accept("Accept", "accept").with_raw_attribute("imageUri", "a.png"))
+            .action(Action::call_decline("Decline", "decline"));
+        assert_eq!(
+            toast.validation_issues(),
+            [ValidationIssue::CallButtonIcon("Accept".to_string())]
+        );
+        toast.scenario(Scenario::Reminder);
+        assert!(toast.validation_issues().is_empty());
+    }
+
+    #[test]
+    fn schema_versions_are_checked_against_the_known_range() {
+        let issues = |version| {
+            let mut toast = Toast::new();
+            toast.text1("Hello").schema_version(version);
+            toast.validation_issues()
+        };
+        let (first, last) = (
+            *limits::SCHEMA_VERSIONS.start(),
+            *limits::SCHEMA_VERSIONS.end(),
+        );
+        assert!(issues(first).is_empty());
+        assert!(issues(last).is_empty());
+        for version in [first - 1, last + 1] {
+            assert_eq!(
+                issues(version),
+                [ValidationIssue::UnknownSchemaVersion(version)]
+            );
+        }
+    }
+
+    #[test]
+    fn payloads_with_markup_are_valid_but_control_characters_are_not() {
+        let payload = r#"<a href="x">&amp; 'quoted'</a>"#;
+        let mut toast = Toast::new();
+        toast
+            .text1("Hello")
+            .launch(payload)
+            .action(Action::new("Open", payload, ""));
+        assert!(toast.validation_issues().is_empty());
+
+        toast
+            .launch("a\0b")
+            .action(Action::new("Open", "tab\tis fine, escape\u{1B} is not", ""));
+        assert_eq!(
+            toast.validation_issues(),
+            [
+                ValidationIssue::IllegalXmlCharacter("launch string", '\0'),
+                ValidationIssue::IllegalXmlCharacter("action arguments", '\u{1B}'),
+            ]
+        );
+    }
+}
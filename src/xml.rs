@@ -0,0 +1,66 @@
+use windows::{
+    core::BSTR,
+    Data::Xml::Dom::XmlDocument,
+    Win32::{
+        Data::Xml::MsXml::{DOMDocument60, IXMLDOMDocument},
+        System::Com::{CoCreateInstance, CLSCTX_INPROC_SERVER},
+    },
+};
+
+use crate::{hs, WinToastError};
+
+/// Create an empty XML document.
+///
+/// A failure comes from the platform rather than from any content, so it is reported as
+/// [`WinToastError::DocumentUnavailable`].
+pub(crate) fn new_document() -> crate::Result<XmlDocument> {
+    XmlDocument::new().map_err(WinToastError::DocumentUnavailable)
+}
+
+/// Load `xml` into a new document.
+///
+/// Returns [`WinToastError::InvalidXml`], with the position of the error, if `xml` can't be parsed.
+pub(crate) fn load_document(xml: &str) -> crate::Result<XmlDocument> {
+    let doc = new_document()?;
+    doc.LoadXml(&hs(xml))
+        .map_err(|source| WinToastError::InvalidXml {
+            source,
+            position: error_position(xml),
+        })?;
+    Ok(doc)
+}
+
+/// The line and position where parsing `xml` fails.
+///
+/// `XmlDocument` only reports what is wrong, so `xml` is parsed again with MSXML,
+/// which `XmlDocument` is built on, to find out where.
+fn error_position(xml: &str) -> Option<(u32, u32)> {
+    unsafe {
+        let doc: IXMLDOMDocument =
+            CoCreateInstance(&DOMDocument60, None, CLSCTX_INPROC_SERVER).ok()?;
+        if doc.loadXML(&BSTR::from(xml)).ok()?.as_bool() {
+            return None;
+        }
+        let error = doc.parseError().ok()?;
+        let line = u32::try_from(error.line().ok()?).ok()?;
+        let position = u32::try_from(error.linepos().ok()?).ok()?;
+        Some((line, position))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invalid_xml_reports_its_position() {
+        let xml = "<toast>\n  <visual>\n</toast>";
+        match load_document(xml) {
+            Err(WinToastError::InvalidXml {
+                position: Some((line, _)),
+                ..
+            }) => assert_eq!(line, 3),
+            other => panic!("unexpected result: {:?}", other.map(|_| ())),
+        }
+    }
+}
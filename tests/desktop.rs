@@ -0,0 +1,173 @@
+//! Checks the crate against the real notification platform, on an interactive desktop session.
+//!
+//! These tests are ignored by default; run them with `cargo test --test desktop -- --ignored`.
+//! Each test registers a throwaway AUM_ID, shows toasts, checks the Action Center history,
+//! and removes the toasts and the registration again, even if a check fails.
+
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use winrt_toast_reborn::{
+    content::{audio::Sound, input::InputType},
+    register, unregister, Action, Audio, HistoryEntry, Input, Result, Toast, ToastManager,
+};
+
+const GROUP: &str = "desktop-check";
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A registered throwaway AUM_ID, whose toasts and registration are removed when dropped.
+struct Desktop {
+    aum_id: String,
+    manager: ToastManager,
+}
+
+impl Desktop {
+    /// Register an AUM_ID for the test `name`, so tests running in parallel don't share
+    /// a history.
+    fn new(name: &str) -> Result<Self> {
+        let aum_id = format!("WinrtToastReborn.DesktopCheck.{}", name);
+        register(&aum_id, "winrt-toast-reborn desktop check", None)?;
+        let desktop = Self {
+            manager: ToastManager::new(&aum_id),
+            aum_id,
+        };
+        desktop.manager.clear()?;
+        Ok(desktop)
+    }
+
+    /// Show a plain and a rich toast in [`GROUP`], and an ungrouped one.
+    fn show_all(&self) -> Result<()> {
+        for toast in [plain(), rich(), other()] {
+            self.manager.show(&toast)?;
+        }
+        self.wait_for("all toasts in the history", |history| {
+            tags(history) == ["other", "plain", "rich"]
+        })
+    }
+
+    /// Poll the history until `done` accepts it, failing after [`TIMEOUT`].
+    fn wait_for(&self, what: &str, done: impl Fn(&[HistoryEntry]) -> bool) -> Result<()> {
+        let start = Instant::now();
+        loop {
+            let history = self.manager.history()?;
+            if done(&history) {
+                return Ok(());
+            }
+            if start.elapsed() > TIMEOUT {
+                panic!(
+                    "timed out waiting for {}, the history is {:#?}",
+                    what, history
+                );
+            }
+            thread::sleep(Duration::from_millis(200));
+        }
+    }
+}
+
+impl Drop for Desktop {
+    fn drop(&mut self) {
+        let _ = self.manager.clear();
+        let _ = unregister(&self.aum_id);
+    }
+}
+
+fn plain() -> Toast {
+    let mut toast = Toast::new();
+    toast
+        .text1("Plain")
+        .text2("Texts only")
+        .tag("plain")
+        .group(GROUP);
+    toast
+}
+
+fn rich() -> Toast {
+    let mut toast = Toast::new();
+    toast
+        .text1("Rich")
+        .text2("Audio, actions and an input")
+        .audio(Audio::new(Sound::Default))
+        .input(Input::new("reply", InputType::Text))
+        .action(Action::new("Send", "send", "").with_input_id("reply"))
+        .action(Action::new("Later", "later", ""))
+        .tag("rich")
+        .group(GROUP);
+    toast
+}
+
+fn other() -> Toast {
+    let mut toast = Toast::new();
+    toast.text1("Ungrouped").tag("other");
+    toast
+}
+
+fn tags(history: &[HistoryEntry]) -> Vec<&str> {
+    let mut tags: Vec<_> = history
+        .iter()
+        .filter_map(|entry| entry.tag.as_deref())
+        .collect();
+    tags.sort_unstable();
+    tags
+}
+
+#[test]
+#[ignore = "needs an interactive desktop session"]
+fn shown_toasts_are_in_the_history() -> Result<()> {
+    let desktop = Desktop::new("History")?;
+    desktop.show_all()?;
+
+    let entry = desktop
+        .manager
+        .history_entry("rich", Some(GROUP))?
+        .expect("rich toast");
+    assert_eq!(entry.texts, ["Rich", "Audio, actions and an input"]);
+    assert!(
+        entry.xml.contains("hint-inputId=\"reply\""),
+        "{}",
+        entry.xml
+    );
+    assert_eq!(desktop.manager.history_in_group(GROUP)?.len(), 2);
+    Ok(())
+}
+
+#[test]
+#[ignore = "needs an interactive desktop session"]
+fn toasts_are_removed_by_tag_and_group() -> Result<()> {
+    let desktop = Desktop::new("Remove")?;
+    desktop.show_all()?;
+
+    desktop.manager.remove_grouped_tag(GROUP, "plain")?;
+    desktop.wait_for("the plain toast removed", |history| {
+        tags(history) == ["other", "rich"]
+    })?;
+
+    desktop.manager.remove_group(GROUP)?;
+    desktop.wait_for("the group removed", |history| tags(history) == ["other"])
+}
+
+#[test]
+#[ignore = "needs an interactive desktop session"]
+fn a_toast_is_removed_by_its_handle() -> Result<()> {
+    let desktop = Desktop::new("Handle")?;
+    let handle = desktop.manager.show(&plain())?;
+    desktop.wait_for("the plain toast shown", |history| {
+        tags(history) == ["plain"]
+    })?;
+
+    handle.remove_from_history()?;
+    desktop.wait_for("the plain toast removed by its handle", |history| {
+        history.is_empty()
+    })
+}
+
+#[test]
+#[ignore = "needs an interactive desktop session"]
+fn the_history_is_cleared() -> Result<()> {
+    let desktop = Desktop::new("Clear")?;
+    desktop.show_all()?;
+
+    desktop.manager.clear()?;
+    desktop.wait_for("the history cleared", |history| history.is_empty())
+}